@@ -0,0 +1,185 @@
+use crate::{error::Error, lexer::line_index::LineIndex};
+use std::fmt::Display;
+
+// renders an error as plain text, with the lines of the source it covers and a caret under each
+// part of them that's in its span. spans count bytes, like the lexer's, but there's a caret per
+// character
+pub fn render_diagnostic<T: Display>(source: &str, name: &str, error: &Error<T>) -> String {
+  render("Error", source, name, error)
+}
+
+// a warning that has a span, rendered the same way as an error
+pub fn render_warning<T: Display>(source: &str, name: &str, warning: &Error<T>) -> String {
+  render("Warning", source, name, warning)
+}
+
+fn render<T: Display>(kind: &str, source: &str, name: &str, error: &Error<T>) -> String {
+  let Error(span, error) = error;
+
+  let lines = source
+    .split('\n')
+    .map(|line| line.strip_suffix('\r').unwrap_or(line))
+    .collect::<Vec<_>>();
+
+  let index = LineIndex::new(source);
+  let position = index.position(span.start);
+  let first = position.line;
+  // the end is exclusive, so a span that stops at the start of a line doesn't cover it
+  let last = if span.end > span.start {
+    index.position(span.end - 1).line.max(first)
+  } else {
+    first
+  };
+
+  let width = last.to_string().len();
+  let gutter = " ".repeat(width);
+
+  let mut output = vec![
+    format!("{}: {}", kind, error),
+    format!("{} --> {}:{}:{}", gutter, name, first, position.column),
+    format!("{} |", gutter),
+  ];
+
+  for (i, line) in lines.iter().enumerate().take(last).skip(first - 1) {
+    // offsets inside a character are moved back to its start
+    let offset = |offset: usize| {
+      let mut offset = offset
+        .saturating_sub(index.line_start(i + 1))
+        .min(line.len());
+      while !line.is_char_boundary(offset) {
+        offset -= 1;
+      }
+      offset
+    };
+    let (start, end) = (offset(span.start), offset(span.end));
+    let length = line[start..end].chars().count();
+    // an empty span still gets a caret, but an empty line in the middle of a longer one doesn't
+    let carets = if first == last { length.max(1) } else { length };
+
+    output.push(format!("{:>width$} | {}", i + 1, line));
+
+    if carets > 0 {
+      // tabs are kept so that the carets line up with the line above
+      let indent = line[..start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+
+      output.push(format!("{} | {}{}", gutter, indent, "^".repeat(carets)));
+    }
+  }
+
+  output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    error::{TypecheckerError, TypecheckerWarning},
+    parser::ast::util::Type,
+  };
+
+  #[test]
+  fn test_render_diagnostic() {
+    let source = "fn main(): () {\n  let x: bool = 12;\n};";
+    let error = Error(
+      32..34,
+      TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::I32,
+      },
+    );
+
+    assert_eq!(
+      render_diagnostic(source, "x.ldc", &error),
+      [
+        "Error: Type i32 does not satisfy expected type bool",
+        "  --> x.ldc:2:17",
+        "  |",
+        "2 |   let x: bool = 12;",
+        "  |                 ^^",
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn test_render_multiline() {
+    let source = "fn f(): i32 {\n  let x = 1;\n\n  'a'\n};\n";
+    let error = Error(
+      12..35,
+      TypecheckerError::<Type<Vec<String>>>::UnresolvedIdentifier("x".to_string()),
+    );
+
+    // the first and last lines are only underlined where the span covers them
+    assert_eq!(
+      render_diagnostic(source, "x.ldc", &error),
+      [
+        "Error: Unresolved identifier x",
+        "  --> x.ldc:1:13",
+        "  |",
+        "1 | fn f(): i32 {",
+        "  |             ^",
+        "2 |   let x = 1;",
+        "  | ^^^^^^^^^^^^",
+        "3 | ",
+        "4 |   'a'",
+        "  | ^^^^^",
+        "5 | };",
+        "  | ^",
+      ]
+      .join("\n")
+    );
+
+    // an empty span at the end of the source points just past it
+    let error = Error(
+      source.len()..source.len(),
+      TypecheckerError::<Type<Vec<String>>>::CannotInfer,
+    );
+    assert!(render_diagnostic(source, "x.ldc", &error).ends_with("6 | \n  | ^"));
+  }
+
+  #[test]
+  fn test_render_warning() {
+    let source = "fn f(): i32 {\n  return 1;\n  2\n};";
+    let warning = Error(28..29, TypecheckerWarning::UnreachableCode { span: None });
+
+    assert_eq!(
+      render_warning(source, "x.ldc", &warning),
+      [
+        "Warning: Unreachable code, since an expression before it never finishes",
+        "  --> x.ldc:3:3",
+        "  |",
+        "3 |   2",
+        "  |   ^",
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn test_render_multibyte() {
+    // `é` is two bytes, but a single column and caret
+    let source = "let é = '→';";
+    let error = Error(
+      9..14,
+      TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::Char,
+      },
+    );
+
+    assert_eq!(
+      render_diagnostic(source, "x.ldc", &error),
+      [
+        "Error: Type char does not satisfy expected type bool",
+        "  --> x.ldc:1:9",
+        "  |",
+        "1 | let é = '→';",
+        "  |         ^^^",
+      ]
+      .join("\n")
+    );
+  }
+}
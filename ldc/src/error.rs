@@ -1,23 +1,14 @@
-use crate::lexer::token::{NumericType, TokenKind};
-use ariadne::{ColorGenerator, Fmt, Label, Report, ReportKind, Source};
-use std::ops::{Range, RangeInclusive};
-
-pub trait Reportable {
-  fn report(&self, span: Range<usize>, name: &'static str) -> Report<'_, (&str, Range<usize>)>;
-}
+use crate::{
+  lexer::token::{NumericType, TokenKind},
+  parser::ast::util::Type,
+};
+use std::{
+  fmt::{Display, Formatter},
+  ops::{Range, RangeInclusive},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Error<T: Reportable>(pub Range<usize>, pub T);
-
-impl<T: Reportable> Error<T> {
-  pub fn print(&self, source: &str, name: &'static str) {
-    self
-      .1
-      .report(self.0.clone(), name)
-      .print((name, Source::from(source)))
-      .unwrap();
-  }
-}
+pub struct Error<T>(pub Range<usize>, pub T);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LexerError {
@@ -25,116 +16,602 @@ pub enum LexerError {
   UnexpectedEof(usize),
   InvalidCodepoint(String),
   InvalidNumericType(String),
+  MissingDigits(String),
+  InvalidSeparator,
+  UnterminatedString,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParserError {
   UnexpectedToken(Option<TokenKind>, Vec<TokenKind>),
   InvalidNumber(String, NumericType),
+  NumberOverflow(String, NumericType),
+  InvalidTupleIndex(String),
+  WildcardExpression,
+  FatArrowExpression,
+  InvalidArrayLength,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TypecheckerError<T> {
-  InvalidType { expected: T, found: T },
-  InvalidArguments { expected: Vec<T>, found: Vec<T> },
+  InvalidType {
+    expected: T,
+    found: T,
+  },
+  InvalidArguments {
+    expected: Vec<T>,
+    found: Vec<T>,
+  },
   UnresolvedIdentifier(String),
+  // the path and the first segment that couldn't be found
+  UnresolvedPath {
+    path: String,
+    segment: String,
+  },
+  NotIterable(T),
+  // an inference variable that nothing solved, like the element type of an empty array that's never
+  // used as anything
+  CannotInfer,
+  CannotInferParameter {
+    name: String,
+  },
+  DuplicateVariant(String),
+  UnresolvedType {
+    name: String,
+  },
+  NotCallable {
+    found: T,
+  },
+  UnknownArgument {
+    name: String,
+  },
+  DuplicateArgument {
+    name: String,
+  },
+  DuplicateDefinition {
+    name: String,
+    kind: &'static str,
+  },
+  UnimplementedTraitMethod {
+    r#trait: String,
+    method: String,
+  },
+  InvalidTypeArguments {
+    name: String,
+    expected: usize,
+    found: usize,
+  },
+  AssignToImmutable {
+    name: String,
+  },
+  InvalidAssignmentTarget,
+  MutableBorrowOfImmutable {
+    name: String,
+  },
+  ImmutableReference,
+  NotDereferenceable {
+    found: T,
+  },
+  NonConstantValue {
+    name: String,
+  },
+  UnsatisfiedBound {
+    type_arg: T,
+    trait_name: String,
+  },
+  TupleIndexOutOfBounds {
+    found: T,
+    index: usize,
+  },
+  UnknownField {
+    struct_name: String,
+    field: String,
+  },
+  DuplicateField {
+    struct_name: String,
+    field: String,
+  },
+  MissingFields {
+    struct_name: String,
+    fields: Vec<String>,
+  },
+  CannotInferTypeArgument {
+    name: String,
+  },
+  PrivateField {
+    struct_name: String,
+    field: String,
+  },
+  PrivateItem {
+    name: String,
+  },
+  BreakOutsideLoop,
+  InvalidCast {
+    from: T,
+    to: T,
+  },
+  InvalidOperands {
+    operator: String,
+    left: T,
+    right: T,
+  },
+  InvalidOperand {
+    operator: String,
+    found: T,
+  },
+  NotIndexable {
+    found: T,
+  },
+  InvalidIndex {
+    found: T,
+  },
+  NegativeIndex,
+  InvalidTuplePattern {
+    elements: usize,
+    found: T,
+  },
+  RefutablePattern,
+  InvalidVariantPattern {
+    variant: String,
+    expected: usize,
+    found: usize,
+  },
+  NonExhaustiveMatch {
+    missing: Vec<String>,
+  },
+  LiteralOutOfRange {
+    value: i128,
+    ty: T,
+  },
+  NamedVariantFields {
+    variant: String,
+  },
+  // the left side of `??`, which has to be a union with unit
+  NotNullable(T),
+  // a `break` or `continue` to a label that no enclosing loop in the function has
+  UndefinedLabel {
+    label: String,
+  },
+  // `?` on something other than a result, which is an enum of `Ok(T)` and `Err(E)`
+  NotTryable(T),
+  // `?` in a function that doesn't return a result, or doesn't declare what it returns
+  TryOutsideResult {
+    found: Option<T>,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypecheckerWarning {
+  UnusedVariable(String),
+  DivisionByZero,
+  UnreachableArm,
+  // the expressions after one that never produces a value, if they were parsed with spans
+  UnreachableCode { span: Option<Range<usize>> },
+}
+
+// any error from lexing, parsing or typechecking a source string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError<T> {
+  Lexer(Error<LexerError>),
+  Parser(Error<ParserError>),
+  Typechecker(Vec<Error<TypecheckerError<T>>>),
+}
+
+impl<T> From<Error<LexerError>> for CompileError<T> {
+  fn from(error: Error<LexerError>) -> Self {
+    CompileError::Lexer(error)
+  }
+}
+
+impl<T> From<Error<ParserError>> for CompileError<T> {
+  fn from(error: Error<ParserError>) -> Self {
+    CompileError::Parser(error)
+  }
 }
 
-impl Reportable for LexerError {
-  fn report(
-    &self,
-    span: Range<usize>,
-    name: &'static str,
-  ) -> ariadne::Report<'_, (&str, std::ops::Range<usize>)> {
-    let mut colors = ColorGenerator::new();
-    colors.next();
-    let b = colors.next();
-
-    let builder = Report::build(ReportKind::Error, &*name, span.start).with_label(
-      Label::new((&*name, span.clone()))
-        .with_message(match self {
-          LexerError::UnexpectedCharacter(c, ranges, expected) => {
-            let items = vec![
-              expected
-                .iter()
-                .map(|c| c.fg(b).to_string())
-                .collect::<Vec<_>>(),
-              ranges
-                .iter()
-                .map(|r| format!("{}-{}", r.start().fg(b), r.end().fg(b)))
-                .collect::<Vec<_>>(),
-            ]
-            .concat();
-
-            format!(
-              "Unexpected character {}{}",
-              c.fg(b),
-              if items.is_empty() {
-                "".to_string()
-              } else {
-                format!(", expected {}", items.join(", "))
-              }
-            )
+impl<T> From<Vec<Error<TypecheckerError<T>>>> for CompileError<T> {
+  fn from(errors: Vec<Error<TypecheckerError<T>>>) -> Self {
+    CompileError::Typechecker(errors)
+  }
+}
+
+impl LexerError {
+  fn message(&self) -> String {
+    match self {
+      LexerError::UnexpectedCharacter(c, ranges, expected) => {
+        let items = [
+          expected.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+          ranges
+            .iter()
+            .map(|r| format!("{}-{}", r.start(), r.end()))
+            .collect::<Vec<_>>(),
+        ]
+        .concat();
+
+        format!(
+          "Unexpected character {}{}",
+          c,
+          if items.is_empty() {
+            "".to_string()
+          } else {
+            format!(", expected {}", items.join(", "))
           }
-          LexerError::UnexpectedEof(_) => "Unexpected end of input".to_string(),
-          LexerError::InvalidCodepoint(codepoint) => {
-            format!("Invalid code point 0x{}", codepoint)
+        )
+      }
+      LexerError::UnexpectedEof(_) => "Unexpected end of input".to_string(),
+      LexerError::InvalidCodepoint(codepoint) => {
+        format!("Invalid code point 0x{}", codepoint)
+      }
+      LexerError::InvalidNumericType(ty) => format!("Invalid numeric type {}", ty),
+      LexerError::MissingDigits(prefix) => format!("Expected digits after {}", prefix),
+      LexerError::InvalidSeparator => "Digit separators must be between two digits".to_string(),
+      LexerError::UnterminatedString => "Unterminated string".to_string(),
+    }
+  }
+}
+
+impl ParserError {
+  fn message(&self) -> String {
+    match self {
+      ParserError::UnexpectedToken(token, expected) => {
+        let items = expected.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        format!(
+          "Unexpected {}{}",
+          match token {
+            None | Some(TokenKind::Eof) => "end of input".to_string(),
+            Some(t) => t.to_string(),
+          },
+          if items.is_empty() {
+            "".to_string()
+          } else {
+            format!(", expected {}", items.join(", "))
           }
-          LexerError::InvalidNumericType(ty) => format!("Invalid numeric type {}", ty),
-        })
-        .with_color(b),
-    );
+        )
+      }
+      ParserError::InvalidNumber(s, ty) => format!("Invalid number {} for type {}", s, ty),
+      ParserError::NumberOverflow(s, ty) => {
+        format!("Number {} is out of range for type {}", s, ty)
+      }
+      ParserError::InvalidTupleIndex(s) => format!("Invalid tuple index {}", s),
+      ParserError::WildcardExpression => "_ can only be used in patterns".to_string(),
+      ParserError::FatArrowExpression => {
+        "=> can only follow the pattern of a match arm".to_string()
+      }
+      ParserError::InvalidArrayLength => {
+        "Array lengths must be constant, non-negative integers".to_string()
+      }
+    }
+  }
+}
+
+impl Display for ParserError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.message())
+  }
+}
+
+impl Display for LexerError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.message())
+  }
+}
+
+impl Display for TypecheckerWarning {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TypecheckerWarning::UnusedVariable(name) => write!(f, "Unused variable {}", name),
+      TypecheckerWarning::DivisionByZero => write!(f, "Division by zero"),
+      TypecheckerWarning::UnreachableArm => {
+        write!(f, "Match arm is unreachable, since earlier arms cover it")
+      }
+      TypecheckerWarning::UnreachableCode { .. } => write!(
+        f,
+        "Unreachable code, since an expression before it never finishes"
+      ),
+    }
+  }
+}
 
+impl Display for TypecheckerError<Type<Vec<String>>> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     match self {
-      LexerError::UnexpectedEof(start) => builder
-        .with_label(
-          Label::new((&*name, *start..*start))
-            .with_message("Token began here")
-            .with_color(b),
+      // nested types only point at the part that's off
+      TypecheckerError::InvalidType { expected, found } => match found.mismatch(expected) {
+        (path, _, _) if path.is_empty() => write!(
+          f,
+          "Type {} does not satisfy expected type {}",
+          found, expected
+        ),
+        (path, found, expected) => {
+          let path = path.into_iter().rev().collect::<Vec<_>>().join(" of the ");
+          write!(
+            f,
+            "Expected the {} to be {}, found {}",
+            path, expected, found
+          )
+        }
+      },
+      TypecheckerError::InvalidArguments { expected, found } => write!(
+        f,
+        "Invalid arguments: expected {}, found {}",
+        expected
+          .iter()
+          .map(ToString::to_string)
+          .collect::<Vec<String>>()
+          .join(", "),
+        found
+          .iter()
+          .map(ToString::to_string)
+          .collect::<Vec<String>>()
+          .join(", ")
+      ),
+      TypecheckerError::UnresolvedIdentifier(name) => {
+        write!(f, "Unresolved identifier {}", name)
+      }
+      TypecheckerError::UnresolvedPath { path, segment } => {
+        write!(f, "Unresolved path {}: {} not found", path, segment)
+      }
+      TypecheckerError::NotIterable(ty) => {
+        write!(f, "Type {} is not iterable", ty)
+      }
+      TypecheckerError::CannotInfer => {
+        write!(f, "Cannot infer a type, which needs an annotation")
+      }
+      TypecheckerError::CannotInferParameter { name } => {
+        write!(
+          f,
+          "Cannot infer the type of closure parameter {}, which needs an annotation",
+          name
+        )
+      }
+      TypecheckerError::DuplicateVariant(name) => {
+        write!(f, "Duplicate variant {}", name)
+      }
+      TypecheckerError::UnresolvedType { name } => {
+        write!(f, "Unresolved type {}", name)
+      }
+      TypecheckerError::NotCallable { found } => {
+        write!(f, "Type {} is not callable", found)
+      }
+      TypecheckerError::UnknownArgument { name } => {
+        write!(f, "Unknown argument {}", name)
+      }
+      TypecheckerError::DuplicateArgument { name } => {
+        write!(f, "Argument {} is given more than once", name)
+      }
+      TypecheckerError::DuplicateDefinition { name, kind } => {
+        write!(f, "Duplicate definition of {} {}", kind, name)
+      }
+      TypecheckerError::UnimplementedTraitMethod { r#trait, method } => {
+        write!(
+          f,
+          "Method {} of trait {} is not implemented",
+          method, r#trait
+        )
+      }
+      TypecheckerError::InvalidTypeArguments {
+        name,
+        expected,
+        found,
+      } => write!(
+        f,
+        "Type {} takes {} type arguments, found {}",
+        name, expected, found
+      ),
+      TypecheckerError::AssignToImmutable { name } => {
+        write!(
+          f,
+          "Cannot assign to {}, which isn't declared with let mut",
+          name
+        )
+      }
+      TypecheckerError::MutableBorrowOfImmutable { name } => {
+        write!(
+          f,
+          "Cannot borrow {} mutably, since it isn't declared with let mut",
+          name
+        )
+      }
+      TypecheckerError::ImmutableReference => {
+        write!(f, "Cannot change what an immutable reference points to")
+      }
+      TypecheckerError::NotDereferenceable { found } => {
+        write!(
+          f,
+          "Type {} is not a reference, so it can't be dereferenced",
+          found
         )
-        .finish(),
-      _ => builder.finish(),
+      }
+      TypecheckerError::InvalidAssignmentTarget => {
+        write!(f,
+          "Only variables, their elements and fields, and what references point to can be assigned to"
+        )
+      }
+      TypecheckerError::NonConstantValue { name } => {
+        write!(
+          f,
+          "The value of constant {} is not known at compile time",
+          name
+        )
+      }
+      TypecheckerError::UnsatisfiedBound {
+        type_arg,
+        trait_name,
+      } => write!(
+        f,
+        "Type {} does not implement trait {}",
+        type_arg, trait_name
+      ),
+      TypecheckerError::TupleIndexOutOfBounds { found, index } => {
+        write!(f, "Type {} has no element {}", found, index)
+      }
+      TypecheckerError::UnknownField { struct_name, field } => {
+        write!(f, "Type {} has no field {}", struct_name, field)
+      }
+      TypecheckerError::DuplicateField { struct_name, field } => {
+        write!(
+          f,
+          "Field {} of {} is given more than once",
+          field, struct_name
+        )
+      }
+      TypecheckerError::MissingFields {
+        struct_name,
+        fields,
+      } => {
+        write!(
+          f,
+          "Missing fields of {}: {}",
+          struct_name,
+          fields.join(", ")
+        )
+      }
+      TypecheckerError::CannotInferTypeArgument { name } => {
+        write!(f, "Cannot infer type argument {}", name)
+      }
+      TypecheckerError::PrivateField { struct_name, field } => {
+        write!(f, "Field {} of type {} is private", field, struct_name)
+      }
+      TypecheckerError::PrivateItem { name } => {
+        write!(f, "{} is private", name)
+      }
+      TypecheckerError::BreakOutsideLoop => {
+        write!(f, "Cannot break or continue outside of a loop")
+      }
+      TypecheckerError::InvalidCast { from, to } => {
+        write!(f, "Cannot cast {} to {}", from, to)
+      }
+      TypecheckerError::InvalidOperands {
+        operator,
+        left,
+        right,
+      } => write!(
+        f,
+        "Operator {} cannot be applied to {} and {}",
+        operator, left, right
+      ),
+      TypecheckerError::InvalidOperand { operator, found } => {
+        write!(f, "Operator {} cannot be applied to {}", operator, found)
+      }
+      TypecheckerError::NotIndexable { found } => {
+        write!(f, "Type {} cannot be indexed", found)
+      }
+      TypecheckerError::InvalidIndex { found } => {
+        write!(
+          f,
+          "Type {} is not an integer, so it cannot be an index",
+          found
+        )
+      }
+      TypecheckerError::NegativeIndex => {
+        write!(f, "Arrays cannot be indexed with a negative number")
+      }
+      TypecheckerError::InvalidTuplePattern { elements, found } => write!(
+        f,
+        "A pattern with {} elements cannot match type {}",
+        elements, found
+      ),
+      TypecheckerError::InvalidVariantPattern {
+        variant,
+        expected,
+        found,
+      } => write!(
+        f,
+        "Variant {} has {} fields, but the pattern has {}",
+        variant, expected, found
+      ),
+      TypecheckerError::NonExhaustiveMatch { missing } => {
+        write!(f, "Match is missing variants {}", missing.join(", "))
+      }
+      TypecheckerError::LiteralOutOfRange { value, ty } => {
+        write!(f, "Literal {} is out of range for type {}", value, ty)
+      }
+      TypecheckerError::NamedVariantFields { variant } => write!(
+        f,
+        "Variant {} has named fields, so it has to be built as {} {{ ... }}",
+        variant, variant
+      ),
+      TypecheckerError::NotNullable(ty) => write!(
+        f,
+        "Type {} can't be unit, so ?? would never use its right side",
+        ty
+      ),
+      TypecheckerError::UndefinedLabel { label } => {
+        write!(f, "No enclosing loop is labeled '{}", label)
+      }
+      TypecheckerError::NotTryable(ty) => {
+        write!(f, "Type {} is not a result, so ? can't be used on it", ty)
+      }
+      TypecheckerError::TryOutsideResult { found: Some(found) } => write!(
+        f,
+        "? can only be used in a function that returns a result, not {}",
+        found
+      ),
+      TypecheckerError::TryOutsideResult { found: None } => write!(
+        f,
+        "? can only be used in a function that declares a result as its return type"
+      ),
+      TypecheckerError::RefutablePattern => {
+        write!(
+          f,
+          "Patterns in let bindings and for loops must match every value"
+        )
+      }
     }
   }
 }
 
-impl Reportable for ParserError {
-  fn report(
-    &self,
-    span: Range<usize>,
-    name: &'static str,
-  ) -> ariadne::Report<'_, (&str, std::ops::Range<usize>)> {
-    let mut colors = ColorGenerator::new();
-    colors.next();
-    let b = colors.next();
-
-    Report::build(ReportKind::Error, &*name, span.start)
-      .with_label(
-        Label::new((&*name, span.clone()))
-          .with_message(match self {
-            ParserError::UnexpectedToken(token, expected) => {
-              let items = expected
-                .iter()
-                .map(|c| c.fg(b).to_string())
-                .collect::<Vec<_>>();
-
-              format!(
-                "Unexpected {}{}",
-                match token {
-                  None | Some(TokenKind::Eof) => "end of input".to_string(),
-                  Some(t) => t.fg(b).to_string(),
-                },
-                if items.is_empty() {
-                  "".to_string()
-                } else {
-                  format!(", expected {}", items.join(", "))
-                }
-              )
-            }
-            ParserError::InvalidNumber(s, ty) => format!("Invalid number {} for type {}", s, ty),
-          })
-          .with_color(b),
-      )
-      .finish()
+impl<T: Display> Display for Error<T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}..{}: {}", self.0.start, self.0.end, self.1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_invalid_type() {
+    let tuple = |types: Vec<Type<Vec<String>>>| Type::Tuple(types);
+    let large = |element| {
+      tuple(vec![
+        Type::I32,
+        Type::Char,
+        tuple(vec![Type::Bool, Type::U8]),
+        Type::Array(Box::new(Type::F64)),
+        element,
+        Type::I64,
+      ])
+    };
+
+    assert_eq!(
+      TypecheckerError::InvalidType {
+        expected: large(Type::I32),
+        found: large(Type::Bool),
+      }
+      .to_string(),
+      "Expected the 5th tuple element to be i32, found bool"
+    );
+
+    assert_eq!(
+      TypecheckerError::InvalidType {
+        expected: Type::Function(vec![Type::Char], Box::new(large(Type::I32))),
+        found: Type::Function(vec![Type::Char], Box::new(large(Type::Char))),
+      }
+      .to_string(),
+      "Expected the 5th tuple element of the return type to be i32, found char"
+    );
+
+    // without a common shape, the whole types are shown
+    assert_eq!(
+      TypecheckerError::InvalidType {
+        expected: tuple(vec![Type::I32, Type::Char]),
+        found: tuple(vec![Type::I32, Type::Char, Type::Bool]),
+      }
+      .to_string(),
+      "Type (i32, char, bool) does not satisfy expected type (i32, char)"
+    );
   }
 }
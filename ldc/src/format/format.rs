@@ -0,0 +1,781 @@
+use crate::parser::ast::{
+  module, operator, r#enum, r#struct, r#trait,
+  util::{
+    self, Argument, Expression, Literal, Modifiers, NumberLiteral, Parameter, Path, Pattern,
+    TypeParameter,
+  },
+};
+
+type Type = util::Type<Vec<String>>;
+
+// prints a module back out as source, so comments are lost but everything the parser keeps survives
+pub struct Formatter {
+  indent: usize,
+}
+
+impl Default for Formatter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Formatter {
+  pub fn new() -> Formatter {
+    Formatter { indent: 0 }
+  }
+
+  pub fn format(&mut self, module: &module::Module<Type>) -> String {
+    let items = module
+      .items
+      .iter()
+      .map(|item| self.item(item))
+      .collect::<Vec<_>>();
+
+    format!("{}\n", items.join("\n\n"))
+  }
+
+  fn indentation(&self) -> String {
+    "  ".repeat(self.indent)
+  }
+
+  // formats `items` one per line inside braces, or `{}` if there are none
+  fn body(&mut self, items: Vec<String>) -> String {
+    if items.is_empty() {
+      return "{}".to_string();
+    }
+
+    let mut result = "{\n".to_string();
+
+    for item in items {
+      result.push_str(&item);
+      result.push('\n');
+    }
+
+    result.push_str(&self.indentation());
+    result.push('}');
+    result
+  }
+
+  fn item(&mut self, item: &module::Item<Type>) -> String {
+    let indentation = self.indentation();
+    let mut result = String::new();
+
+    for doc in &item.docs {
+      result.push_str(&format!("{}///{}\n", indentation, doc));
+    }
+
+    result.push_str(&indentation);
+    result.push_str(&modifiers(&item.modifiers));
+
+    result.push_str(&match &item.kind {
+      module::ItemKind::Function(function) => format!(
+        "fn {}({}){} {}",
+        function.header.name,
+        self.parameters(&function.header.parameters),
+        match &function.header.ty {
+          Some(ty) => format!(": {}", self::ty(ty)),
+          None => String::new(),
+        },
+        self.expression(&function.body)
+      ),
+      module::ItemKind::Const { name, ty, value } => format!(
+        "const {}: {} = {}",
+        name,
+        self::ty(ty),
+        self.expression(value)
+      ),
+      module::ItemKind::Field { name, ty, default } => match default {
+        Some(default) => format!("{}: {} = {}", name, self::ty(ty), self.expression(default)),
+        None => format!("{}: {}", name, self::ty(ty)),
+      },
+      module::ItemKind::Struct(r#struct) => self.r#struct(r#struct),
+      module::ItemKind::Impl { ty, module } => {
+        let items = self.items(module);
+        format!("impl {} {}", self::ty(ty), self.body(items))
+      }
+      module::ItemKind::Enum(r#enum) => self::r#enum(r#enum),
+      module::ItemKind::Trait(r#trait) => self.r#trait(r#trait),
+      module::ItemKind::Operator(operator) => {
+        let header = match &operator.header {
+          operator::Header::Prefix(operator::Prefix {
+            operator,
+            operand,
+            result,
+          }) => format!(
+            "{} ({}): {}",
+            operator,
+            self.parameters([operand]),
+            ty(result)
+          ),
+          operator::Header::Infix(operator::Infix {
+            operator,
+            operands: (a, b),
+            result,
+          }) => format!("{} ({}): {}", operator, self.parameters([a, b]), ty(result)),
+        };
+
+        format!("{} {}", header, self.expression(&operator.body))
+      }
+    });
+
+    result.push(';');
+    result
+  }
+
+  // the items of a struct or impl block, one level further in
+  fn items(&mut self, module: &module::Module<Type>) -> Vec<String> {
+    self.indent += 1;
+    let items = module.items.iter().map(|item| self.item(item)).collect();
+    self.indent -= 1;
+    items
+  }
+
+  fn r#struct(&mut self, r#struct: &r#struct::Struct<Type>) -> String {
+    let items = self.items(&r#struct.module);
+
+    format!(
+      "struct {}{}{} {}",
+      r#struct.header.name,
+      type_parameters(&r#struct.header.type_parameters),
+      traits(&r#struct.header.traits),
+      self.body(items)
+    )
+  }
+
+  fn r#trait(&mut self, r#trait: &r#trait::Trait<Type>) -> String {
+    self.indent += 1;
+    let items = r#trait
+      .items
+      .iter()
+      .map(|(modifiers, item)| {
+        format!(
+          "{}{}{};",
+          self.indentation(),
+          self::modifiers(modifiers),
+          self.trait_item(item)
+        )
+      })
+      .collect();
+    self.indent -= 1;
+
+    format!(
+      "trait {}{} {}",
+      r#trait.header.name,
+      traits(&r#trait.header.traits),
+      self.body(items)
+    )
+  }
+
+  fn trait_item(&mut self, item: &r#trait::Item<Type>) -> String {
+    match item {
+      r#trait::Item::Function(function) => format!(
+        "fn {}({}): {}",
+        function.name,
+        types(&function.parameters),
+        ty(&function.ty)
+      ),
+      r#trait::Item::Struct(r#struct) => {
+        format!("struct {}{}", r#struct.name, traits(&r#struct.traits))
+      }
+      r#trait::Item::Enum(r#enum) => format!("enum {}", r#enum.name),
+      r#trait::Item::Trait(r#trait) => format!("trait {}{}", r#trait.name, traits(&r#trait.traits)),
+      r#trait::Item::Child(r#trait) => self.r#trait(r#trait),
+      r#trait::Item::Operator(r#trait::Operator::Prefix {
+        operator,
+        operand,
+        result,
+      }) => format!("{} ({}): {}", operator, ty(operand), ty(result)),
+      r#trait::Item::Operator(r#trait::Operator::Infix {
+        operator,
+        operands: (a, b),
+        result,
+      }) => format!("{} ({}, {}): {}", operator, ty(a), ty(b), ty(result)),
+    }
+  }
+
+  pub fn expression(&mut self, expression: &Expression<Type>) -> String {
+    match expression {
+      Expression::Block {
+        expressions,
+        has_value,
+      } => {
+        self.indent += 1;
+        let lines = expressions
+          .iter()
+          .enumerate()
+          .map(|(i, expression)| {
+            let terminator = if *has_value && i == expressions.len() - 1 {
+              ""
+            } else {
+              ";"
+            };
+
+            format!(
+              "{}{}{}",
+              self.indentation(),
+              self.expression(expression),
+              terminator
+            )
+          })
+          .collect();
+        self.indent -= 1;
+
+        self.body(lines)
+      }
+      Expression::If {
+        condition,
+        consequence,
+        alternative,
+      } => {
+        let mut result = format!(
+          "if {} {}",
+          self.expression(condition),
+          self.expression(consequence)
+        );
+
+        if let Some(alternative) = alternative {
+          result.push_str(&format!(" else {}", self.expression(alternative)));
+        }
+
+        result
+      }
+      Expression::Loop { label, body } => {
+        format!("{}loop {}", self::label(label), self.expression(body))
+      }
+      Expression::While {
+        label,
+        condition,
+        body,
+      } => format!(
+        "{}while {} {}",
+        self::label(label),
+        self.expression(condition),
+        self.expression(body)
+      ),
+      Expression::For {
+        label,
+        pattern,
+        iterable,
+        body,
+      } => format!(
+        "{}for {} in {} {}",
+        self::label(label),
+        self.pattern(pattern),
+        self.expression(iterable),
+        self.expression(body)
+      ),
+      Expression::Match { scrutinee, arms } => {
+        let scrutinee = self.expression(scrutinee);
+
+        self.indent += 1;
+        let arms = arms
+          .iter()
+          .enumerate()
+          .map(|(i, (pattern, expression))| {
+            format!(
+              "{}{} => {}{}",
+              self.indentation(),
+              self.pattern(pattern),
+              self.expression(expression),
+              if i == arms.len() - 1 { "" } else { "," }
+            )
+          })
+          .collect();
+        self.indent -= 1;
+
+        format!("match {} {}", scrutinee, self.body(arms))
+      }
+      Expression::Return(value) => self.optional_value("return", value),
+      Expression::Break {
+        label: Some(label),
+        value,
+      } => self.optional_value(&format!("break '{}", label), value),
+      Expression::Break { label: None, value } => self.optional_value("break", value),
+      Expression::Continue { label: Some(label) } => format!("continue '{}", label),
+      Expression::Continue { label: None } => "continue".to_string(),
+      Expression::Let {
+        mutable,
+        pattern,
+        ty,
+        value,
+      } => format!(
+        "let {}{}{} = {}",
+        if *mutable { "mut " } else { "" },
+        self.pattern(pattern),
+        match ty {
+          Some(ty) => format!(": {}", self::ty(ty)),
+          None => String::new(),
+        },
+        self.expression(value)
+      ),
+      Expression::Call {
+        expression,
+        arguments,
+      } => format!(
+        "{}({})",
+        self.expression(expression),
+        self.arguments(arguments)
+      ),
+      Expression::Index { expression, index } => format!(
+        "{}[{}]",
+        self.expression(expression),
+        self.expression(index)
+      ),
+      Expression::TupleIndex { expression, index } => {
+        format!("{}.{}", self.expression(expression), index)
+      }
+      Expression::Field { expression, field } => {
+        format!("{}.{}", self.expression(expression), field)
+      }
+      Expression::Cast { value, ty } => format!("{} as {}", self.expression(value), self::ty(ty)),
+      Expression::Try(expression) => format!("{}?", self.expression(expression)),
+      Expression::Range { start, end, kind } => match end.unspanned() {
+        // keep a prefix operator from being lexed as part of the range
+        Expression::Prefix { .. } => format!(
+          "{}{} {}",
+          self.expression(start),
+          kind.operator(),
+          self.expression(end)
+        ),
+        _ => format!(
+          "{}{}{}",
+          self.expression(start),
+          kind.operator(),
+          self.expression(end)
+        ),
+      },
+      Expression::Literal(literal) => self.literal(literal),
+      Expression::Infix {
+        operator,
+        operands: (a, b),
+      } => match operator.as_str() {
+        "::" => format!("{}::{}", self.expression(a), self.expression(b)),
+        _ => format!("{} {} {}", self.expression(a), operator, self.expression(b)),
+      },
+      Expression::Prefix { operator, operand } => match operand.unspanned() {
+        // keep the operators apart so they aren't lexed as one
+        Expression::Prefix { .. } => format!("{} {}", operator, self.expression(operand)),
+        _ if operator == "&mut" => format!("&mut {}", self.expression(operand)),
+        _ => format!("{}{}", operator, self.expression(operand)),
+      },
+      Expression::Identifier(name) => name.to_string(),
+      Expression::Path(path) => path.join("::"),
+      Expression::StructLiteral { path, fields } if fields.is_empty() => {
+        format!("{} {{}}", path.join("::"))
+      }
+      Expression::StructLiteral { path, fields } => format!(
+        "{} {{ {} }}",
+        path.join("::"),
+        fields
+          .iter()
+          .map(|(name, value)| match value.unspanned() {
+            Expression::Identifier(value) if value == name => name.clone(),
+            _ => format!("{}: {}", name, self.expression(value)),
+          })
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+      Expression::Spanned(_, expression) => self.expression(expression),
+    }
+  }
+
+  fn optional_value(&mut self, keyword: &str, value: &Option<Box<Expression<Type>>>) -> String {
+    match value {
+      Some(value) => format!("{} {}", keyword, self.expression(value)),
+      None => keyword.to_string(),
+    }
+  }
+
+  fn parameters<'a>(
+    &mut self,
+    parameters: impl IntoIterator<Item = &'a Parameter<Type>>,
+  ) -> String {
+    parameters
+      .into_iter()
+      .map(|parameter| match &parameter.default {
+        Some(default) => format!(
+          "{}: {} = {}",
+          parameter.name,
+          ty(&parameter.ty),
+          self.expression(default)
+        ),
+        None if parameter.rest => format!("{}: {}...", parameter.name, ty(&parameter.ty)),
+        None => format!("{}: {}", parameter.name, ty(&parameter.ty)),
+      })
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  fn expressions(&mut self, expressions: &[Expression<Type>]) -> String {
+    expressions
+      .iter()
+      .map(|expression| self.expression(expression))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  fn arguments(&mut self, arguments: &[Argument<Type>]) -> String {
+    arguments
+      .iter()
+      .map(|argument| match &argument.name {
+        Some(name) => format!("{}: {}", name, self.expression(&argument.value)),
+        None => self.expression(&argument.value),
+      })
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  fn literal(&mut self, literal: &Literal<Type>) -> String {
+    match literal {
+      Literal::Char(c) => format!("'{}'", escape(*c, '\'')),
+      Literal::String(s) => format!(
+        "\"{}\"",
+        s.chars().map(|c| escape(c, '"')).collect::<String>()
+      ),
+      Literal::Tuple(expressions) => format!("({})", self.expressions(expressions)),
+      Literal::Record(fields) => format!(
+        "{{ {} }}",
+        fields
+          .iter()
+          .map(|(name, value)| format!("{}: {}", name, self.expression(value)))
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+      Literal::Number(number) => self::number(number),
+      Literal::Array(expressions) => format!("[{}]", self.expressions(expressions)),
+      Literal::Bool(value) => value.to_string(),
+      Literal::Closure {
+        parameters,
+        ty,
+        body,
+      } => format!(
+        "fn({}){} {}",
+        parameters
+          .iter()
+          .map(|parameter| match &parameter.ty {
+            Some(ty) => format!("{}: {}", parameter.name, self::ty(ty)),
+            None => parameter.name.clone(),
+          })
+          .collect::<Vec<_>>()
+          .join(", "),
+        match ty {
+          Some(ty) => format!(": {}", self::ty(ty)),
+          None => String::new(),
+        },
+        self.expression(body)
+      ),
+    }
+  }
+
+  fn patterns(&mut self, patterns: &[Pattern<Type>]) -> String {
+    patterns
+      .iter()
+      .map(|pattern| self.pattern(pattern))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  fn pattern(&mut self, pattern: &Pattern<Type>) -> String {
+    match pattern {
+      Pattern::Wildcard => "_".to_string(),
+      Pattern::Identifier(name) => name.to_string(),
+      Pattern::Literal(literal) => self.literal(literal),
+      Pattern::Tuple(patterns) => format!("({})", self.patterns(patterns)),
+      Pattern::Variant { path, fields } if fields.is_empty() => path.join("::"),
+      Pattern::Variant { path, fields } => {
+        format!("{}({})", path.join("::"), self.patterns(fields))
+      }
+      Pattern::Struct { path, fields } => {
+        let fields = fields
+          .iter()
+          .map(|(name, pattern)| match pattern {
+            Pattern::Identifier(binding) if binding == name => name.to_string(),
+            pattern => format!("{}: {}", name, self.pattern(pattern)),
+          })
+          .collect::<Vec<_>>();
+
+        if fields.is_empty() {
+          format!("{} {{}}", path.join("::"))
+        } else {
+          format!("{} {{ {} }}", path.join("::"), fields.join(", "))
+        }
+      }
+    }
+  }
+}
+
+fn r#enum(r#enum: &r#enum::Enum<Type>) -> String {
+  let variants = r#enum
+    .variants
+    .iter()
+    .map(
+      |variant| match (variant.fields.is_empty(), variant.names.is_empty()) {
+        (true, _) => variant.name.to_string(),
+        (false, true) => format!("{}({})", variant.name, types(&variant.fields)),
+        (false, false) => {
+          let fields = variant
+            .names
+            .iter()
+            .zip(&variant.fields)
+            .map(|(name, field)| format!("{}: {}", name, ty(field)))
+            .collect::<Vec<_>>();
+
+          format!("{} {{ {} }}", variant.name, fields.join(", "))
+        }
+      },
+    )
+    .collect::<Vec<_>>();
+
+  if variants.is_empty() {
+    format!("enum {} {{}}", r#enum.header.name)
+  } else {
+    format!("enum {} {{ {} }}", r#enum.header.name, variants.join(", "))
+  }
+}
+
+fn modifiers(modifiers: &Modifiers) -> String {
+  let mut result = String::new();
+
+  if modifiers.public {
+    result.push_str("pub ");
+  }
+
+  if modifiers.static_ {
+    result.push_str("static ");
+  }
+
+  result
+}
+
+fn type_parameters(parameters: &[TypeParameter]) -> String {
+  if parameters.is_empty() {
+    return String::new();
+  }
+
+  let parameters = parameters
+    .iter()
+    .map(|parameter| {
+      if parameter.traits.is_empty() {
+        parameter.name.to_string()
+      } else {
+        format!(
+          "{}: {}",
+          parameter.name,
+          parameter
+            .traits
+            .iter()
+            .map(|path| path.join("::"))
+            .collect::<Vec<_>>()
+            .join(" + ")
+        )
+      }
+    })
+    .collect::<Vec<_>>();
+
+  format!("<{}>", parameters.join(", "))
+}
+
+fn traits(traits: &[Path]) -> String {
+  if traits.is_empty() {
+    String::new()
+  } else {
+    format!(
+      ": {}",
+      traits
+        .iter()
+        .map(|path| path.join("::"))
+        .collect::<Vec<_>>()
+        .join(", ")
+    )
+  }
+}
+
+fn types(types: &[Type]) -> String {
+  types.iter().map(ty).collect::<Vec<_>>().join(", ")
+}
+
+// the label before a loop, if it has one
+fn label(label: &Option<String>) -> String {
+  match label {
+    Some(label) => format!("'{}: ", label),
+    None => "".to_string(),
+  }
+}
+
+// unlike `Display`, this always produces something the parser accepts
+pub fn ty(ty: &Type) -> String {
+  match ty {
+    Type::Named(path, parameters) if !parameters.is_empty() => {
+      let parameters = types(parameters);
+
+      // `>>` would be lexed as a single operator
+      if parameters.ends_with('>') {
+        format!("{}<{} >", path.join("::"), parameters)
+      } else {
+        format!("{}<{}>", path.join("::"), parameters)
+      }
+    }
+    Type::Named(path, _) => path.join("::"),
+    Type::Function(parameters, return_type) => {
+      format!("fn({}): {}", types(parameters), self::ty(return_type))
+    }
+    Type::Tuple(types) => format!("({})", self::types(types)),
+    Type::Record(fields) if fields.is_empty() => "{}".to_string(),
+    Type::Record(fields) => format!(
+      "{{ {} }}",
+      fields
+        .iter()
+        .map(|(name, ty)| format!("{}: {}", name, self::ty(ty)))
+        .collect::<Vec<_>>()
+        .join(", ")
+    ),
+    Type::Array(ty) => format!("[{}]", self::ty(ty)),
+    Type::FixedArray(ty, length) => format!("[{}; {}]", self::ty(ty), length),
+    Type::Reference(ty, false) => format!("&{}", self::ty(ty)),
+    Type::Reference(ty, true) => format!("&mut {}", self::ty(ty)),
+    Type::Union(types) => types
+      .iter()
+      .map(|ty| match ty {
+        // the return type or referenced type would otherwise swallow the rest of the union
+        Type::Function(..) | Type::Reference(..) => format!("({})", self::ty(ty)),
+        _ => self::ty(ty),
+      })
+      .collect::<Vec<_>>()
+      .join(" | "),
+    _ => ty.to_string(),
+  }
+}
+
+fn number(number: &NumberLiteral<Type>) -> String {
+  match number {
+    NumberLiteral::Integer(n) => n.to_string(),
+    NumberLiteral::I8(n) => format!("{}i8", n),
+    NumberLiteral::I16(n) => format!("{}i16", n),
+    NumberLiteral::I32(n) => format!("{}i32", n),
+    NumberLiteral::I64(n) => format!("{}i64", n),
+    NumberLiteral::I128(n) => format!("{}i128", n),
+    NumberLiteral::U8(n) => format!("{}u8", n),
+    NumberLiteral::U16(n) => format!("{}u16", n),
+    NumberLiteral::U32(n) => format!("{}u32", n),
+    NumberLiteral::U64(n) => format!("{}u64", n),
+    NumberLiteral::U128(n) => format!("{}u128", n),
+    // `Debug` always keeps a `.` or an exponent, so the literal stays a float
+    NumberLiteral::F32(n) => format!("{:?}f32", n),
+    NumberLiteral::F64(n) => format!("{:?}", n),
+    NumberLiteral::_PhantomData(_) => unreachable!(),
+  }
+}
+
+fn escape(c: char, quote: char) -> String {
+  match c {
+    '\n' => "\\n".to_string(),
+    '\r' => "\\r".to_string(),
+    '\t' => "\\t".to_string(),
+    '\0' => "\\0".to_string(),
+    '\\' => "\\\\".to_string(),
+    c if c == quote => format!("\\{}", c),
+    c if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+    c => c.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{lexer::lexer::Lexer, parser::parser::Parser};
+
+  fn parse(source: &str) -> module::Module<Type> {
+    let tokens = Lexer::new(source).lex(false).unwrap();
+    Parser::new(tokens.iter()).parse().unwrap()
+  }
+
+  #[test]
+  fn test_format() {
+    let source = r#"
+      /// a struct
+      /// with two lines of docs
+      pub struct a<T: x + y::z, U>: b, c::d {
+        // dropped
+        static fn new(x: i32, y: [T] = [], z: bool = !x, w: u8...) -> a<T, U> { new(x, z: y) };
+        fn get(): fn(i32): a<a<T, U> > { self };
+        - (x: i32): i32 -x;
+        x: i32;
+        pub y: [T] = [];
+      };
+
+      impl a<T, U> {
+        pub fn set(x: i32): Self self;
+        static fn empty() ();
+      };
+
+      const n: i32 = 2 * 3;
+      enum e { f, g(char, (i32, bool) | fn(): u8), h(e), i { x: i32, y: [e] } };
+      enum empty {};
+
+      trait t: b {
+        fn x(i32, T): T;
+        static struct y: b;
+        enum z;
+        pub trait w: b;
+        trait v { + (i32, i32): i32; };
+        - (i32): i32;
+      };
+
+      fn main() {
+        let x: i32 | f64 = 1 + 2 * (3 - 4) / 5;
+        let y = fn(a: i32): i32 { -!a };
+        let z = fn(a, b: i32) a;
+        if x == 1 { y(x) } else if x { a::new } else { return };
+        loop { break x; };
+        'outer: for i in a { 'inner: while true { if i { break 'outer } else { continue 'inner } } };
+        'a: loop { break 'a 1 };
+        while true { continue };
+        if let e::g(c, _) = x { c } else { 'a' };
+        let w = -y(x)?.z?;
+        while let (a, 1) = y() { y = a };
+        for i in [1, 2u8, 3.0, 4.5e10f32, 'a', '\n', "b\"\\c"] { i; };
+        match x {
+          1 => 'a',
+          "s" => break,
+          true => x.0.1,
+          (a, (b, 'c')) => a,
+          e::g(c, _) => c,
+          e::f => 1,
+          e::i { x, y: _ } => x,
+          i { x: 1 } => 3,
+          f => 2,
+          _ => x.y[0]
+        };
+        {};
+        (x, (y), ());
+        x as u8 + 1;
+        let (p, (q, r)): (i32, (i32, bool)) = (x, (x, true));
+        let z: [[i32; 2]; 1 + 2] = [[1, 2], [3, 4], [5, 6]];
+        let w: [i32; n - 5] = [n];
+        let mut v = 1;
+        v = v + 1;
+        let s = a { x: 1, y };
+        let o: { x: i32, y: bool } = { x: 1, y: { true } };
+        let r: &mut i32 = &mut v;
+        *r = *&x;
+        let q: &&[i32] | (&mut i32) | char = & &mut r;
+        if (a {}).x { b::c { x: s.x } };
+        for i in 0..10 + 1 { i; };
+        0..=-x;
+        - -x
+      };
+    "#;
+
+    let module = parse(source);
+    let formatted = Formatter::new().format(&module);
+
+    assert_eq!(parse(&formatted), module);
+    assert_eq!(Formatter::new().format(&parse(&formatted)), formatted);
+
+    // spans don't show up in the output
+    let tokens = Lexer::new(source).lex(false).unwrap();
+    let mut parser = Parser::new(tokens.iter());
+    parser.spans = true;
+    assert_eq!(Formatter::new().format(&parser.parse().unwrap()), formatted);
+  }
+}
@@ -1,4 +1,7 @@
-use super::token::{NumericType, Token, TokenKind};
+use super::{
+  line_index::{LineIndex, Position},
+  token::{NumericType, Token, TokenKind},
+};
 use crate::{
   error::{Error, LexerError},
   escape, match_operators,
@@ -11,23 +14,43 @@ use std::{
 
 #[derive(Debug, Clone)]
 pub struct Lexer<'a> {
+  pub source: &'a str,
   pub input: Peekable<Chars<'a>>,
   pub start: usize,
   pub end: usize,
+  // the length in bytes of the char `advance` last returned, since offsets count bytes
+  last: usize,
 }
 
 impl<'a> Lexer<'a> {
   pub fn new(input: &'a str) -> Lexer<'a> {
     Lexer {
+      source: input,
       input: input.chars().peekable(),
       start: 0,
       end: 0,
+      last: 0,
     }
   }
 
   pub fn advance(&mut self) -> Option<char> {
-    self.end += 1;
-    self.input.next()
+    let c = self.input.next();
+    self.last = c.map_or(0, char::len_utf8);
+    self.end += self.last;
+    c
+  }
+
+  pub fn peek_nth(&self, n: usize) -> Option<char> {
+    self.input.clone().nth(n)
+  }
+
+  // whether the input after a `'` is an identifier that isn't closed by another `'`, so `'ab'` is
+  // still lexed (and rejected) as a char
+  fn label_follows(&self) -> bool {
+    let mut input = self.input.clone();
+
+    matches!(input.next(), Some('a'..='z' | 'A'..='Z' | '_'))
+      && input.find(|c| !matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_')) != Some('\'')
   }
 
   pub fn reset(&mut self) {
@@ -52,13 +75,13 @@ impl<'a> Lexer<'a> {
 
   pub fn eof(&self) -> Error<LexerError> {
     Error(
-      (self.end - 1)..(self.end - 1),
+      (self.end - self.last)..(self.end - self.last),
       LexerError::UnexpectedEof(self.start),
     )
   }
 
   pub fn last_char(&self) -> Range<usize> {
-    (self.end - 1)..self.end
+    (self.end - self.last)..self.end
   }
 
   pub fn match_until<F>(&mut self, c: char, mut f: F) -> Result<String, Error<LexerError>>
@@ -81,7 +104,7 @@ impl<'a> Lexer<'a> {
     Ok(result)
   }
 
-  pub fn next(&mut self) -> Result<Token, Error<LexerError>> {
+  pub fn next_token(&mut self) -> Result<Token, Error<LexerError>> {
     let result = match self.advance() {
       Some(c) => match c {
         ' ' | '\n' | '\r' | '\t' => Ok(TokenKind::Whitespace(self.match_until(c, |c, _| {
@@ -91,6 +114,42 @@ impl<'a> Lexer<'a> {
           })
         })?)),
 
+        // raw strings have no escapes, and end at a quote followed by as many `#`s as they began with
+        'r' if matches!(self.input.peek(), Some('"' | '#')) => {
+          let start = self.last_char().start;
+          let mut hashes = 0;
+
+          while let Some('#') = self.input.peek() {
+            self.advance();
+            hashes += 1;
+          }
+
+          match self.advance() {
+            Some('"') => (),
+            Some(c) => Err(self.unexpected_character(c, &[], &['"', '#']))?,
+            None => Err(self.eof())?,
+          }
+
+          let unterminated = Error(start..self.end, LexerError::UnterminatedString);
+          let mut string = String::new();
+
+          loop {
+            match self.advance() {
+              Some('"') if (0..hashes).all(|n| self.peek_nth(n) == Some('#')) => {
+                for _ in 0..hashes {
+                  self.advance();
+                }
+
+                break;
+              }
+              Some(c) => string.push(c),
+              None => Err(unterminated.clone())?,
+            }
+          }
+
+          Ok(TokenKind::StringLiteral(string))
+        }
+
         'a'..='z' | 'A'..='Z' | '_' => Ok(TokenKind::from_identifier(self.match_until(
           c,
           |c, _| {
@@ -101,20 +160,54 @@ impl<'a> Lexer<'a> {
           },
         )?)),
 
+        // strings may span several lines, and keep their line breaks and indentation as written. a
+        // backslash at the end of a line drops the line break and the next line's indentation
         '"' => {
           let mut string = String::new();
+          let unterminated = Error(self.last_char(), LexerError::UnterminatedString);
 
+          // the closing quote is checked before decoding so that `\"` doesn't end the string
           loop {
-            string.push(match self.lex_char()? {
-              '"' => break,
-              c => c,
-            });
+            match self.input.peek().copied() {
+              Some('"') => {
+                self.advance();
+                break;
+              }
+              None => Err(unterminated.clone())?,
+              Some('\\') if matches!(self.peek_nth(1), Some('\n' | '\r')) => {
+                self.advance();
+
+                while let Some(' ' | '\n' | '\r' | '\t') = self.input.peek() {
+                  self.advance();
+                }
+              }
+              _ => string.push(self.lex_char().map_err(|error| match error.1 {
+                LexerError::UnexpectedEof(_) => unterminated.clone(),
+                _ => error,
+              })?),
+            }
           }
 
           Ok(TokenKind::StringLiteral(string))
         }
 
+        // `'a'` is a char, but `'a` without the closing quote is a label
+        '\'' if self.label_follows() => {
+          let mut label = String::new();
+
+          while let Some(&c @ ('a'..='z' | 'A'..='Z' | '0'..='9' | '_')) = self.input.peek() {
+            label.push(c);
+            self.advance();
+          }
+
+          Ok(TokenKind::Label(label))
+        }
         '\'' => {
+          if let Some('\'') = self.input.peek() {
+            self.advance();
+            Err(self.unexpected_character('\'', &[], &[]))?
+          }
+
           let c = self.lex_char()?;
           match self.advance() {
             Some('\'') => Ok(TokenKind::CharLiteral(c)),
@@ -127,30 +220,74 @@ impl<'a> Lexer<'a> {
           let mut number = String::new();
           let mut is_float = false;
 
-          number.push_str(&self.match_until(c, |c, _| {
-            Ok(match c {
-              '0'..='9' => Some(c.to_string()),
-              '.' => {
-                if is_float {
-                  None
-                } else {
+          match (c, self.input.peek().copied()) {
+            ('0', Some(prefix @ ('x' | 'o' | 'b'))) => {
+              self.advance();
+              number.push(c);
+              number.push(prefix);
+
+              let radix = match prefix {
+                'x' => 16,
+                'o' => 8,
+                _ => 2,
+              };
+
+              self.lex_digits(&mut number, radix, false)?;
+
+              if number.len() == 2 {
+                Err(Error(
+                  self.span(),
+                  LexerError::MissingDigits(number.clone()),
+                ))?
+              }
+            }
+            _ => {
+              number.push(c);
+              self.lex_digits(&mut number, 10, true)?;
+
+              // a `.` continues the number unless it begins a range (`1..2`) or a member access
+              // (`1.x`), so `1.` is a float but `.5` is an operator followed by a number
+              if self.peek_nth(0) == Some('.')
+                && !matches!(self.peek_nth(1), Some(c) if c == '.' || c == '_' || c.is_alphabetic())
+              {
+                self.advance();
+                number.push('.');
+                is_float = true;
+
+                self.lex_digits(&mut number, 10, false)?;
+              }
+
+              if let Some('e' | 'E') = self.peek_nth(0) {
+                let digit = match self.peek_nth(1) {
+                  Some('+' | '-') => self.peek_nth(2),
+                  c => c,
+                };
+
+                if digit.is_some_and(|c| c.is_ascii_digit()) {
+                  number.push(self.advance().unwrap());
+
+                  if let Some(sign @ ('+' | '-')) = self.peek_nth(0) {
+                    self.advance();
+                    number.push(sign);
+                  }
+
                   is_float = true;
-                  Some(c.to_string())
+                  self.lex_digits(&mut number, 10, false)?;
                 }
               }
-              _ => None,
-            })
-          })?);
+            }
+          }
 
           let mut number_type = if is_float {
             NumericType::F64
           } else {
-            NumericType::I32
+            NumericType::Integer
           };
 
           match self.input.peek() {
             Some(&c) if c.is_alphabetic() => {
               self.advance();
+              let start = self.last_char().start;
 
               let mut number_type_str = String::new();
               number_type_str.push_str(&self.match_until(c, |c, _| {
@@ -175,7 +312,7 @@ impl<'a> Lexer<'a> {
                 "f32" => NumericType::F32,
                 "f64" => NumericType::F64,
                 _ => Err(Error(
-                  self.last_char(),
+                  start..self.end,
                   LexerError::InvalidNumericType(number_type_str),
                 ))?,
               };
@@ -195,11 +332,22 @@ impl<'a> Lexer<'a> {
 
         ',' => Ok(TokenKind::Comma),
         ';' => Ok(TokenKind::Semicolon),
+        '=' if self.input.peek() == Some(&'>') => {
+          self.advance();
+          Ok(TokenKind::FatArrow)
+        }
+        '?' if self.input.peek() != Some(&'?') => Ok(TokenKind::Question),
 
         '/' => match self.input.peek() {
           Some('/') => {
             self.advance();
 
+            // `///` starts a doc comment, but `////` is an ordinary comment
+            let doc = self.input.peek() == Some(&'/') && self.peek_nth(1) != Some('/');
+            if doc {
+              self.advance();
+            }
+
             let mut comment = String::new();
 
             while let Some(&c) = self.input.peek() {
@@ -212,23 +360,36 @@ impl<'a> Lexer<'a> {
               }
             }
 
-            Ok(TokenKind::Comment(comment))
+            Ok(if doc {
+              TokenKind::DocComment(comment)
+            } else {
+              TokenKind::Comment(comment)
+            })
           }
           Some('*') => {
             self.advance();
 
             let mut comment = String::new();
+            // block comments nest, and only the outermost delimiters are dropped
+            let mut depth = 1;
 
             loop {
               match self.advance() {
-                Some('*') => match self.advance() {
-                  Some('/') => break,
-                  Some(c) => {
-                    comment.push('*');
-                    comment.push(c);
+                Some('*') if self.input.peek() == Some(&'/') => {
+                  self.advance();
+                  depth -= 1;
+
+                  if depth == 0 {
+                    break;
                   }
-                  None => Err(self.eof())?,
-                },
+
+                  comment.push_str("*/");
+                }
+                Some('/') if self.input.peek() == Some(&'*') => {
+                  self.advance();
+                  depth += 1;
+                  comment.push_str("/*");
+                }
                 Some(c) => comment.push(c),
                 None => Err(self.eof())?,
               }
@@ -236,6 +397,10 @@ impl<'a> Lexer<'a> {
 
             Ok(TokenKind::Comment(comment))
           }
+          Some('=') => {
+            self.advance();
+            Ok(TokenKind::Operator("/=".to_string()))
+          }
           _ => Ok(TokenKind::Operator("/".to_string())),
         },
 
@@ -245,9 +410,13 @@ impl<'a> Lexer<'a> {
             c,
             Err(self.unexpected_character(c, &[], &[])) =>
 
+            ">>>="
             ">>>"
+            "..."
             "..="
             "..<"
+            "<<="
+            ">>="
 
             "=="
             "!="
@@ -261,6 +430,13 @@ impl<'a> Lexer<'a> {
             ".."
             "??"
             "::"
+            "+="
+            "-="
+            "*="
+            "%="
+            "&="
+            "|="
+            "^="
 
             "<"
             ">"
@@ -275,7 +451,6 @@ impl<'a> Lexer<'a> {
             "&"
             "|"
             "^"
-            "?"
             "~"
           )
         }
@@ -289,6 +464,45 @@ impl<'a> Lexer<'a> {
     Ok((span, result))
   }
 
+  // underscores may only separate two digits, and are dropped from the result
+  pub fn lex_digits(
+    &mut self,
+    number: &mut String,
+    radix: u32,
+    after_digit: bool,
+  ) -> Result<(), Error<LexerError>> {
+    let mut after_digit = after_digit;
+    let mut after_separator = false;
+
+    while let Some(&c) = self.input.peek() {
+      if c.is_digit(radix) {
+        number.push(c);
+        after_digit = true;
+        after_separator = false;
+      } else if c == '_' {
+        self.advance();
+
+        if !after_digit {
+          Err(Error(self.last_char(), LexerError::InvalidSeparator))?
+        }
+
+        after_digit = false;
+        after_separator = true;
+        continue;
+      } else {
+        break;
+      }
+
+      self.advance();
+    }
+
+    if after_separator {
+      Err(Error(self.last_char(), LexerError::InvalidSeparator))?
+    }
+
+    Ok(())
+  }
+
   pub fn lex_char(&mut self) -> Result<char, Error<LexerError>> {
     match self.advance() {
       Some(c) => match c {
@@ -304,48 +518,13 @@ impl<'a> Lexer<'a> {
               '0' => '\0',
               '\\' => '\\',
               '"' => '"',
-              '{' => {
-                let mut code = String::new();
-
-                loop {
-                  match self.advance() {
-                    Some(c) => match c {
-                      '0'..='9' | 'a'..='f' | 'A'..='F' => code.push(c),
-                      '}' => break,
-                      _ => {
-                        Err(self.unexpected_character(
-                          c,
-                          &['0'..='9', 'a'..='f', 'A'..='F'],
-                          &['}'],
-                        ))?
-                      }
-                    },
-                    None => {
-                      Err(self.eof())?
-                    }
-                  }
-                }
-
-                match u32::from_str_radix(&code, 16) {
-                  Ok(codepoint) => {
-                    match std::char::from_u32(codepoint) {
-                      Some(c) => c,
-                      None => {
-                        Err(Error(
-                          self.last_char(),
-                          LexerError::InvalidCodepoint(code),
-                        ))?
-                      }
-                    }
-                  }
-                  Err(_) => {
-                    Err(Error(
-                      self.last_char(),
-                      LexerError::InvalidCodepoint(code),
-                    ))?
-                  }
-                }
-              }
+              '\'' => '\'',
+              'u' => match self.advance() {
+                Some('{') => self.lex_codepoint()?,
+                Some(c) => Err(self.unexpected_character(c, &[], &['{']))?,
+                None => Err(self.eof())?,
+              },
+              '{' => self.lex_codepoint()?,
             )
           }
           None => Err(self.eof()),
@@ -356,34 +535,206 @@ impl<'a> Lexer<'a> {
     }
   }
 
-  pub fn lex(&mut self, emit_ignored: bool) -> Result<Vec<Token>, Error<LexerError>> {
-    let mut tokens = Vec::new();
+  // lexes the hexadecimal code point and closing brace of a `\{...}` or `\u{...}` escape
+  pub fn lex_codepoint(&mut self) -> Result<char, Error<LexerError>> {
+    let start = self.last_char().start;
+    let mut code = String::new();
 
     loop {
-      let (span, kind) = self.next()?;
-
-      if kind == TokenKind::Eof {
-        tokens.push((span.start..span.start, kind));
-        break;
+      match self.advance() {
+        Some(c) => match c {
+          '0'..='9' | 'a'..='f' | 'A'..='F' => code.push(c),
+          '}' => break,
+          _ => Err(self.unexpected_character(c, &['0'..='9', 'a'..='f', 'A'..='F'], &['}']))?,
+        },
+        None => Err(self.eof())?,
       }
+    }
 
-      if let TokenKind::Whitespace(_) | TokenKind::Comment(_) = kind {
-        if !emit_ignored {
-          continue;
-        }
-      }
+    match u32::from_str_radix(&code, 16)
+      .ok()
+      .and_then(std::char::from_u32)
+    {
+      Some(c) => Ok(c),
+      None => Err(Error(start..self.end, LexerError::InvalidCodepoint(code))),
+    }
+  }
+
+  pub fn lex(&mut self, emit_ignored: bool) -> Result<Vec<Token>, Error<LexerError>> {
+    let tokens = self.tokens().collect::<Result<Vec<_>, _>>()?;
+
+    Ok(if emit_ignored {
+      tokens
+    } else {
+      significant_tokens(tokens.into_iter()).collect()
+    })
+  }
+
+  // like `lex`, but with the lines and columns each token covers instead of its offsets
+  pub fn lex_positions(
+    &mut self,
+    emit_ignored: bool,
+  ) -> Result<Vec<(Range<Position>, TokenKind)>, Error<LexerError>> {
+    let index = LineIndex::new(self.source);
 
-      tokens.push((span, kind));
+    Ok(
+      self
+        .lex(emit_ignored)?
+        .into_iter()
+        .map(|token| index.token(token))
+        .collect(),
+    )
+  }
+
+  // lexes lazily, one token per item. whitespace and comments are kept, like `lex(true)`
+  pub fn tokens(&mut self) -> Tokens<'_, 'a> {
+    Tokens {
+      lexer: self,
+      done: false,
+    }
+  }
+}
+
+// ends after `Eof` or the first error, since the lexer can't pick up where an error left it
+#[derive(Debug)]
+pub struct Tokens<'b, 'a> {
+  lexer: &'b mut Lexer<'a>,
+  done: bool,
+}
+
+impl Iterator for Tokens<'_, '_> {
+  type Item = Result<Token, Error<LexerError>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
     }
 
-    Ok(tokens)
+    Some(match self.lexer.next_token() {
+      Ok((span, TokenKind::Eof)) => {
+        self.done = true;
+        Ok((span.start..span.start, TokenKind::Eof))
+      }
+      Ok(token) => Ok(token),
+      Err(error) => {
+        self.done = true;
+        Err(error)
+      }
+    })
   }
 }
 
+// drops whitespace and (non-doc) comments, for when the raw stream has been kept around
+pub fn significant_tokens(tokens: impl Iterator<Item = Token>) -> impl Iterator<Item = Token> {
+  tokens.filter(|(_, kind)| !matches!(kind, TokenKind::Whitespace(_) | TokenKind::Comment(_)))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[test]
+  fn test_tokens() {
+    let source = "fn f(x: i32) { x + 1 }; // done";
+    let mut lexer = Lexer::new(source);
+    let mut tokens = lexer.tokens();
+
+    assert_eq!(tokens.next(), Some(Ok(((0..2), TokenKind::Fn))));
+    assert_eq!(
+      tokens.next(),
+      Some(Ok(((2..3), TokenKind::Whitespace(" ".to_string()))))
+    );
+    assert_eq!(
+      tokens.next(),
+      Some(Ok(((3..4), TokenKind::Identifier("f".to_string()))))
+    );
+
+    let rest = tokens.collect::<Vec<_>>();
+    assert_eq!(rest.last(), Some(&Ok(((31..31), TokenKind::Eof))));
+
+    let mut all = Lexer::new(source)
+      .tokens()
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    assert_eq!(all, Lexer::new(source).lex(true).unwrap());
+
+    all.retain(|(_, kind)| !matches!(kind, TokenKind::Whitespace(_) | TokenKind::Comment(_)));
+    assert_eq!(all, Lexer::new(source).lex(false).unwrap());
+
+    // an error ends the stream, after the tokens before it
+    let mut lexer = Lexer::new("a $ b");
+    let mut tokens = lexer.tokens();
+    assert_eq!(
+      tokens.next(),
+      Some(Ok(((0..1), TokenKind::Identifier("a".to_string()))))
+    );
+    assert!(matches!(
+      tokens.next(),
+      Some(Ok((_, TokenKind::Whitespace(_))))
+    ));
+    assert!(matches!(tokens.next(), Some(Err(_))));
+    assert_eq!(tokens.next(), None);
+  }
+
+  #[test]
+  fn test_lex_positions() {
+    let at = |line, column| Position { line, column };
+
+    assert_eq!(
+      Lexer::new("\"ü\"\n  'é' x").lex_positions(false),
+      Ok(vec![
+        (
+          at(1, 1)..at(1, 4),
+          TokenKind::StringLiteral("ü".to_string())
+        ),
+        (at(2, 3)..at(2, 6), TokenKind::CharLiteral('é')),
+        (at(2, 7)..at(2, 8), TokenKind::Identifier("x".to_string())),
+        (at(2, 8)..at(2, 8), TokenKind::Eof),
+      ])
+    );
+  }
+
+  #[test]
+  fn test_errors() {
+    let error = |source: &str| Lexer::new(source).lex(false).unwrap_err();
+
+    assert_eq!(
+      error("a $"),
+      Error(2..3, LexerError::UnexpectedCharacter('$', &[], &[]))
+    );
+    assert_eq!(error("'1"), Error(2..2, LexerError::UnexpectedEof(0)));
+    // spans count bytes
+    assert_eq!(error("'é"), Error(3..3, LexerError::UnexpectedEof(0)));
+    assert_eq!(
+      error("\"→\" $"),
+      Error(6..7, LexerError::UnexpectedCharacter('$', &[], &[]))
+    );
+    assert_eq!(
+      error("'\\u{110000}'"),
+      Error(3..11, LexerError::InvalidCodepoint("110000".to_string()))
+    );
+    assert_eq!(
+      error("1u7"),
+      Error(1..3, LexerError::InvalidNumericType("u7".to_string()))
+    );
+    assert_eq!(
+      error("0x;"),
+      Error(0..2, LexerError::MissingDigits("0x".to_string()))
+    );
+    assert_eq!(error("1__2"), Error(2..3, LexerError::InvalidSeparator));
+    assert_eq!(error("1_"), Error(1..2, LexerError::InvalidSeparator));
+    assert_eq!(error("\"abc"), Error(0..1, LexerError::UnterminatedString));
+
+    assert_eq!(
+      error("1u7").to_string(),
+      "1..3: Invalid numeric type u7".to_string()
+    );
+    assert_eq!(
+      LexerError::UnexpectedCharacter('q', &['0'..='9'], &['n']).to_string(),
+      "Unexpected character q, expected n, 0-9".to_string()
+    );
+  }
+
   #[test]
   fn test_general() {
     assert_eq!(
@@ -416,6 +767,93 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_operators() {
+    assert_eq!(
+      Lexer::new("a += b /= c <<= d >>= e >= f >>>= g >>> h")
+        .lex(false)
+        .unwrap(),
+      vec![
+        ((0..1), TokenKind::Identifier("a".to_string())),
+        ((2..4), TokenKind::Operator("+=".to_string())),
+        ((5..6), TokenKind::Identifier("b".to_string())),
+        ((7..9), TokenKind::Operator("/=".to_string())),
+        ((10..11), TokenKind::Identifier("c".to_string())),
+        ((12..15), TokenKind::Operator("<<=".to_string())),
+        ((16..17), TokenKind::Identifier("d".to_string())),
+        ((18..21), TokenKind::Operator(">>=".to_string())),
+        ((22..23), TokenKind::Identifier("e".to_string())),
+        ((24..26), TokenKind::Operator(">=".to_string())),
+        ((27..28), TokenKind::Identifier("f".to_string())),
+        ((29..33), TokenKind::Operator(">>>=".to_string())),
+        ((34..35), TokenKind::Identifier("g".to_string())),
+        ((36..39), TokenKind::Operator(">>>".to_string())),
+        ((40..41), TokenKind::Identifier("h".to_string())),
+        ((41..41), TokenKind::Eof)
+      ]
+    );
+
+    // `=>` is its own token, unlike `->`, `==` and `>=`
+    assert_eq!(
+      Lexer::new("a => b -> c ==> d =>= e").lex(false).unwrap(),
+      vec![
+        ((0..1), TokenKind::Identifier("a".to_string())),
+        ((2..4), TokenKind::FatArrow),
+        ((5..6), TokenKind::Identifier("b".to_string())),
+        ((7..9), TokenKind::Operator("->".to_string())),
+        ((10..11), TokenKind::Identifier("c".to_string())),
+        ((12..14), TokenKind::Operator("==".to_string())),
+        ((14..15), TokenKind::Operator(">".to_string())),
+        ((16..17), TokenKind::Identifier("d".to_string())),
+        ((18..20), TokenKind::FatArrow),
+        ((20..21), TokenKind::Operator("=".to_string())),
+        ((22..23), TokenKind::Identifier("e".to_string())),
+        ((23..23), TokenKind::Eof)
+      ]
+    );
+
+    // so is `?`, but `??` is still an operator
+    assert_eq!(
+      Lexer::new("a? ?? b???").lex(false).unwrap(),
+      vec![
+        ((0..1), TokenKind::Identifier("a".to_string())),
+        ((1..2), TokenKind::Question),
+        ((3..5), TokenKind::Operator("??".to_string())),
+        ((6..7), TokenKind::Identifier("b".to_string())),
+        ((7..9), TokenKind::Operator("??".to_string())),
+        ((9..10), TokenKind::Question),
+        ((10..10), TokenKind::Eof)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_labels() {
+    assert_eq!(
+      Lexer::new("'outer: 'a' 'b_2").lex(false).unwrap(),
+      vec![
+        ((0..6), TokenKind::Label("outer".to_string())),
+        ((6..7), TokenKind::Operator(":".to_string())),
+        ((8..11), TokenKind::CharLiteral('a')),
+        ((12..16), TokenKind::Label("b_2".to_string())),
+        ((16..16), TokenKind::Eof)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_bool() {
+    assert_eq!(
+      Lexer::new("true false truer").lex(false).unwrap(),
+      vec![
+        ((0..4), TokenKind::True),
+        ((5..10), TokenKind::False),
+        ((11..16), TokenKind::Identifier("truer".to_string())),
+        ((16..16), TokenKind::Eof)
+      ]
+    );
+  }
+
   #[test]
   fn test_string() {
     assert_eq!(
@@ -431,14 +869,14 @@ mod tests {
 
     assert_eq!(
       Lexer::new(r#""hello, world!\n"#).lex(false).unwrap_err(),
-      Error(16..16, LexerError::UnexpectedEof(0))
+      Error(0..1, LexerError::UnterminatedString)
     );
 
     assert_eq!(
       Lexer::new(r#""hello, world!\x""#).lex(false).unwrap_err(),
       Error(
         15..16,
-        LexerError::UnexpectedCharacter('x', &[], &['n', 'r', 't', '0', '\\', '"', '{'])
+        LexerError::UnexpectedCharacter('x', &[], &['n', 'r', 't', '0', '\\', '"', '\'', 'u', '{'])
       )
     );
 
@@ -452,7 +890,103 @@ mod tests {
 
     assert_eq!(
       Lexer::new(r#""hello, world!\{"#).lex(false).unwrap_err(),
-      Error(16..16, LexerError::UnexpectedEof(0))
+      Error(0..1, LexerError::UnterminatedString)
+    );
+  }
+
+  #[test]
+  fn test_multiline_string() {
+    assert_eq!(
+      Lexer::new("\"a\n  b\"").lex(false).unwrap(),
+      vec![
+        ((0..7), TokenKind::StringLiteral("a\n  b".to_string())),
+        ((7..7), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("\"a \\\n    b\"").lex(false).unwrap(),
+      vec![
+        ((0..11), TokenKind::StringLiteral("a b".to_string())),
+        ((11..11), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("x = \"a\nb").lex(false).unwrap_err(),
+      Error(4..5, LexerError::UnterminatedString)
+    );
+  }
+
+  #[test]
+  fn test_raw_string() {
+    assert_eq!(
+      Lexer::new(r#"r"\n" r"#).lex(false).unwrap(),
+      vec![
+        ((0..5), TokenKind::StringLiteral("\\n".to_string())),
+        ((6..7), TokenKind::Identifier("r".to_string())),
+        ((7..7), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new(r###"r#"a"b"# r##"c"#d"##"###)
+        .lex(false)
+        .unwrap(),
+      vec![
+        ((0..8), TokenKind::StringLiteral("a\"b".to_string())),
+        ((9..20), TokenKind::StringLiteral("c\"#d".to_string())),
+        ((20..20), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new(r##"x r#"a"b"##).lex(false).unwrap_err(),
+      Error(2..5, LexerError::UnterminatedString)
+    );
+
+    assert_eq!(
+      Lexer::new("r#a").lex(false).unwrap_err(),
+      Error(2..3, LexerError::UnexpectedCharacter('a', &[], &['"', '#']))
+    );
+  }
+
+  #[test]
+  fn test_escape() {
+    assert_eq!(
+      Lexer::new(r#"'\n' "a\tb" '\u{1F600}' "\"\'\\\0\r""#)
+        .lex(false)
+        .unwrap(),
+      vec![
+        ((0..4), TokenKind::CharLiteral('\n')),
+        ((5..11), TokenKind::StringLiteral("a\tb".to_string())),
+        ((12..23), TokenKind::CharLiteral('\u{1F600}')),
+        ((24..36), TokenKind::StringLiteral("\"'\\\0\r".to_string())),
+        ((36..36), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new(r#"'\q'"#).lex(false).unwrap_err(),
+      Error(
+        2..3,
+        LexerError::UnexpectedCharacter('q', &[], &['n', 'r', 't', '0', '\\', '"', '\'', 'u', '{'])
+      )
+    );
+
+    assert_eq!(
+      Lexer::new(r#"'ab'"#).lex(false).unwrap_err(),
+      Error(2..3, LexerError::UnexpectedCharacter('b', &[], &['\'']))
+    );
+
+    assert_eq!(
+      Lexer::new(r#"'\u{110000}'"#).lex(false).unwrap_err(),
+      Error(3..11, LexerError::InvalidCodepoint("110000".to_string()))
+    );
+
+    assert_eq!(
+      Lexer::new(r#"'\u41'"#).lex(false).unwrap_err(),
+      Error(3..4, LexerError::UnexpectedCharacter('4', &[], &['{']))
     );
   }
 
@@ -482,7 +1016,7 @@ mod tests {
     assert_eq!(
       Lexer::new(
         r#"/* hello, world!
-\\**\/*/"#
+\\***\*/"#
       )
       .lex(true)
       .unwrap(),
@@ -491,13 +1025,61 @@ mod tests {
           0..25,
           TokenKind::Comment(
             r#" hello, world!
-\\**\/"#
+\\***\"#
               .to_string()
           )
         ),
         (25..25, TokenKind::Eof)
       ]
     );
+
+    // `/*` inside a comment opens a nested one
+    assert_eq!(
+      Lexer::new(
+        r#"/* hello, world!
+\\**\/*/"#
+      )
+      .lex(true)
+      .unwrap_err(),
+      Error(25..25, LexerError::UnexpectedEof(0))
+    );
+
+    assert_eq!(
+      Lexer::new("/* a /* b */ c */").lex(true).unwrap(),
+      vec![
+        (0..17, TokenKind::Comment(" a /* b */ c ".to_string())),
+        (17..17, TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("/* a /* b */ c").lex(true).unwrap_err(),
+      Error(14..14, LexerError::UnexpectedEof(0))
+    );
+
+    assert_eq!(
+      Lexer::new("/// hello\n//// world\nfn").lex(false).unwrap(),
+      vec![
+        (0..9, TokenKind::DocComment(" hello".to_string())),
+        (21..23, TokenKind::Fn),
+        (23..23, TokenKind::Eof)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_significant_tokens() {
+    let tokens = Lexer::new("fn /*x*/ f").lex(true).unwrap();
+    assert_eq!(tokens.len(), 6);
+
+    assert_eq!(
+      significant_tokens(tokens.into_iter()).collect::<Vec<_>>(),
+      vec![
+        (0..2, TokenKind::Fn),
+        (9..10, TokenKind::Identifier("f".to_string())),
+        (10..10, TokenKind::Eof)
+      ]
+    );
   }
 
   #[test]
@@ -507,12 +1089,45 @@ mod tests {
       vec![
         (
           (0..3),
-          TokenKind::NumberLiteral("123".to_string(), NumericType::I32)
+          TokenKind::NumberLiteral("123".to_string(), NumericType::Integer)
         ),
         ((3..3), TokenKind::Eof)
       ]
     );
 
+    assert_eq!(
+      Lexer::new("42").lex(false).unwrap(),
+      vec![
+        (
+          (0..2),
+          TokenKind::NumberLiteral("42".to_string(), NumericType::Integer)
+        ),
+        ((2..2), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("3.14").lex(false).unwrap(),
+      vec![
+        (
+          (0..4),
+          TokenKind::NumberLiteral("3.14".to_string(), NumericType::F64)
+        ),
+        ((4..4), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("0").lex(false).unwrap(),
+      vec![
+        (
+          (0..1),
+          TokenKind::NumberLiteral("0".to_string(), NumericType::Integer)
+        ),
+        ((1..1), TokenKind::Eof)
+      ]
+    );
+
     assert_eq!(
       Lexer::new("123u8").lex(false).unwrap(),
       vec![
@@ -535,6 +1150,151 @@ mod tests {
       ]
     );
 
+    assert_eq!(
+      Lexer::new("0xFF 0o755 0b1010").lex(false).unwrap(),
+      vec![
+        (
+          (0..4),
+          TokenKind::NumberLiteral("0xFF".to_string(), NumericType::Integer)
+        ),
+        (
+          (5..10),
+          TokenKind::NumberLiteral("0o755".to_string(), NumericType::Integer)
+        ),
+        (
+          (11..17),
+          TokenKind::NumberLiteral("0b1010".to_string(), NumericType::Integer)
+        ),
+        ((17..17), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("0xFFu8").lex(false).unwrap(),
+      vec![
+        (
+          (0..6),
+          TokenKind::NumberLiteral("0xFF".to_string(), NumericType::U8)
+        ),
+        ((6..6), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("0x").lex(false).unwrap_err(),
+      Error(0..2, LexerError::MissingDigits("0x".to_string()))
+    );
+
+    assert_eq!(
+      Lexer::new("1_000 0xFF_FF").lex(false).unwrap(),
+      vec![
+        (
+          (0..5),
+          TokenKind::NumberLiteral("1000".to_string(), NumericType::Integer)
+        ),
+        (
+          (6..13),
+          TokenKind::NumberLiteral("0xFFFF".to_string(), NumericType::Integer)
+        ),
+        ((13..13), TokenKind::Eof)
+      ]
+    );
+
+    // a leading underscore makes an identifier, not a number
+    assert_eq!(
+      Lexer::new("_1").lex(false).unwrap(),
+      vec![
+        ((0..2), TokenKind::Identifier("_1".to_string())),
+        ((2..2), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("1__0").lex(false).unwrap_err(),
+      Error(2..3, LexerError::InvalidSeparator)
+    );
+
+    assert_eq!(
+      Lexer::new("1_").lex(false).unwrap_err(),
+      Error(1..2, LexerError::InvalidSeparator)
+    );
+
+    assert_eq!(
+      Lexer::new("0x_FF").lex(false).unwrap_err(),
+      Error(2..3, LexerError::InvalidSeparator)
+    );
+
+    assert_eq!(
+      Lexer::new("1.5 2e10 6.022e23 1e-5 1.").lex(false).unwrap(),
+      vec![
+        (
+          (0..3),
+          TokenKind::NumberLiteral("1.5".to_string(), NumericType::F64)
+        ),
+        (
+          (4..8),
+          TokenKind::NumberLiteral("2e10".to_string(), NumericType::F64)
+        ),
+        (
+          (9..17),
+          TokenKind::NumberLiteral("6.022e23".to_string(), NumericType::F64)
+        ),
+        (
+          (18..22),
+          TokenKind::NumberLiteral("1e-5".to_string(), NumericType::F64)
+        ),
+        (
+          (23..25),
+          TokenKind::NumberLiteral("1.".to_string(), NumericType::F64)
+        ),
+        ((25..25), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("1.2.3").lex(false).unwrap(),
+      vec![
+        (
+          (0..3),
+          TokenKind::NumberLiteral("1.2".to_string(), NumericType::F64)
+        ),
+        ((3..4), TokenKind::Operator(".".to_string())),
+        (
+          (4..5),
+          TokenKind::NumberLiteral("3".to_string(), NumericType::Integer)
+        ),
+        ((5..5), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new(".5").lex(false).unwrap(),
+      vec![
+        ((0..1), TokenKind::Operator(".".to_string())),
+        (
+          (1..2),
+          TokenKind::NumberLiteral("5".to_string(), NumericType::Integer)
+        ),
+        ((2..2), TokenKind::Eof)
+      ]
+    );
+
+    assert_eq!(
+      Lexer::new("1..2").lex(false).unwrap(),
+      vec![
+        (
+          (0..1),
+          TokenKind::NumberLiteral("1".to_string(), NumericType::Integer)
+        ),
+        ((1..3), TokenKind::Operator("..".to_string())),
+        (
+          (3..4),
+          TokenKind::NumberLiteral("2".to_string(), NumericType::Integer)
+        ),
+        ((4..4), TokenKind::Eof)
+      ]
+    );
+
     assert_eq!(
       Lexer::new("123.456c").lex(false).unwrap(),
       vec![
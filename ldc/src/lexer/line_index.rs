@@ -0,0 +1,108 @@
+use super::token::TokenKind;
+use std::ops::Range;
+
+// a line and column, both starting at 1. offsets count bytes, but columns count chars, so a
+// multi-byte character is a single column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+  pub line: usize,
+  pub column: usize,
+}
+
+// converts the offsets in spans to lines and columns, for tools that want those instead. only `\n`
+// ends a line, so the `\r` of a `\r\n` is the last column of its line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+  source: String,
+  // the offset each line starts at
+  starts: Vec<usize>,
+}
+
+impl LineIndex {
+  pub fn new(source: &str) -> LineIndex {
+    let mut starts = vec![0];
+
+    for (offset, c) in source.char_indices() {
+      if c == '\n' {
+        starts.push(offset + 1);
+      }
+    }
+
+    LineIndex {
+      source: source.to_string(),
+      starts,
+    }
+  }
+
+  pub fn lines(&self) -> usize {
+    self.starts.len()
+  }
+
+  // the offset a line starts at
+  pub fn line_start(&self, line: usize) -> usize {
+    self.starts[line - 1]
+  }
+
+  // an offset past the end of the source is on the last line, and one inside a character is in
+  // its column
+  pub fn position(&self, offset: usize) -> Position {
+    let line = self.starts.partition_point(|&start| start <= offset);
+    let start = self.starts[line - 1];
+    let chars = self.source[start..]
+      .char_indices()
+      .take_while(|(i, c)| start + i + c.len_utf8() <= offset)
+      .count();
+
+    Position {
+      line,
+      column: chars + offset.saturating_sub(self.source.len()) + 1,
+    }
+  }
+
+  // the end stays exclusive, so it's the position just after the span
+  pub fn span(&self, span: Range<usize>) -> Range<Position> {
+    self.position(span.start)..self.position(span.end)
+  }
+
+  pub fn token(&self, (span, kind): (Range<usize>, TokenKind)) -> (Range<Position>, TokenKind) {
+    (self.span(span), kind)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn position(line: usize, column: usize) -> Position {
+    Position { line, column }
+  }
+
+  #[test]
+  fn test_line_index() {
+    // `é` and `→` are more than a byte each, and `🦀` is four
+    let source = "let é = 'a';\n\nfn f(): char '→' // 🦀\r\nx";
+    let index = LineIndex::new(source);
+
+    assert_eq!(index.lines(), 4);
+    assert_eq!(index.position(0), position(1, 1));
+    assert_eq!(index.position(4), position(1, 5));
+    assert_eq!(index.position(7), position(1, 7));
+    // an offset inside a character is in its column
+    assert_eq!(index.position(5), position(1, 5));
+    // the newline itself is the last column of its line
+    assert_eq!(index.position(13), position(1, 13));
+    assert_eq!(index.position(14), position(2, 1));
+    assert_eq!(index.position(15), position(3, 1));
+    assert_eq!(index.span(28..33), position(3, 14)..position(3, 17));
+    assert_eq!(index.position(37), position(3, 21));
+    assert_eq!(index.position(41), position(3, 22));
+    assert_eq!(index.position(43), position(4, 1));
+    assert_eq!(index.span(43..44), position(4, 1)..position(4, 2));
+
+    // spans can cover several lines, and the end of the source is just past its last column
+    assert_eq!(index.span(4..44), position(1, 5)..position(4, 2));
+    assert_eq!(index.line_start(3), 15);
+
+    assert_eq!(LineIndex::new("").position(0), position(1, 1));
+  }
+}
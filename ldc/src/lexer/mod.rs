@@ -1,2 +1,3 @@
 pub mod lexer;
+pub mod line_index;
 pub mod token;
@@ -6,9 +6,11 @@ pub type Token = (Range<usize>, TokenKind);
 pub enum TokenKind {
   Whitespace(String),
   Comment(String),
+  DocComment(String), // not ignored, since it documents the next item
   Eof,
 
   Identifier(String),
+  Label(String), // `'outer`, without the apostrophe
   StringLiteral(String),
   CharLiteral(char),
   NumberLiteral(String, NumericType),
@@ -17,14 +19,24 @@ pub enum TokenKind {
   Struct,
   Enum,
   Trait,
+  Impl,
+  Const,
   Let,
+  Mut,
   Pub,
   Self_,
   Static,
   While,
+  For,
+  In,
+  Match,
   If,
   Else,
   Return,
+  Loop,
+  Break,
+  Continue,
+  As,
   Bool,
   True,
   False,
@@ -40,6 +52,8 @@ pub enum TokenKind {
 
   Comma,
   Semicolon,
+  FatArrow, // separates a match arm's pattern from its value, so it's never an operator
+  Question, // `?` after an expression, while `??` is still an operator
 
   Operator(String),
 }
@@ -47,6 +61,7 @@ pub enum TokenKind {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum NumericType {
   Char,
+  Integer, // an integer without a suffix, whose type comes from where it's used
   I8,
   I16,
   I32,
@@ -65,7 +80,7 @@ impl TokenKind {
   pub fn prefix_precedence(&self) -> u8 {
     match self {
       TokenKind::Operator(operator) => match operator.as_str() {
-        "+" | "-" | "~" | "!" => 14,
+        "+" | "-" | "~" | "!" | "&" | "&&" | "*" => 14,
         _ => 0,
       },
       _ => 0,
@@ -90,18 +105,21 @@ impl TokenKind {
         "||" => 3,
         "??" => 2,
         ".." | "..=" | "..<" => 1,
+        // assignments bind the loosest, like anything that isn't an infix operator
         _ => 0,
       },
+      // dart puts casts alongside the relational operators
+      TokenKind::As => 6,
       _ => 0,
     }
   }
 
   pub fn left_associative(&self) -> bool {
     match self {
-      TokenKind::Operator(operator) => match operator.as_str() {
-        "=" => false,
-        _ => true,
-      },
+      TokenKind::Operator(operator) => !matches!(
+        operator.as_str(),
+        "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>=" | ">>>="
+      ),
       _ => false,
     }
   }
@@ -112,14 +130,24 @@ impl TokenKind {
       "struct" => TokenKind::Struct,
       "enum" => TokenKind::Enum,
       "trait" => TokenKind::Trait,
+      "impl" => TokenKind::Impl,
+      "const" => TokenKind::Const,
       "let" => TokenKind::Let,
+      "mut" => TokenKind::Mut,
       "pub" => TokenKind::Pub,
       "self" => TokenKind::Self_,
       "static" => TokenKind::Static,
       "while" => TokenKind::While,
+      "for" => TokenKind::For,
+      "in" => TokenKind::In,
+      "match" => TokenKind::Match,
       "if" => TokenKind::If,
       "else" => TokenKind::Else,
       "return" => TokenKind::Return,
+      "loop" => TokenKind::Loop,
+      "break" => TokenKind::Break,
+      "continue" => TokenKind::Continue,
+      "as" => TokenKind::As,
       "bool" => TokenKind::Bool,
       "true" => TokenKind::True,
       "false" => TokenKind::False,
@@ -152,9 +180,11 @@ impl std::fmt::Display for TokenKind {
       match self {
         TokenKind::Whitespace(_) => "whitespace",
         TokenKind::Comment(_) => "comment",
+        TokenKind::DocComment(_) => "doc comment",
         TokenKind::Eof => "end of input",
 
         TokenKind::Identifier(_) => "identifier",
+        TokenKind::Label(_) => "label",
         TokenKind::StringLiteral(_) => "string literal",
         TokenKind::CharLiteral(_) => "character literal",
         TokenKind::NumberLiteral(_, _) => "number literal",
@@ -163,19 +193,31 @@ impl std::fmt::Display for TokenKind {
         TokenKind::Struct => "struct",
         TokenKind::Enum => "enum",
         TokenKind::Trait => "trait",
+        TokenKind::Impl => "impl",
+        TokenKind::Const => "const",
         TokenKind::Let => "let",
+        TokenKind::Mut => "mut",
         TokenKind::Pub => "pub",
         TokenKind::Self_ => "self",
         TokenKind::Static => "static",
         TokenKind::While => "while",
+        TokenKind::For => "for",
+        TokenKind::In => "in",
+        TokenKind::Match => "match",
         TokenKind::If => "if",
         TokenKind::Else => "else",
         TokenKind::Return => "return",
+        TokenKind::Loop => "loop",
+        TokenKind::Break => "break",
+        TokenKind::Continue => "continue",
+        TokenKind::As => "as",
         TokenKind::Bool => "bool",
         TokenKind::True => "true",
         TokenKind::False => "false",
 
         TokenKind::Numeric(NumericType::Char) => "char",
+        // there's no keyword for it, so it's only ever the type of a literal
+        TokenKind::Numeric(NumericType::Integer) => "integer",
         TokenKind::Numeric(NumericType::I8) => "i8",
         TokenKind::Numeric(NumericType::I16) => "i16",
         TokenKind::Numeric(NumericType::I32) => "i32",
@@ -198,6 +240,8 @@ impl std::fmt::Display for TokenKind {
 
         TokenKind::Comma => ",",
         TokenKind::Semicolon => ";",
+        TokenKind::FatArrow => "=>",
+        TokenKind::Question => "?",
 
         TokenKind::Operator(op) =>
           if op.is_empty() {
@@ -217,6 +261,7 @@ impl std::fmt::Display for NumericType {
       "{}",
       match self {
         NumericType::Char => "char",
+        NumericType::Integer => "integer",
         NumericType::I8 => "i8",
         NumericType::I16 => "i16",
         NumericType::I32 => "i32",
@@ -0,0 +1,9 @@
+#![allow(clippy::module_inception)]
+
+pub mod diagnostic;
+pub mod error;
+pub mod format;
+pub mod lexer;
+pub mod parser;
+pub mod typechecker;
+pub mod util;
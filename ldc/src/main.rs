@@ -1,18 +1,28 @@
-use lexer::lexer::Lexer;
-use parser::parser::Parser;
-use std::process;
-use typechecker::typechecker::Typechecker;
-
-mod error;
-mod lexer;
-mod parser;
-mod typechecker;
-mod util;
+use ldc::{
+  diagnostic::{render_diagnostic, render_warning},
+  error,
+  typechecker::typechecker::Typechecker,
+};
+use std::{env, fs, process};
 
 // TODO: allow top-level variables
 // TODO: fn x() {}; should just be syntactic sugar for let x = fn() {};
 fn main() {
-  let name = "[]";
+  let Some(name) = env::args().nth(1) else {
+    eprintln!("Usage: ldc <file>");
+    process::exit(2);
+  };
+  let name = name.as_str();
+
+  let source = match fs::read_to_string(name) {
+    Ok(source) => source,
+    Err(error) => {
+      eprintln!("Could not read {}: {}", name, error);
+      process::exit(1);
+    }
+  };
+  let source = source.as_str();
+
   /* let source = r#"
     pub enum X {
       Y,
@@ -57,88 +67,36 @@ fn main() {
     };
   "#
     .trim(); */
-  let source = r#"
-pub fn hello(a: a::b, b: c, c: c, x: bool): a::b | (a::b, c) {
-  if x a else hello(a, c, b, x)
-};
-
-pub struct Util {
-  pub fn x(a: i32, b: i32): i32 {
-    choose(a, b, true)
-  };
-
-  pub fn y(a: i32): i32 x(a, a);
-
-  pub static fn negate(x: bool): bool {
-    if x false else true
-  };
-
-  pub static fn choose(a: i32, b: i32, x: bool): i32 {
-    if negate(x) a else b
-  };
-
-  pub struct Helpers {
-    pub fn y() x(1, 2);
-  };
-};
-"#
-  .trim();
+  let mut typechecker = Typechecker::new();
+  let result = typechecker.typecheck_str(source);
+
+  for warning in &typechecker.warnings {
+    match warning {
+      error::TypecheckerWarning::UnreachableCode { span: Some(span) } => println!(
+        "{}",
+        render_warning(source, name, &error::Error(span.clone(), warning))
+      ),
+      warning => println!("Warning: {}", warning),
+    }
+  }
 
-  let mut lexer = Lexer::new(source);
-  match lexer.lex(false) {
-    Ok(tokens) => {
-      for _ in tokens.clone() {
-        // println!("{:?}", token);
+  match result {
+    Ok(_) => {
+      println!("Typechecked successfully")
+    }
+    Err(error::CompileError::Lexer(error)) => {
+      println!("{}", render_diagnostic(source, name, &error));
+      process::exit(1);
+    }
+    Err(error::CompileError::Parser(error)) => {
+      println!("{}", render_diagnostic(source, name, &error));
+      process::exit(1);
+    }
+    Err(error::CompileError::Typechecker(errors)) => {
+      for error in errors {
+        println!("{}", render_diagnostic(source, name, &error));
       }
 
-      let mut parser = Parser::new(tokens.iter().peekable());
-      let ast: Result<
-        parser::ast::module::Module<parser::ast::util::Type<Vec<String>>>,
-        error::Error<error::ParserError>,
-      > = parser.parse();
-
-      match ast {
-        Ok(ast) => {
-          // println!("{:#?}", ast);
-
-          let mut typechecker = Typechecker::new();
-          let result = typechecker.typecheck(ast.clone());
-
-          match result {
-            Ok(_) => {
-              println!("Typechecked successfully")
-            }
-            Err(error) => match error {
-              error::TypecheckerError::InvalidType { expected, found } => {
-                println!("Type {} does not satisfy expected type {}", found, expected)
-              }
-              error::TypecheckerError::InvalidArguments { expected, found } => println!(
-                "Invalid arguments: expected {}, found {}",
-                expected
-                  .iter()
-                  .map(ToString::to_string)
-                  .collect::<Vec<String>>()
-                  .join(", "),
-                found
-                  .iter()
-                  .map(ToString::to_string)
-                  .collect::<Vec<String>>()
-                  .join(", ")
-              ),
-              error::TypecheckerError::UnresolvedIdentifier(name) => {
-                println!("Unresolved identifier {}", name)
-              }
-            },
-          }
-        }
-        Err(error) => {
-          error.print(source, name);
-          process::exit(1);
-        }
-      }
-    }
-    Err(error) => {
-      error.print(source, name);
       process::exit(1);
     }
   }
@@ -14,4 +14,6 @@ pub struct Header {
 pub struct Variant<T> {
   pub name: String,
   pub fields: Vec<T>,
+  // the names of the fields of `V { x: T }`, in order, or none if they're positional like `V(T)`
+  pub names: Vec<String>,
 }
@@ -6,7 +6,7 @@ pub struct Function<T> {
   pub body: util::Expression<T>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Header<T> {
   pub name: String,
   // pub type_parameters: Vec<util::TypeParameter>,
@@ -3,5 +3,6 @@ pub mod function;
 pub mod module;
 pub mod operator;
 pub mod r#struct;
+pub mod substitute;
 pub mod r#trait;
 pub mod util;
@@ -7,11 +7,16 @@ pub struct Module<T> {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Item<T> {
+  pub docs: Vec<String>,
   pub modifiers: util::Modifiers,
   pub kind: ItemKind<T>,
+  // the tokens from its modifiers to its semicolon, if it was parsed with spans
+  pub span: Option<std::ops::Range<usize>>,
 }
 
 // TODO: traits (trait bounds, default implementations, fields?)
+// operators hold their bodies inline, so they're larger than the other items
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum ItemKind<T> {
   Function(function::Function<T>),
@@ -19,4 +24,20 @@ pub enum ItemKind<T> {
   Enum(r#enum::Enum<T>),
   Trait(r#trait::Trait<T>),
   Operator(operator::Operator<T>),
+  Const {
+    name: String,
+    ty: T,
+    value: util::Expression<T>,
+  },
+  // only declared in struct bodies. a field with a default can be left out of a struct literal
+  Field {
+    name: String,
+    ty: T,
+    default: Option<util::Expression<T>>,
+  },
+  // `impl Foo<T> { ... }`, whose items are added to the struct `Foo`, with `Self` as `ty`
+  Impl {
+    ty: T,
+    module: Module<T>,
+  },
 }
@@ -6,13 +6,13 @@ pub struct Operator<T> {
   pub body: util::Expression<T>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Header<T> {
   Prefix(Prefix<T>),
   Infix(Infix<T>),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Prefix<T> {
   pub operator: String,
   // pub type_parameters: Vec<util::TypeParameter>,
@@ -20,7 +20,7 @@ pub struct Prefix<T> {
   pub result: T,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Infix<T> {
   pub operator: String,
   // pub type_parameters: Vec<util::TypeParameter>,
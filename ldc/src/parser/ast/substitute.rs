@@ -0,0 +1,309 @@
+use super::{function, module, operator, r#enum, r#struct, util};
+use std::collections::BTreeMap;
+
+type Type = util::Type<Vec<String>>;
+type Arguments = BTreeMap<Vec<String>, Type>;
+
+// `Type::substitute` over every type written in a tree, for items that are checked as if they were
+// written with other names. traits are left alone, since their types are in terms of their own
+// `Self`, and nested structs shadow the names of their type parameters
+impl module::Module<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    module::Module {
+      items: self
+        .items
+        .into_iter()
+        .map(|item| module::Item {
+          kind: item.kind.substitute(arguments),
+          ..item
+        })
+        .collect(),
+    }
+  }
+}
+
+impl module::ItemKind<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    match self {
+      module::ItemKind::Function(f) => module::ItemKind::Function(f.substitute(arguments)),
+      module::ItemKind::Struct(s) => module::ItemKind::Struct(s.substitute(arguments)),
+      module::ItemKind::Enum(e) => module::ItemKind::Enum(r#enum::Enum {
+        header: e.header,
+        variants: e
+          .variants
+          .into_iter()
+          .map(|variant| r#enum::Variant {
+            fields: substitute_all(&variant.fields, arguments),
+            ..variant
+          })
+          .collect(),
+      }),
+      module::ItemKind::Trait(t) => module::ItemKind::Trait(t),
+      module::ItemKind::Operator(o) => module::ItemKind::Operator(o.substitute(arguments)),
+      module::ItemKind::Const { name, ty, value } => module::ItemKind::Const {
+        name,
+        ty: ty.substitute(arguments),
+        value: value.substitute(arguments),
+      },
+      module::ItemKind::Field { name, ty, default } => module::ItemKind::Field {
+        name,
+        ty: ty.substitute(arguments),
+        default: default.map(|default| default.substitute(arguments)),
+      },
+      module::ItemKind::Impl { ty, module } => module::ItemKind::Impl {
+        ty: ty.substitute(arguments),
+        module: module.substitute(arguments),
+      },
+    }
+  }
+}
+
+impl r#struct::Struct<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    let mut arguments = arguments.clone();
+    for name in ["Self".to_string()]
+      .into_iter()
+      .chain(self.header.type_parameters.iter().map(|p| p.name.clone()))
+    {
+      arguments.remove(&vec![name]);
+    }
+
+    r#struct::Struct {
+      module: self.module.substitute(&arguments),
+      header: self.header,
+    }
+  }
+}
+
+impl function::Function<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    function::Function {
+      header: function::Header {
+        name: self.header.name,
+        parameters: self
+          .header
+          .parameters
+          .into_iter()
+          .map(|parameter| parameter.substitute(arguments))
+          .collect(),
+        ty: self.header.ty.map(|ty| ty.substitute(arguments)),
+      },
+      body: self.body.substitute(arguments),
+    }
+  }
+}
+
+impl operator::Operator<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    operator::Operator {
+      header: match self.header {
+        operator::Header::Prefix(prefix) => operator::Header::Prefix(operator::Prefix {
+          operator: prefix.operator,
+          operand: prefix.operand.substitute(arguments),
+          result: prefix.result.substitute(arguments),
+        }),
+        operator::Header::Infix(infix) => operator::Header::Infix(operator::Infix {
+          operator: infix.operator,
+          operands: (
+            infix.operands.0.substitute(arguments),
+            infix.operands.1.substitute(arguments),
+          ),
+          result: infix.result.substitute(arguments),
+        }),
+      },
+      body: self.body.substitute(arguments),
+    }
+  }
+}
+
+impl util::Parameter<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    util::Parameter {
+      ty: self.ty.substitute(arguments),
+      default: self.default.map(|default| default.substitute(arguments)),
+      ..self
+    }
+  }
+}
+
+impl util::Expression<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    let substitute = |expression: Box<Self>| Box::new(expression.substitute(arguments));
+
+    match self {
+      util::Expression::Block {
+        expressions,
+        has_value,
+      } => util::Expression::Block {
+        expressions: expressions
+          .into_iter()
+          .map(|expression| expression.substitute(arguments))
+          .collect(),
+        has_value,
+      },
+      util::Expression::If {
+        condition,
+        consequence,
+        alternative,
+      } => util::Expression::If {
+        condition: substitute(condition),
+        consequence: substitute(consequence),
+        alternative: alternative.map(substitute),
+      },
+      util::Expression::Loop { label, body } => util::Expression::Loop {
+        label,
+        body: substitute(body),
+      },
+      util::Expression::While {
+        label,
+        condition,
+        body,
+      } => util::Expression::While {
+        label,
+        condition: substitute(condition),
+        body: substitute(body),
+      },
+      util::Expression::For {
+        label,
+        pattern,
+        iterable,
+        body,
+      } => util::Expression::For {
+        label,
+        pattern,
+        iterable: substitute(iterable),
+        body: substitute(body),
+      },
+      util::Expression::Match { scrutinee, arms } => util::Expression::Match {
+        scrutinee: substitute(scrutinee),
+        arms: arms
+          .into_iter()
+          .map(|(pattern, arm)| (pattern, arm.substitute(arguments)))
+          .collect(),
+      },
+      util::Expression::Return(value) => util::Expression::Return(value.map(substitute)),
+      util::Expression::Break { label, value } => util::Expression::Break {
+        label,
+        value: value.map(substitute),
+      },
+      util::Expression::Let {
+        mutable,
+        pattern,
+        ty,
+        value,
+      } => util::Expression::Let {
+        mutable,
+        pattern,
+        ty: ty.map(|ty| ty.substitute(arguments)),
+        value: substitute(value),
+      },
+      util::Expression::Call {
+        expression,
+        arguments: values,
+      } => util::Expression::Call {
+        expression: substitute(expression),
+        arguments: values
+          .into_iter()
+          .map(|argument| util::Argument {
+            name: argument.name,
+            value: argument.value.substitute(arguments),
+          })
+          .collect(),
+      },
+      util::Expression::Index { expression, index } => util::Expression::Index {
+        expression: substitute(expression),
+        index: substitute(index),
+      },
+      util::Expression::TupleIndex { expression, index } => util::Expression::TupleIndex {
+        expression: substitute(expression),
+        index,
+      },
+      util::Expression::Field { expression, field } => util::Expression::Field {
+        expression: substitute(expression),
+        field,
+      },
+      util::Expression::Cast { value, ty } => util::Expression::Cast {
+        value: substitute(value),
+        ty: ty.substitute(arguments),
+      },
+      util::Expression::Try(expression) => util::Expression::Try(substitute(expression)),
+      util::Expression::Range { start, end, kind } => util::Expression::Range {
+        start: substitute(start),
+        end: substitute(end),
+        kind,
+      },
+      util::Expression::Literal(literal) => {
+        util::Expression::Literal(literal.substitute(arguments))
+      }
+      util::Expression::Infix {
+        operator,
+        operands: (a, b),
+      } => util::Expression::Infix {
+        operator,
+        operands: (substitute(a), substitute(b)),
+      },
+      util::Expression::Prefix { operator, operand } => util::Expression::Prefix {
+        operator,
+        operand: substitute(operand),
+      },
+      util::Expression::StructLiteral { path, fields } => util::Expression::StructLiteral {
+        path,
+        fields: fields
+          .into_iter()
+          .map(|(name, value)| (name, value.substitute(arguments)))
+          .collect(),
+      },
+      util::Expression::Spanned(span, expression) => {
+        util::Expression::Spanned(span, substitute(expression))
+      }
+      expression @ (util::Expression::Continue { .. }
+      | util::Expression::Identifier(_)
+      | util::Expression::Path(_)) => expression,
+    }
+  }
+}
+
+impl util::Literal<Type> {
+  pub fn substitute(self, arguments: &Arguments) -> Self {
+    let all = |expressions: Vec<util::Expression<Type>>| {
+      expressions
+        .into_iter()
+        .map(|expression| expression.substitute(arguments))
+        .collect()
+    };
+
+    match self {
+      util::Literal::Tuple(expressions) => util::Literal::Tuple(all(expressions)),
+      util::Literal::Array(expressions) => util::Literal::Array(all(expressions)),
+      util::Literal::Record(fields) => util::Literal::Record(
+        fields
+          .into_iter()
+          .map(|(name, value)| (name, value.substitute(arguments)))
+          .collect(),
+      ),
+      util::Literal::Closure {
+        parameters,
+        ty,
+        body,
+      } => util::Literal::Closure {
+        parameters: parameters
+          .into_iter()
+          .map(|parameter| util::ClosureParameter {
+            name: parameter.name,
+            ty: parameter.ty.map(|ty| ty.substitute(arguments)),
+          })
+          .collect(),
+        ty: ty.map(|ty| ty.substitute(arguments)),
+        body: Box::new(body.substitute(arguments)),
+      },
+      literal @ (util::Literal::Char(_)
+      | util::Literal::String(_)
+      | util::Literal::Number(_)
+      | util::Literal::Bool(_)) => literal,
+    }
+  }
+}
+
+fn substitute_all(types: &[Type], arguments: &Arguments) -> Vec<Type> {
+  types.iter().map(|ty| ty.substitute(arguments)).collect()
+}
@@ -1,28 +1,53 @@
 use crate::{error::ParserError, lexer::token::NumericType, map0, map1};
-use std::{collections::BTreeSet, fmt::Display, marker::PhantomData};
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fmt::Display,
+  hash::{Hash, Hasher},
+  marker::PhantomData,
+  num::IntErrorKind,
+  ops::{Range, RangeInclusive},
+};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct Modifiers {
   pub public: bool,
   pub static_: bool,
 }
 
-impl Default for Modifiers {
-  fn default() -> Self {
-    Modifiers {
-      public: false,
-      static_: false,
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter<T> {
+  pub name: String,
+  pub ty: T,
+  pub default: Option<Expression<T>>, // only function headers have defaults
+  pub rest: bool,                     // `name: T...`, only the last parameter of a function header
+}
+
+impl<Ref: Clone + PartialEq + Ord> Parameter<Type<Ref>> {
+  // the type of the variable the parameter binds, which is an array for a rest parameter
+  pub fn binding(&self) -> Type<Ref> {
+    if self.rest {
+      Type::Array(Box::new(self.ty.clone()))
+    } else {
+      self.ty.clone()
     }
   }
 }
 
+// the type of an unannotated closure parameter is inferred from where the closure is passed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosureParameter<T> {
+  pub name: String,
+  pub ty: Option<T>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Parameter<T> {
+pub struct TypeParameter {
   pub name: String,
-  pub ty: T,
+  pub traits: Vec<Path>,
 }
 
-#[derive(Debug, Clone, Eq, Hash, PartialOrd, Ord)]
+// equality is defined modulo `reduce`, so (T) and T compare equal, and hashing reduces too
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
 pub enum Type<Ref>
 where
   Ref: Clone + PartialEq + Ord,
@@ -41,14 +66,130 @@ where
   F32,
   F64,
   Char,
+  Never, // the type of expressions that don't produce a value, like a loop that's never broken out of
   Named(Ref, Vec<Type<Ref>>),
   Function(Vec<Type<Ref>>, Box<Type<Ref>>),
   Tuple(Vec<Type<Ref>>),
+  Record(Vec<(String, Type<Ref>)>), // `{ x: T, y: U }`, whose fields are compared by name in any order
   Array(Box<Type<Ref>>),
-  Union(BTreeSet<Type<Ref>>), // the parser guarantees that the union is flat (no unions of unions) and that there is at least one type
+  FixedArray(Box<Type<Ref>>, usize),
+  Reference(Box<Type<Ref>>, bool), // `&T`, or `&mut T` if the flag is set
+  Union(BTreeSet<Type<Ref>>), // built with `Type::union`, so it is flat (no unions of unions) and has at least two types
+  Infer(usize), // a type the typechecker hasn't worked out yet, which is never written in the source
 }
 
 impl<Ref: Clone + PartialEq + Ord> Type<Ref> {
+  // nested unions are flattened, members are deduplicated after reducing them, and `!` is dropped
+  // since it has no values. a union of one type is just that type, and a union of none is `!`
+  pub fn union(types: impl IntoIterator<Item = Type<Ref>>) -> Type<Ref> {
+    fn insert<Ref: Clone + PartialEq + Ord>(set: &mut BTreeSet<Type<Ref>>, ty: Type<Ref>) {
+      match ty.reduce() {
+        Type::Union(types) => types.into_iter().for_each(|ty| insert(set, ty)),
+        Type::Never => (),
+        ty => {
+          set.insert(ty);
+        }
+      }
+    }
+
+    let mut set = BTreeSet::new();
+    for ty in types {
+      insert(&mut set, ty);
+    }
+
+    match set.len() {
+      0 => Type::Never,
+      1 => set.into_iter().next().unwrap(),
+      _ => Type::Union(set),
+    }
+  }
+
+  // replaces references to type parameters with the types they're instantiated with
+  pub fn substitute(&self, arguments: &BTreeMap<Ref, Type<Ref>>) -> Type<Ref> {
+    match self {
+      Type::Named(name, parameters) if parameters.is_empty() && arguments.contains_key(name) => {
+        arguments[name].clone()
+      }
+      Type::Named(name, parameters) => Type::Named(
+        name.clone(),
+        parameters
+          .iter()
+          .map(|ty| ty.substitute(arguments))
+          .collect(),
+      ),
+      Type::Function(parameters, ty) => Type::Function(
+        parameters
+          .iter()
+          .map(|ty| ty.substitute(arguments))
+          .collect(),
+        Box::new(ty.substitute(arguments)),
+      ),
+      Type::Tuple(types) => Type::Tuple(types.iter().map(|ty| ty.substitute(arguments)).collect()),
+      Type::Record(fields) => Type::Record(
+        fields
+          .iter()
+          .map(|(name, ty)| (name.clone(), ty.substitute(arguments)))
+          .collect(),
+      ),
+      Type::Array(ty) => Type::Array(Box::new(ty.substitute(arguments))),
+      Type::FixedArray(ty, length) => Type::FixedArray(Box::new(ty.substitute(arguments)), *length),
+      Type::Reference(ty, mutable) => Type::Reference(Box::new(ty.substitute(arguments)), *mutable),
+      Type::Union(types) => Type::Union(types.iter().map(|ty| ty.substitute(arguments)).collect()),
+      _ => self.clone(),
+    }
+  }
+
+  // replaces the inference variables that `resolved` knows the types of. those types are filled in
+  // too, so `resolved` can give back what it was told without resolving it first
+  pub fn fill(&self, resolved: &impl Fn(usize) -> Option<Type<Ref>>) -> Type<Ref> {
+    match self {
+      Type::Infer(id) => match resolved(*id) {
+        Some(ty) => ty.fill(resolved),
+        None => self.clone(),
+      },
+      Type::Named(name, parameters) => Type::Named(
+        name.clone(),
+        parameters.iter().map(|ty| ty.fill(resolved)).collect(),
+      ),
+      Type::Function(parameters, ty) => Type::Function(
+        parameters.iter().map(|ty| ty.fill(resolved)).collect(),
+        Box::new(ty.fill(resolved)),
+      ),
+      Type::Tuple(types) => Type::Tuple(types.iter().map(|ty| ty.fill(resolved)).collect()),
+      Type::Record(fields) => Type::Record(
+        fields
+          .iter()
+          .map(|(name, ty)| (name.clone(), ty.fill(resolved)))
+          .collect(),
+      ),
+      Type::Array(ty) => Type::Array(Box::new(ty.fill(resolved))),
+      Type::FixedArray(ty, length) => Type::FixedArray(Box::new(ty.fill(resolved)), *length),
+      Type::Reference(ty, mutable) => Type::Reference(Box::new(ty.fill(resolved)), *mutable),
+      // the members may have become the same type, or unions themselves
+      Type::Union(types) => Type::union(types.iter().map(|ty| ty.fill(resolved))),
+      _ => self.clone(),
+    }
+  }
+
+  // the inference variables anywhere in the type
+  pub fn inferences(&self) -> BTreeSet<usize> {
+    match self {
+      Type::Infer(id) => BTreeSet::from([*id]),
+      Type::Named(_, types) | Type::Tuple(types) => {
+        types.iter().flat_map(Type::inferences).collect()
+      }
+      Type::Function(parameters, ty) => parameters
+        .iter()
+        .chain([ty.as_ref()])
+        .flat_map(Type::inferences)
+        .collect(),
+      Type::Record(fields) => fields.iter().flat_map(|(_, ty)| ty.inferences()).collect(),
+      Type::Array(ty) | Type::FixedArray(ty, _) | Type::Reference(ty, _) => ty.inferences(),
+      Type::Union(types) => types.iter().flat_map(Type::inferences).collect(),
+      _ => BTreeSet::new(),
+    }
+  }
+
   pub fn reduce(&self) -> Type<Ref> {
     match self {
       Type::Named(name, parameters) => {
@@ -60,35 +201,197 @@ impl<Ref: Clone + PartialEq + Ord> Type<Ref> {
       ),
       Type::Tuple(types) => {
         if types.len() == 1 {
-          types[0].clone()
+          types[0].reduce()
         } else {
           Type::Tuple(types.iter().map(Type::reduce).collect())
         }
       }
+      // the fields are sorted, so that records only differing in their order compare equal
+      Type::Record(fields) => {
+        let mut fields = fields
+          .iter()
+          .map(|(name, ty)| (name.clone(), ty.reduce()))
+          .collect::<Vec<_>>();
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Type::Record(fields)
+      }
       Type::Array(ty) => Type::Array(Box::new(ty.reduce())),
+      Type::FixedArray(ty, length) => Type::FixedArray(Box::new(ty.reduce()), *length),
+      Type::Reference(ty, mutable) => Type::Reference(Box::new(ty.reduce()), *mutable),
+      // `collect` would dedup with `==`, which reduces both sides again and makes nested unions
+      // exponential. inserting only compares with `Ord`
       Type::Union(types) => {
-        Type::Union(types.iter().map(|ty| ty.reduce()).collect::<BTreeSet<_>>())
+        let mut reduced = BTreeSet::new();
+        for ty in types {
+          reduced.insert(ty.reduce());
+        }
+
+        Type::Union(reduced)
       }
       _ => self.clone(),
     }
   }
 
+  pub fn is_integer(&self) -> bool {
+    matches!(
+      self,
+      Type::I8
+        | Type::I16
+        | Type::I32
+        | Type::I64
+        | Type::I128
+        | Type::U8
+        | Type::U16
+        | Type::U32
+        | Type::U64
+        | Type::U128
+    )
+  }
+
+  // the values an integer type can hold. u128 is capped at i128::MAX, which is as far as literals
+  // are compared
+  pub fn integer_range(&self) -> Option<RangeInclusive<i128>> {
+    Some(match self {
+      Type::I8 => i8::MIN as i128..=i8::MAX as i128,
+      Type::I16 => i16::MIN as i128..=i16::MAX as i128,
+      Type::I32 => i32::MIN as i128..=i32::MAX as i128,
+      Type::I64 => i64::MIN as i128..=i64::MAX as i128,
+      Type::I128 => i128::MIN..=i128::MAX,
+      Type::U8 => 0..=u8::MAX as i128,
+      Type::U16 => 0..=u16::MAX as i128,
+      Type::U32 => 0..=u32::MAX as i128,
+      Type::U64 => 0..=u64::MAX as i128,
+      Type::U128 => 0..=i128::MAX,
+      _ => None?,
+    })
+  }
+
+  pub fn is_float(&self) -> bool {
+    matches!(self, Type::F32 | Type::F64)
+  }
+
+  pub fn is_numeric(&self) -> bool {
+    self.is_integer() || self.is_float()
+  }
+
+  pub fn is_signed(&self) -> bool {
+    matches!(
+      self,
+      Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::I128 | Type::F32 | Type::F64
+    )
+  }
+
   pub fn satisfies(&self, other: &Type<Ref>) -> bool {
-    match (self.reduce(), other.reduce()) {
-      (Type::Named(a, _), Type::Named(b, _)) => a == b, // TODO: traits, parameters
+    self.reduce().satisfies_reduced(&other.reduce())
+  }
+
+  // the innermost part of `self` that doesn't satisfy the same part of `expected`, along with the
+  // parts leading to it from the outside in. parts are only compared while the shapes agree, so a
+  // tuple of the wrong length is the mismatch itself. parameters are compared the other way round,
+  // since a function has to accept whatever the expected one would be given
+  pub fn mismatch(&self, expected: &Type<Ref>) -> (Vec<String>, Type<Ref>, Type<Ref>) {
+    let (found, expected) = (self.reduce(), expected.reduce());
+
+    let parts = match (&found, &expected) {
+      (Type::Tuple(a), Type::Tuple(b)) if a.len() == b.len() => a
+        .iter()
+        .zip(b)
+        .enumerate()
+        .map(|(i, (a, b))| (format!("{} tuple element", ordinal(i + 1)), a, b, false))
+        .collect(),
+      (Type::Record(a), Type::Record(b)) if same_fields(a, b) => a
+        .iter()
+        .zip(b)
+        .map(|((name, a), (_, b))| (format!("field {}", name), a, b, false))
+        .collect(),
+      (Type::Function(a, x), Type::Function(b, y)) if a.len() == b.len() => a
+        .iter()
+        .zip(b)
+        .enumerate()
+        .map(|(i, (a, b))| (format!("{} parameter", ordinal(i + 1)), a, b, true))
+        .chain([("return type".to_string(), x.as_ref(), y.as_ref(), false)])
+        .collect(),
+      (Type::Array(a) | Type::FixedArray(a, _), Type::Array(b)) => {
+        vec![("element type".to_string(), a.as_ref(), b.as_ref(), false)]
+      }
+      (Type::FixedArray(a, n), Type::FixedArray(b, m)) if n == m => {
+        vec![("element type".to_string(), a.as_ref(), b.as_ref(), false)]
+      }
+      (Type::Reference(a, x), Type::Reference(b, y)) if x == y || !y => {
+        vec![("referenced type".to_string(), a.as_ref(), b.as_ref(), false)]
+      }
+      _ => vec![],
+    };
+
+    let mismatched = parts.into_iter().find(|(_, a, b, parameter)| {
+      if *parameter {
+        !b.satisfies_reduced(a)
+      } else {
+        !a.satisfies_reduced(b)
+      }
+    });
+
+    match mismatched {
+      Some((part, a, b, parameter)) => {
+        let (mut path, a, b) = if parameter {
+          let (path, b, a) = b.mismatch(a);
+          (path, a, b)
+        } else {
+          a.mismatch(b)
+        };
+        path.insert(0, part);
+        (path, a, b)
+      }
+      None => (vec![], found.clone(), expected.clone()),
+    }
+  }
+
+  // reducing is recursive, so the parts of a reduced type don't have to be reduced again
+  fn satisfies_reduced(&self, other: &Type<Ref>) -> bool {
+    match (self, other) {
+      // there are no values of `!`, so it can stand in for anything
+      (Type::Never, _) => true,
+      // type arguments are invariant, since a method could take them as well as give them back
+      (Type::Named(a, x), Type::Named(b, y)) => a == b && x == y, // TODO: traits
       (Type::Function(a, b), Type::Function(c, d)) => {
-        a.len() == c.len() && a.iter().zip(c.iter()).all(|(a, c)| a.satisfies(c)) && b.satisfies(&d)
+        a.len() == c.len()
+          && a.iter().zip(c.iter()).all(|(a, c)| c.satisfies_reduced(a))
+          && b.satisfies_reduced(d)
       }
       (Type::Tuple(a), Type::Tuple(b)) => {
-        a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.satisfies(b))
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.satisfies_reduced(b))
+      }
+      // both are sorted, so the fields line up when they have the same names
+      (Type::Record(a), Type::Record(b)) => {
+        same_fields(a, b)
+          && a
+            .iter()
+            .zip(b.iter())
+            .all(|((_, a), (_, b))| a.satisfies_reduced(b))
+      }
+      (Type::Array(a), Type::Array(b)) => a.satisfies_reduced(b),
+      (Type::FixedArray(a, n), Type::FixedArray(b, m)) => n == m && a.satisfies_reduced(b),
+      // the length can be forgotten, but a dynamic array never has a known one
+      (Type::FixedArray(a, _), Type::Array(b)) => a.satisfies_reduced(b),
+      // a mutable reference can be used as an immutable one. anything written through a mutable
+      // reference has to fit the original, so its type can't change
+      (Type::Reference(a, x), Type::Reference(b, y)) => {
+        if *y {
+          *x && a == b
+        } else {
+          a.satisfies_reduced(b)
+        }
       }
-      (Type::Array(a), Type::Array(b)) => a.satisfies(&b),
 
       // if a union `a` satisfies a type `b`, then all types in `a` must satisfy `b`
-      (Type::Union(a), b) => a.iter().all(|ty| ty.satisfies(&b)),
-      // if a type `a` satisfies a union `b`, then `a` must satisfy at least one type in `b`
-      (a, Type::Union(b)) => b.iter().any(|ty| a.satisfies(ty)),
+      (Type::Union(a), b) => a.iter().all(|ty| ty.satisfies_reduced(b)),
+      // if a type `a` satisfies a union `b`, then `a` must satisfy at least one type in `b`. the
+      // members are reduced, so looking `a` up first saves checking them one by one
+      (a, Type::Union(b)) => b.contains(a) || b.iter().any(|ty| a.satisfies_reduced(ty)),
 
+      // numeric types must match exactly; there is no implicit widening between widths or
+      // signedness, so a `u8` only satisfies `u8` (literals take their suffix, or `i32`/`f64`)
       _ => self == other,
     }
   }
@@ -111,12 +414,17 @@ impl<Ref: Clone + PartialEq + Ord> PartialEq for Type<Ref> {
       | (Type::U128, Type::U128)
       | (Type::F32, Type::F32)
       | (Type::F64, Type::F64)
-      | (Type::Char, Type::Char) => true,
+      | (Type::Char, Type::Char)
+      | (Type::Never, Type::Never) => true,
       (Type::Named(a, b), Type::Named(c, d)) => a == c && b == d, // TODO: traits
       (Type::Function(a, b), Type::Function(c, d)) => a == c && b == d,
       (Type::Tuple(a), Type::Tuple(b)) => a == b,
+      (Type::Record(a), Type::Record(b)) => a == b,
       (Type::Array(a), Type::Array(b)) => a == b,
+      (Type::FixedArray(a, n), Type::FixedArray(b, m)) => a == b && n == m,
+      (Type::Reference(a, x), Type::Reference(b, y)) => a == b && x == y,
       (Type::Union(a), Type::Union(b)) => a == b,
+      (Type::Infer(a), Type::Infer(b)) => a == b,
 
       // (t) == t, vice versa
       // (Type::Tuple(a), b) | (b, Type::Tuple(a)) if a.len() == 1 => a[0] == b,
@@ -126,6 +434,63 @@ impl<Ref: Clone + PartialEq + Ord> PartialEq for Type<Ref> {
   }
 }
 
+impl<Ref: Clone + PartialEq + Ord + Hash> Hash for Type<Ref> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.reduce().hash_reduced(state)
+  }
+}
+
+impl<Ref: Clone + PartialEq + Ord + Hash> Type<Ref> {
+  // the parts of a reduced type are reduced already, so they're hashed as they are
+  fn hash_reduced<H: Hasher>(&self, state: &mut H) {
+    let all = |types: &mut dyn Iterator<Item = &Type<Ref>>, state: &mut H| {
+      for ty in types {
+        ty.hash_reduced(state);
+      }
+    };
+
+    std::mem::discriminant(self).hash(state);
+    match self {
+      Type::Named(name, arguments) => {
+        name.hash(state);
+        arguments.len().hash(state);
+        all(&mut arguments.iter(), state);
+      }
+      Type::Function(parameters, ty) => {
+        parameters.len().hash(state);
+        all(&mut parameters.iter(), state);
+        ty.hash_reduced(state);
+      }
+      Type::Tuple(types) => {
+        types.len().hash(state);
+        all(&mut types.iter(), state);
+      }
+      Type::Record(fields) => {
+        fields.len().hash(state);
+        for (name, ty) in fields {
+          name.hash(state);
+          ty.hash_reduced(state);
+        }
+      }
+      Type::Array(ty) => ty.hash_reduced(state),
+      Type::FixedArray(ty, length) => {
+        ty.hash_reduced(state);
+        length.hash(state);
+      }
+      Type::Reference(ty, mutable) => {
+        ty.hash_reduced(state);
+        mutable.hash(state);
+      }
+      Type::Union(types) => {
+        types.len().hash(state);
+        all(&mut types.iter(), state);
+      }
+      Type::Infer(id) => id.hash(state),
+      _ => (),
+    }
+  }
+}
+
 impl Display for Type<Vec<String>> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
@@ -143,6 +508,9 @@ impl Display for Type<Vec<String>> {
       Type::F32 => write!(f, "f32"),
       Type::F64 => write!(f, "f64"),
       Type::Char => write!(f, "char"),
+      Type::Never => write!(f, "!"),
+      // the type couldn't be worked out, like the elements of an empty array
+      Type::Infer(_) => write!(f, "_"),
       Type::Named(name, parameters) => {
         for (i, part) in name.iter().enumerate() {
           if i != 0 {
@@ -172,7 +540,7 @@ impl Display for Type<Vec<String>> {
           }
           write!(f, "{}", parameter)?;
         }
-        write!(f, "): {}", return_type)
+        write!(f, ") -> {}", return_type)
       }
       Type::Tuple(types) => {
         write!(f, "(")?;
@@ -184,7 +552,21 @@ impl Display for Type<Vec<String>> {
         }
         write!(f, ")")
       }
+      Type::Record(fields) if fields.is_empty() => write!(f, "{{}}"),
+      Type::Record(fields) => {
+        write!(f, "{{ ")?;
+        for (i, (name, ty)) in fields.iter().enumerate() {
+          if i != 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{}: {}", name, ty)?;
+        }
+        write!(f, " }}")
+      }
       Type::Array(ty) => write!(f, "[{}]", ty),
+      Type::FixedArray(ty, length) => write!(f, "[{}; {}]", ty, length),
+      Type::Reference(ty, false) => write!(f, "&{}", ty),
+      Type::Reference(ty, true) => write!(f, "&mut {}", ty),
       Type::Union(types) => {
         for (i, ty) in types.iter().enumerate() {
           if i != 0 {
@@ -212,21 +594,68 @@ pub enum Expression<T> {
     consequence: Box<Expression<T>>,
     alternative: Option<Box<Expression<T>>>,
   },
+  // loops can be labeled, so that `break` and `continue` can leave one that isn't the innermost
+  Loop {
+    label: Option<String>,
+    body: Box<Expression<T>>,
+  },
   While {
+    label: Option<String>,
     condition: Box<Expression<T>>,
     body: Box<Expression<T>>,
   },
-  Return(Box<Expression<T>>),
-  Declaration(String),
+  For {
+    label: Option<String>,
+    pattern: Pattern<T>,
+    iterable: Box<Expression<T>>,
+    body: Box<Expression<T>>,
+  },
+  Match {
+    scrutinee: Box<Expression<T>>,
+    arms: Vec<(Pattern<T>, Expression<T>)>,
+  },
+  Return(Option<Box<Expression<T>>>),
+  Break {
+    label: Option<String>,
+    value: Option<Box<Expression<T>>>,
+  },
+  Continue {
+    label: Option<String>,
+  },
+  Let {
+    mutable: bool, // `let mut`, for every binding in the pattern
+    pattern: Pattern<T>,
+    ty: Option<T>,
+    value: Box<Expression<T>>,
+  },
   // TODO: allow overloading for calling and indexing
   Call {
     expression: Box<Expression<T>>,
-    arguments: Vec<Expression<T>>,
+    arguments: Vec<Argument<T>>,
   },
   Index {
     expression: Box<Expression<T>>,
     index: Box<Expression<T>>,
   },
+  TupleIndex {
+    expression: Box<Expression<T>>,
+    index: usize,
+  },
+  Field {
+    expression: Box<Expression<T>>,
+    field: String,
+  },
+  Cast {
+    value: Box<Expression<T>>,
+    ty: T,
+  },
+  // `x?`, which unwraps the `Ok` of a result or returns its `Err` from the function
+  Try(Box<Expression<T>>),
+  Range {
+    start: Box<Expression<T>>,
+    end: Box<Expression<T>>,
+    kind: RangeKind,
+  },
   Literal(Literal<T>),
   Infix {
     operator: String,
@@ -237,6 +666,160 @@ pub enum Expression<T> {
     operand: Box<Expression<T>>,
   },
   Identifier(String),
+  Path(Vec<String>), // `Foo::bar`, always at least two segments
+  // `Foo { a: 1, b }`, where `b` is short for `b: b`
+  StructLiteral {
+    path: Path,
+    fields: Vec<(String, Expression<T>)>,
+  },
+  // the byte range an expression was parsed from. only produced when the parser is asked to, so
+  // that trees can still be compared without them
+  Spanned(Range<usize>, Box<Expression<T>>),
+}
+
+impl<T> Expression<T> {
+  pub fn unspanned(&self) -> &Expression<T> {
+    match self {
+      Expression::Spanned(_, expression) => expression.unspanned(),
+      expression => expression,
+    }
+  }
+
+  pub fn span(&self) -> Option<Range<usize>> {
+    match self {
+      Expression::Spanned(span, _) => Some(span.clone()),
+      _ => None,
+    }
+  }
+
+  pub fn const_eval(&self) -> Option<i128> {
+    self.const_eval_with(&|_| None)
+  }
+
+  // folds integer literals, the constants that `constants` knows the values of, and the arithmetic
+  // and bitwise operators over them. anything that isn't known until runtime, or that overflows or
+  // divides by zero, has no constant value
+  pub fn const_eval_with(&self, constants: &impl Fn(&str) -> Option<i128>) -> Option<i128> {
+    self.fold(constants).map(|(n, _)| n)
+  }
+
+  // the value along with the range of the integer type it's folded in. a suffixed literal gives
+  // its own type to the operators over it, so `0u8 - 1u8` overflows and `~0u8` is 255, while the
+  // constants are folded as they are
+  fn fold(
+    &self,
+    constants: &impl Fn(&str) -> Option<i128>,
+  ) -> Option<(i128, Option<RangeInclusive<i128>>)> {
+    let (n, range) = match self.unspanned() {
+      Expression::Literal(Literal::Number(n)) => (n.integer()?, n.integer_range()),
+      Expression::Identifier(name) => (constants(name)?, None),
+      // parentheses
+      Expression::Literal(Literal::Tuple(expressions)) if expressions.len() == 1 => {
+        expressions[0].fold(constants)?
+      }
+      Expression::Prefix { operator, operand } => {
+        let (n, range) = operand.fold(constants)?;
+
+        let n = match operator.as_str() {
+          "+" => Some(n),
+          "-" => n.checked_neg(),
+          // an unsigned complement flips the bits the type has. u128 is the only type whose range
+          // is capped, and its complement is never small enough to be compared
+          "~" => match &range {
+            Some(range) if *range.start() == 0 && *range.end() == i128::MAX => None,
+            Some(range) if *range.start() == 0 => Some(range.end() - n),
+            _ => Some(!n),
+          },
+          _ => None,
+        }?;
+
+        (n, range)
+      }
+      Expression::Infix {
+        operator,
+        operands: (a, b),
+      } => {
+        let ((a, x), (b, y)) = (a.fold(constants)?, b.fold(constants)?);
+        let range = match operator.as_str() {
+          "<<" | ">>" => x,
+          _ => x.or(y),
+        };
+
+        let n = match operator.as_str() {
+          "+" => a.checked_add(b),
+          "-" => a.checked_sub(b),
+          "*" => a.checked_mul(b),
+          "/" => a.checked_div(b),
+          "%" => a.checked_rem(b),
+          "&" => Some(a & b),
+          "|" => Some(a | b),
+          "^" => Some(a ^ b),
+          "<<" => a.checked_shl(b.try_into().ok()?),
+          ">>" => a.checked_shr(b.try_into().ok()?),
+          _ => None,
+        }?;
+
+        (n, range)
+      }
+      _ => None?,
+    };
+
+    match &range {
+      Some(range) if !range.contains(&n) => None,
+      _ => Some((n, range)),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument<T> {
+  pub name: Option<String>, // `name: value`, or positional if there's no name
+  pub value: Expression<T>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RangeKind {
+  Exclusive, // `..` and `..<`
+  Inclusive, // `..=`
+}
+
+impl RangeKind {
+  pub fn operator(&self) -> &'static str {
+    match self {
+      RangeKind::Exclusive => "..",
+      RangeKind::Inclusive => "..=",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern<T> {
+  Wildcard, // `_`, which matches anything and binds nothing
+  Identifier(String),
+  Literal(Literal<T>), // only chars, strings, numbers, and bools
+  Tuple(Vec<Pattern<T>>),
+  // `Circle(r)` or `Shape::Empty`. a bare name like `Empty` is an identifier until it's resolved
+  Variant {
+    path: Vec<String>,
+    fields: Vec<Pattern<T>>,
+  },
+  // `Circle { r }` or `Circle { r: radius }`, for a variant with named fields. the fields that are
+  // left out match anything
+  Struct {
+    path: Vec<String>,
+    fields: Vec<(String, Pattern<T>)>,
+  },
+}
+
+impl<T> Pattern<T> {
+  // whether the pattern matches every value of the type it's checked against
+  pub fn irrefutable(&self) -> bool {
+    match self {
+      Pattern::Wildcard | Pattern::Identifier(_) => true,
+      Pattern::Literal(_) | Pattern::Variant { .. } | Pattern::Struct { .. } => false,
+      Pattern::Tuple(patterns) => patterns.iter().all(Pattern::irrefutable),
+    }
+  }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -244,11 +827,13 @@ pub enum Literal<T> {
   Char(char),
   String(String),
   Tuple(Vec<Expression<T>>),
+  // `{ x: 1, y: 2 }`. a block can't start with `name:`, so it needs at least one field
+  Record(Vec<(String, Expression<T>)>),
   Number(NumberLiteral<T>),
   Array(Vec<Expression<T>>),
   Bool(bool),
   Closure {
-    parameters: Vec<Parameter<T>>,
+    parameters: Vec<ClosureParameter<T>>,
     ty: Option<T>,
     body: Box<Expression<T>>,
   },
@@ -256,6 +841,9 @@ pub enum Literal<T> {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum NumberLiteral<T> {
+  // an integer without a suffix, which takes the integer type it's used as and is an i32 otherwise.
+  // it's kept as written, since whether it fits isn't known until then
+  Integer(u128),
   I8(i8),
   I16(i16),
   I32(i32),
@@ -272,46 +860,141 @@ pub enum NumberLiteral<T> {
 }
 
 impl<T> NumberLiteral<T> {
+  // integers are parsed as u128 and narrowed, which is a no-op for u128 itself. a literal too large
+  // for its type is an overflow rather than a malformed number, and that includes floats that would
+  // round to infinity
+  #[allow(clippy::useless_conversion)]
   pub fn from_string(s: &str, ty: NumericType) -> Result<Literal<T>, ParserError> {
+    let error = || ParserError::InvalidNumber(s.to_string(), ty.clone());
+    let overflow = || ParserError::NumberOverflow(s.to_string(), ty.clone());
+
+    let (digits, radix) = match s.get(..2) {
+      Some("0x") => (&s[2..], 16),
+      Some("0o") => (&s[2..], 8),
+      Some("0b") => (&s[2..], 2),
+      _ => (s, 10),
+    };
+
     Ok(match ty.clone() {
       NumericType::Char => {
-        let n = s
-          .parse::<u32>()
-          .map_err(|_| ParserError::InvalidNumber(s.to_string(), ty.clone()))?;
+        let n = u32::from_str_radix(digits, radix).map_err(|_| error())?;
 
         std::char::from_u32(n)
           .map(Literal::Char)
-          .ok_or(ParserError::InvalidNumber(s.to_string(), ty))?
+          .ok_or_else(error)?
       }
-      other => Literal::Number(map0!(
+      // floats cannot be written with a radix prefix
+      NumericType::F32 | NumericType::F64 if radix != 10 => Err(error())?,
+      other @ (NumericType::F32 | NumericType::F64) => match map0!(
         other,
         NumericType,
         NumberLiteral<T>,
-        |n| s
-          .parse()
-          .map(n)
-          .map_err(|_| ParserError::InvalidNumber(s.to_string(), ty)),
-        I8,
-        I16,
-        I32,
-        I64,
-        I128,
-        U8,
-        U16,
-        U32,
-        U64,
-        U128,
+        |n| s.parse().map(n).map_err(|_| error()),
         F32,
         F64
-      )?),
+      )? {
+        NumberLiteral::F32(n) if n.is_infinite() => Err(overflow())?,
+        NumberLiteral::F64(n) if n.is_infinite() => Err(overflow())?,
+        n => Literal::Number(n),
+      },
+      other => {
+        let value = u128::from_str_radix(digits, radix).map_err(|e| match e.kind() {
+          IntErrorKind::PosOverflow => overflow(),
+          _ => error(),
+        })?;
+
+        if other == NumericType::Integer {
+          return Ok(Literal::Number(NumberLiteral::Integer(value)));
+        }
+
+        Literal::Number(map0!(
+          other,
+          NumericType,
+          NumberLiteral<T>,
+          |n| value.try_into().map(n).map_err(|_| overflow()),
+          I8,
+          I16,
+          I32,
+          I64,
+          I128,
+          U8,
+          U16,
+          U32,
+          U64,
+          U128
+        )?)
+      }
     })
   }
+
+  // the value of an integer literal, unless it's a float or a u128 too large for an i128
+  pub fn integer(&self) -> Option<i128> {
+    match self {
+      NumberLiteral::Integer(n) => i128::try_from(*n).ok(),
+      NumberLiteral::I8(n) => Some(*n as i128),
+      NumberLiteral::I16(n) => Some(*n as i128),
+      NumberLiteral::I32(n) => Some(*n as i128),
+      NumberLiteral::I64(n) => Some(*n as i128),
+      NumberLiteral::I128(n) => Some(*n),
+      NumberLiteral::U8(n) => Some(*n as i128),
+      NumberLiteral::U16(n) => Some(*n as i128),
+      NumberLiteral::U32(n) => Some(*n as i128),
+      NumberLiteral::U64(n) => Some(*n as i128),
+      NumberLiteral::U128(n) => i128::try_from(*n).ok(),
+      NumberLiteral::F32(_) | NumberLiteral::F64(_) => None,
+      NumberLiteral::_PhantomData(_) => unreachable!(),
+    }
+  }
+
+  // the values the literal's own type can hold, if it's an integer
+  fn integer_range(&self) -> Option<RangeInclusive<i128>> {
+    let ty: Type<String> = match self {
+      NumberLiteral::Integer(_) => None?,
+      NumberLiteral::I8(_) => Type::I8,
+      NumberLiteral::I16(_) => Type::I16,
+      NumberLiteral::I32(_) => Type::I32,
+      NumberLiteral::I64(_) => Type::I64,
+      NumberLiteral::I128(_) => Type::I128,
+      NumberLiteral::U8(_) => Type::U8,
+      NumberLiteral::U16(_) => Type::U16,
+      NumberLiteral::U32(_) => Type::U32,
+      NumberLiteral::U64(_) => Type::U64,
+      NumberLiteral::U128(_) => Type::U128,
+      NumberLiteral::F32(_) | NumberLiteral::F64(_) => None?,
+      NumberLiteral::_PhantomData(_) => unreachable!(),
+    };
+
+    ty.integer_range()
+  }
+
+  pub fn is_zero(&self) -> bool {
+    match self {
+      NumberLiteral::Integer(n) => *n == 0,
+      NumberLiteral::I8(n) => *n == 0,
+      NumberLiteral::I16(n) => *n == 0,
+      NumberLiteral::I32(n) => *n == 0,
+      NumberLiteral::I64(n) => *n == 0,
+      NumberLiteral::I128(n) => *n == 0,
+      NumberLiteral::U8(n) => *n == 0,
+      NumberLiteral::U16(n) => *n == 0,
+      NumberLiteral::U32(n) => *n == 0,
+      NumberLiteral::U64(n) => *n == 0,
+      NumberLiteral::U128(n) => *n == 0,
+      NumberLiteral::F32(n) => *n == 0.0,
+      NumberLiteral::F64(n) => *n == 0.0,
+      NumberLiteral::_PhantomData(_) => unreachable!(),
+    }
+  }
 }
 
-impl<Ref: Clone + PartialEq + Ord> Into<Type<Ref>> for NumberLiteral<Type<Ref>> {
-  fn into(self) -> Type<Ref> {
+impl<Ref: Clone + PartialEq + Ord> From<NumberLiteral<Type<Ref>>> for Type<Ref> {
+  fn from(n: NumberLiteral<Type<Ref>>) -> Type<Ref> {
+    if let NumberLiteral::Integer(_) = n {
+      return Type::I32;
+    }
+
     map1!(
-      self,
+      n,
       NumberLiteral,
       Type,
       |n| n,
@@ -332,3 +1015,280 @@ impl<Ref: Clone + PartialEq + Ord> Into<Type<Ref>> for NumberLiteral<Type<Ref>>
 }
 
 pub type Path = Vec<String>;
+
+// whether two sorted lists of record fields have the same names
+pub fn same_fields<T>(a: &[(String, T)], b: &[(String, T)]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).all(|((a, _), (b, _))| a == b)
+}
+
+// 1st, 2nd, 3rd, 4th, ..., 11th, 12th, 13th, ..., 21st
+fn ordinal(n: usize) -> String {
+  let suffix = match (n % 10, n % 100) {
+    (_, 11..=13) => "th",
+    (1, _) => "st",
+    (2, _) => "nd",
+    (3, _) => "rd",
+    _ => "th",
+  };
+
+  format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::union;
+  use std::hash::{Hash, Hasher};
+
+  type Type = super::Type<Vec<String>>;
+
+  #[test]
+  fn test_union() {
+    assert_eq!(union!(Type::Never, Type::Char), Type::Char);
+    assert_eq!(
+      union!(Type::Bool, Type::Never, Type::Char),
+      union!(Type::Bool, Type::Char)
+    );
+    assert_eq!(union!(Type::Never, Type::Never), Type::Never);
+
+    assert_eq!(union!(Type::Bool, Type::Bool), Type::Bool);
+    assert_eq!(
+      union!(Type::Bool, Type::Tuple(vec![Type::Bool])),
+      Type::Bool
+    );
+
+    let nested = union!(
+      Type::Bool,
+      union!(Type::Char, union!(Type::I32, Type::Bool))
+    );
+    assert_eq!(
+      nested,
+      Type::Union([Type::Bool, Type::Char, Type::I32].into_iter().collect())
+    );
+  }
+
+  #[test]
+  fn test_substitute() {
+    let t = || Type::Named(vec!["T".to_string()], vec![]);
+    let arguments = [(vec!["T".to_string()], Type::I32)].into_iter().collect();
+
+    assert_eq!(t().substitute(&arguments), Type::I32);
+    assert_eq!(
+      Type::Function(vec![t(), Type::Bool], Box::new(Type::Array(Box::new(t()))))
+        .substitute(&arguments),
+      Type::Function(
+        vec![Type::I32, Type::Bool],
+        Box::new(Type::Array(Box::new(Type::I32)))
+      )
+    );
+    assert_eq!(
+      Type::Named(vec!["Foo".to_string()], vec![t()]).substitute(&arguments),
+      Type::Named(vec!["Foo".to_string()], vec![Type::I32])
+    );
+    assert_eq!(
+      Type::Named(vec!["U".to_string()], vec![]).substitute(&arguments),
+      Type::Named(vec!["U".to_string()], vec![])
+    );
+  }
+
+  #[test]
+  fn test_satisfies_large_union() {
+    let mut nested = Type::Bool;
+    for i in 0..100 {
+      nested = Type::union([
+        Type::Tuple(vec![nested, Type::Char]),
+        Type::Named(vec![i.to_string()], vec![]),
+      ]);
+    }
+
+    assert!(nested.satisfies(&nested));
+
+    let wide = Type::union(
+      (0..1000)
+        .map(|i| Type::Named(vec![format!("wide{}", i)], vec![]))
+        .chain([nested.clone()]),
+    );
+
+    assert!(wide.satisfies(&wide));
+    assert!(nested.satisfies(&wide));
+    assert!(!wide.satisfies(&nested));
+  }
+
+  #[test]
+  fn test_satisfies() {
+    assert!(Type::U8.satisfies(&Type::U8));
+    assert!(Type::I64.satisfies(&Type::I64));
+
+    // no narrowing or widening
+    assert!(!Type::U16.satisfies(&Type::U8));
+    assert!(!Type::U8.satisfies(&Type::U16));
+    assert!(!Type::F32.satisfies(&Type::F64));
+
+    // no signed/unsigned conversion
+    assert!(!Type::I8.satisfies(&Type::U8));
+    assert!(!Type::U32.satisfies(&Type::I32));
+
+    assert!(Type::U8.satisfies(&union!(Type::U8, Type::Bool)));
+    assert!(Type::Char.satisfies(&union!(Type::Char, Type::Bool)));
+    assert!(!union!(Type::Char, Type::Bool).satisfies(&Type::Char));
+    assert!(union!(Type::Char, Type::Bool).satisfies(&union!(Type::Char, Type::Bool)));
+    assert!(union!(Type::Char, Type::Bool).satisfies(&union!(Type::Bool, Type::I32, Type::Char)));
+    assert!(!union!(Type::Char, Type::Bool).satisfies(&union!(Type::Bool, Type::I32)));
+
+    assert!(Type::Never.satisfies(&Type::Char));
+    assert!(Type::Never.satisfies(&Type::Tuple(vec![])));
+    assert!(!Type::Char.satisfies(&Type::Never));
+    assert!(!union!(Type::U8, Type::U16).satisfies(&Type::U8));
+
+    let fixed = |n| Type::FixedArray(Box::new(Type::I32), n);
+    let dynamic = Type::Array(Box::new(Type::I32));
+    assert!(fixed(3).satisfies(&fixed(3)));
+    assert!(!fixed(3).satisfies(&fixed(4)));
+    assert!(fixed(3).satisfies(&dynamic));
+    assert!(!dynamic.satisfies(&fixed(3)));
+
+    let boxed = |ty| Type::Named(vec!["Box".to_string()], vec![ty]);
+    assert!(boxed(Type::Char).satisfies(&boxed(Type::Char)));
+    assert!(!boxed(Type::Char).satisfies(&boxed(Type::I32)));
+    assert!(!boxed(Type::Never).satisfies(&boxed(Type::I32)));
+
+    // parameters go the other way round from the return type
+    let function = |parameter, ty| Type::Function(vec![parameter], Box::new(ty));
+    let wide = union!(Type::I32, Type::Bool);
+    assert!(function(wide.clone(), Type::I32).satisfies(&function(Type::I32, Type::I32)));
+    assert!(!function(Type::I32, Type::I32).satisfies(&function(wide.clone(), Type::I32)));
+    assert!(function(Type::I32, Type::I32).satisfies(&function(Type::I32, wide.clone())));
+    assert!(!function(Type::I32, wide).satisfies(&function(Type::I32, Type::I32)));
+  }
+
+  #[test]
+  fn test_hash() {
+    let hash = |ty: &Type| {
+      let mut hasher = std::collections::hash_map::DefaultHasher::new();
+      ty.hash(&mut hasher);
+      hasher.finish()
+    };
+
+    // types that compare equal hash the same, however they're written
+    let parenthesized = Type::Array(Box::new(Type::Tuple(vec![Type::I32])));
+    assert_eq!(parenthesized, Type::Array(Box::new(Type::I32)));
+    assert_eq!(
+      hash(&parenthesized),
+      hash(&Type::Array(Box::new(Type::I32)))
+    );
+
+    let unsorted = Type::Record(vec![
+      ("y".to_string(), Type::Bool),
+      ("x".to_string(), Type::Tuple(vec![Type::Char])),
+    ]);
+    let sorted = Type::Record(vec![
+      ("x".to_string(), Type::Char),
+      ("y".to_string(), Type::Bool),
+    ]);
+    assert_eq!(unsorted, sorted);
+    assert_eq!(hash(&unsorted), hash(&sorted));
+
+    assert_ne!(hash(&Type::I32), hash(&Type::U32));
+  }
+
+  #[test]
+  fn test_display() {
+    assert_eq!(Type::Bool.to_string(), "bool");
+    assert_eq!(Type::Char.to_string(), "char");
+    assert_eq!(Type::I32.to_string(), "i32");
+    assert_eq!(Type::U128.to_string(), "u128");
+    assert_eq!(Type::F64.to_string(), "f64");
+    assert_eq!(Type::Never.to_string(), "!");
+    assert_eq!(Type::Tuple(vec![]).to_string(), "()");
+    assert_eq!(
+      Type::Tuple(vec![Type::I8, Type::Bool]).to_string(),
+      "(i8, bool)"
+    );
+    assert_eq!(Type::Array(Box::new(Type::Char)).to_string(), "[char]");
+    assert_eq!(
+      Type::FixedArray(Box::new(Type::Char), 3).to_string(),
+      "[char; 3]"
+    );
+    assert_eq!(
+      Type::Function(vec![Type::I32, Type::Char], Box::new(Type::Bool)).to_string(),
+      "(i32, char) -> bool"
+    );
+    assert_eq!(
+      Type::Function(vec![], Box::new(Type::Tuple(vec![]))).to_string(),
+      "() -> ()"
+    );
+    assert_eq!(
+      Type::Named(
+        vec!["a".to_string(), "B".to_string()],
+        vec![Type::I32, Type::Named(vec!["C".to_string()], vec![])]
+      )
+      .to_string(),
+      "a::B<i32, C>"
+    );
+    assert_eq!(union!(Type::Bool, Type::Char).to_string(), "bool | char");
+    assert_eq!(
+      Type::Reference(Box::new(Type::Char), true).to_string(),
+      "&mut char"
+    );
+  }
+
+  #[test]
+  fn test_mismatch() {
+    let tuple = |types: &[Type]| Type::Tuple(types.to_vec());
+    let expected = tuple(&[
+      Type::I32,
+      tuple(&[Type::Char, Type::Bool, Type::U8]),
+      Type::Array(Box::new(Type::F64)),
+      Type::Bool,
+    ]);
+    let found = tuple(&[
+      Type::I32,
+      tuple(&[Type::Char, Type::I32, Type::U8]),
+      Type::Array(Box::new(Type::F64)),
+      Type::Bool,
+    ]);
+
+    assert_eq!(
+      found.mismatch(&expected),
+      (
+        vec![
+          "2nd tuple element".to_string(),
+          "2nd tuple element".to_string()
+        ],
+        Type::I32,
+        Type::Bool
+      )
+    );
+
+    let function =
+      |parameters: &[Type], ty: Type| Type::Function(parameters.to_vec(), Box::new(ty));
+    assert_eq!(
+      function(&[Type::I32], Type::FixedArray(Box::new(Type::Char), 2))
+        .mismatch(&function(&[Type::I32], Type::Array(Box::new(Type::Bool)))),
+      (
+        vec!["return type".to_string(), "element type".to_string()],
+        Type::Char,
+        Type::Bool
+      )
+    );
+
+    // different shapes are the mismatch themselves
+    let short = tuple(&[Type::I32, Type::Bool]);
+    assert_eq!(
+      short.mismatch(&expected),
+      (vec![], short.clone(), expected.clone())
+    );
+    assert_eq!(
+      Type::Char.mismatch(&Type::Bool),
+      (vec![], Type::Char, Type::Bool)
+    );
+  }
+
+  #[test]
+  fn test_ordinal() {
+    let ordinals = [1, 2, 3, 4, 11, 12, 13, 21, 102, 111].map(super::ordinal);
+    assert_eq!(
+      ordinals,
+      ["1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "102nd", "111th"]
+    );
+  }
+}
@@ -0,0 +1,224 @@
+use crate::{
+  error::{Error, LexerError},
+  lexer::{lexer::Lexer, token::TokenKind},
+};
+use std::fmt::{self, Display, Formatter};
+
+// a lossless tree of the source, where every token keeps the whitespace and comments around it.
+// it's only grouped by brackets, so that tools can move or rewrite whole groups, and the ast is
+// still what the rest of the compiler works on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+  pub nodes: Vec<Node>,
+  // the trivia after the last token, which there's no token to lead into
+  pub trailing: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+  Token(Token),
+  // `(...)`, `[...]` or `{...}`. a group left open at the end of the source has no closing token,
+  // and a closing bracket without an opening one is a plain token, so any source can be kept
+  Group {
+    open: Token,
+    nodes: Vec<Node>,
+    close: Option<Token>,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+  // the trivia since the end of the previous token's line
+  pub leading: Vec<Trivia>,
+  pub kind: TokenKind,
+  pub text: String,
+  // the trivia up to the end of the token's line, like a comment after it
+  pub trailing: Vec<Trivia>,
+}
+
+// whitespace or a comment, as it was written
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+  pub kind: TokenKind,
+  pub text: String,
+}
+
+impl Cst {
+  pub fn parse(source: &str) -> Result<Cst, Error<LexerError>> {
+    let text = |span: std::ops::Range<usize>| source[span].to_string();
+
+    let mut tokens = Vec::new();
+    let mut leading = Vec::new();
+    // whether the trivia so far is still on the line of the last token
+    let mut same_line = false;
+
+    for (span, kind) in Lexer::new(source).lex(true)? {
+      match kind {
+        TokenKind::Whitespace(_) | TokenKind::Comment(_) => {
+          let trivia = Trivia {
+            kind,
+            text: text(span),
+          };
+          let newline = trivia.text.contains('\n');
+
+          match tokens.last_mut() {
+            Some(Token { trailing, .. }) if same_line && !newline => trailing.push(trivia),
+            _ => leading.push(trivia),
+          }
+
+          same_line &= !newline;
+        }
+        TokenKind::Eof => break,
+        kind => {
+          tokens.push(Token {
+            leading: std::mem::take(&mut leading),
+            kind,
+            text: text(span),
+            trailing: Vec::new(),
+          });
+          same_line = true;
+        }
+      }
+    }
+
+    let mut tokens = tokens.into_iter().peekable();
+    let mut nodes = Vec::new();
+
+    while tokens.peek().is_some() {
+      nodes.push(Cst::node(&mut tokens));
+    }
+
+    Ok(Cst {
+      nodes,
+      trailing: leading,
+    })
+  }
+
+  fn node(tokens: &mut std::iter::Peekable<impl Iterator<Item = Token>>) -> Node {
+    let open = tokens.next().unwrap();
+
+    let closing = match open.kind {
+      TokenKind::LeftParen => TokenKind::RightParen,
+      TokenKind::LeftBracket => TokenKind::RightBracket,
+      TokenKind::LeftBrace => TokenKind::RightBrace,
+      _ => return Node::Token(open),
+    };
+
+    let mut nodes = Vec::new();
+
+    let close = loop {
+      match tokens.peek() {
+        Some(token) if token.kind == closing => break tokens.next(),
+        Some(_) => nodes.push(Cst::node(tokens)),
+        None => break None,
+      }
+    };
+
+    Node::Group { open, nodes, close }
+  }
+}
+
+impl Display for Cst {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    for node in &self.nodes {
+      write!(f, "{}", node)?;
+    }
+
+    for trivia in &self.trailing {
+      write!(f, "{}", trivia.text)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Display for Node {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Node::Token(token) => write!(f, "{}", token),
+      Node::Group { open, nodes, close } => {
+        write!(f, "{}", open)?;
+
+        for node in nodes {
+          write!(f, "{}", node)?;
+        }
+
+        match close {
+          Some(close) => write!(f, "{}", close),
+          None => Ok(()),
+        }
+      }
+    }
+  }
+}
+
+impl Display for Token {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    for trivia in &self.leading {
+      write!(f, "{}", trivia.text)?;
+    }
+
+    write!(f, "{}", self.text)?;
+
+    for trivia in &self.trailing {
+      write!(f, "{}", trivia.text)?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_round_trip() {
+    let sources = [
+      "",
+      "  \n",
+      "fn main(): () ();",
+      "// leading\nfn f(x: i32): i32 { /* inline */ x + 1 } // trailing\n\n/* a /* nested */ one */\n",
+      "/// docs\nstruct Point {\n  x: i32; // the x\n\n  y: i32;\n};\n",
+      "fn f(): char 'é'; // not ascii\n",
+      // unbalanced brackets are kept as they are
+      "fn f( { ] }",
+    ];
+
+    for source in sources {
+      assert_eq!(Cst::parse(source).unwrap().to_string(), source);
+    }
+  }
+
+  #[test]
+  fn test_trivia() {
+    let cst = Cst::parse("a // one\n// two\n(b) ").unwrap();
+    let token = |node: &Node| match node {
+      Node::Token(token) => token.clone(),
+      _ => panic!(),
+    };
+
+    // a comment on the same line trails the token before it, and the ones after the line lead into
+    // the next token
+    let a = token(&cst.nodes[0]);
+    assert_eq!(
+      a.trailing.iter().map(|t| &t.text[..]).collect::<Vec<_>>(),
+      vec![" ", "// one"]
+    );
+
+    match &cst.nodes[1] {
+      Node::Group { open, nodes, close } => {
+        assert_eq!(
+          open.leading.iter().map(|t| &t.text[..]).collect::<Vec<_>>(),
+          vec!["\n", "// two", "\n"]
+        );
+        assert_eq!(token(&nodes[0]).text, "b");
+        assert_eq!(close.as_ref().unwrap().kind, TokenKind::RightParen);
+        assert_eq!(close.as_ref().unwrap().trailing[0].text, " ");
+      }
+      _ => panic!(),
+    }
+
+    assert_eq!(cst.trailing, vec![]);
+  }
+}
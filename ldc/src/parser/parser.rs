@@ -4,18 +4,87 @@ use crate::{
   expect,
   lexer::token::{NumericType, Token, TokenKind},
 };
-use std::{collections::BTreeSet, iter::Peekable, slice::Iter};
+use std::{collections::HashMap, slice::Iter};
 
 type Type = util::Type<Vec<String>>;
 
+// the tokens being parsed, where doc comments are trivia. the ones right before a token are set
+// aside for an item to take as its docs, and dropped once the token is read
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+  tokens: Iter<'a, Token>,
+  next: Option<&'a Token>,
+  last: Option<&'a Token>,
+  docs: Vec<String>,
+}
+
+impl<'a> Tokens<'a> {
+  pub fn new(tokens: Iter<'a, Token>) -> Tokens<'a> {
+    let mut tokens = Tokens {
+      tokens,
+      next: None,
+      last: None,
+      docs: Vec::new(),
+    };
+    tokens.advance();
+    tokens
+  }
+
+  fn advance(&mut self) {
+    self.next = loop {
+      match self.tokens.next() {
+        Some((_, TokenKind::DocComment(doc))) => self.docs.push(doc.to_string()),
+        token => break token,
+      }
+    };
+  }
+
+  pub fn peek(&self) -> Option<&&'a Token> {
+    self.next.as_ref()
+  }
+
+  // the doc comments before the next token
+  pub fn docs(&mut self) -> Vec<String> {
+    std::mem::take(&mut self.docs)
+  }
+
+  // how many tokens are left, counting doc comments
+  fn len(&self) -> usize {
+    self.tokens.len() + usize::from(self.next.is_some())
+  }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+  type Item = &'a Token;
+
+  fn next(&mut self) -> Option<&'a Token> {
+    let token = self.next?;
+    self.docs.clear();
+    self.advance();
+    self.last = Some(token);
+    Some(token)
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct Parser<'a> {
-  pub tokens: Peekable<Iter<'a, Token>>,
+  pub tokens: Tokens<'a>,
+  // whether to wrap expressions in the spans they were parsed from, for tools that need positions
+  pub spans: bool,
+  // the values of the constants parsed so far, since array lengths are folded while parsing
+  pub constants: HashMap<String, i128>,
+  // cleared while parsing a condition, where `a {` starts the body rather than a struct literal
+  struct_literals: bool,
 }
 
 impl Parser<'_> {
-  pub fn new(tokens: Peekable<Iter<Token>>) -> Parser {
-    Parser { tokens }
+  pub fn new(tokens: Iter<Token>) -> Parser {
+    Parser {
+      tokens: Tokens::new(tokens),
+      spans: false,
+      constants: HashMap::new(),
+      struct_literals: true,
+    }
   }
 
   pub fn error(&self, token: Option<&Token>, error: ParserError) -> Error<ParserError> {
@@ -87,18 +156,26 @@ impl Parser<'_> {
     let mut items = Vec::new();
 
     match self.tokens.peek().copied() {
-      Some(&(_, ref token)) if *token == end => {
+      Some((_, token)) if *token == end => {
         self.tokens.next();
       }
       Some(_) => {
         items.push(parse(self)?);
         loop {
           match self.tokens.peek().cloned() {
-            Some(&(_, ref token)) if *token == separator => {
+            Some((_, token)) if *token == separator => {
               self.tokens.next();
-              items.push(parse(self)?);
+
+              // the last item may be followed by a separator
+              match self.tokens.peek().copied() {
+                Some((_, token)) if *token == end => {
+                  self.tokens.next();
+                  break;
+                }
+                _ => items.push(parse(self)?),
+              }
             }
-            Some(&(_, ref token)) if *token == end => {
+            Some((_, token)) if *token == end => {
               self.tokens.next();
               break;
             }
@@ -123,23 +200,20 @@ impl Parser<'_> {
     let mut clone = self.clone();
     let mut items = Vec::new();
 
-    match clone.tokens.peek().copied() {
-      Some(_) => {
-        items.push(parse(&mut clone)?);
-        loop {
-          match clone.tokens.peek().cloned() {
-            Some((_, ref token)) if *token == separator => {
-              clone.tokens.next();
-              match parse(&mut clone) {
-                Ok(item) => items.push(item),
-                Err(_) => break,
-              }
+    if clone.tokens.peek().is_some() {
+      items.push(parse(&mut clone)?);
+      loop {
+        match clone.tokens.peek().cloned() {
+          Some((_, ref token)) if *token == separator => {
+            clone.tokens.next();
+            match parse(&mut clone) {
+              Ok(item) => items.push(item),
+              Err(_) => break,
             }
-            _ => break,
           }
+          _ => break,
         }
       }
-      None => (),
     }
 
     std::mem::swap(&mut self.tokens, &mut clone.tokens);
@@ -212,22 +286,178 @@ impl Parser<'_> {
     modifiers
   }
 
+  // the value of `return` or `break`, which is left out if the next token would end an expression
+  fn parse_optional_value(
+    &mut self,
+  ) -> Result<Option<Box<util::Expression<Type>>>, Error<ParserError>> {
+    match self.tokens.peek() {
+      None
+      | Some((
+        _,
+        TokenKind::Semicolon
+        | TokenKind::Comma
+        | TokenKind::RightParen
+        | TokenKind::RightBrace
+        | TokenKind::RightBracket
+        | TokenKind::Else
+        | TokenKind::Eof,
+      )) => Ok(None),
+      _ => Ok(Some(Box::new(self.parse_expression()?))),
+    }
+  }
+
+  // the rest of a `loop`, `while` or `for`, after its keyword
+  fn parse_loop(
+    &mut self,
+    kind: &TokenKind,
+    label: Option<String>,
+  ) -> Result<util::Expression<Type>, Error<ParserError>> {
+    match kind {
+      TokenKind::Loop => Ok(util::Expression::Loop {
+        label,
+        body: Box::new(self.parse_expression()?),
+      }),
+      TokenKind::While => {
+        let condition = Box::new(self.parse_condition()?);
+        let body = Box::new(self.parse_expression()?);
+
+        Ok(util::Expression::While {
+          label,
+          condition,
+          body,
+        })
+      }
+      TokenKind::For => {
+        let pattern = self.parse_pattern()?;
+        self.expect(vec![TokenKind::In])?;
+        let iterable = Box::new(self.parse_condition()?);
+        let body = Box::new(self.parse_expression()?);
+
+        Ok(util::Expression::For {
+          label,
+          pattern,
+          iterable,
+          body,
+        })
+      }
+      _ => unreachable!(),
+    }
+  }
+
+  // the label after `break` or `continue`, if there is one
+  fn parse_label(&mut self) -> Option<String> {
+    match self.tokens.peek() {
+      Some((_, TokenKind::Label(label))) => {
+        self.tokens.next();
+        Some(label.clone())
+      }
+      _ => None,
+    }
+  }
+
+  // `t.0.1` lexes as `t`, `.` and the float `0.1`, so a float stands for two indices
+  fn parse_tuple_index(&mut self) -> Result<Vec<usize>, Error<ParserError>> {
+    let token = self.tokens.next();
+
+    match token {
+      Some((_, TokenKind::NumberLiteral(value, NumericType::Integer | NumericType::F64))) => value
+        .split('.')
+        .map(|index| {
+          if index.chars().all(|c| c.is_ascii_digit()) {
+            index.parse().ok()
+          } else {
+            None
+          }
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| self.error(token, ParserError::InvalidTupleIndex(value.clone()))),
+      Some((_, TokenKind::NumberLiteral(value, _))) => {
+        Err(self.error(token, ParserError::InvalidTupleIndex(value.clone())))
+      }
+      token => Err(self.unexpected_token(
+        token,
+        vec![TokenKind::NumberLiteral(
+          "".to_string(),
+          NumericType::Integer,
+        )],
+      )),
+    }
+  }
+
+  pub fn parse_docs(&mut self) -> Vec<String> {
+    self.tokens.docs()
+  }
+
   fn parse_item(&mut self, allow_static: bool) -> Result<module::Item<Type>, Error<ParserError>> {
+    let docs = self.parse_docs();
+    let start = self.tokens.clone();
     let modifiers = self.parse_modifiers(allow_static);
 
-    let kind = expect! {
-      self,
-      false,
-      TokenKind::Fn = TokenKind::Fn => { module::ItemKind::Function(self.parse_function()?) },
-      TokenKind::Struct = TokenKind::Struct => { module::ItemKind::Struct(self.parse_struct()?) },
-      TokenKind::Enum = TokenKind::Enum => { module::ItemKind::Enum(self.parse_enum()?) },
-      TokenKind::Trait = TokenKind::Trait => { module::ItemKind::Trait(self.parse_trait()?) },
-      TokenKind::Operator(_) = TokenKind::Operator("".to_string()) => { module::ItemKind::Operator(self.parse_operator()?) }
-    }?;
+    // fields belong to instances, so they're only declared in struct bodies and can't be static
+    let kind = match self.tokens.peek() {
+      Some((_, TokenKind::Identifier(_))) if allow_static && !modifiers.static_ => {
+        self.parse_field()?
+      }
+      _ => expect! {
+        self,
+        false,
+        TokenKind::Fn = TokenKind::Fn => { module::ItemKind::Function(self.parse_function()?) },
+        TokenKind::Struct = TokenKind::Struct => { module::ItemKind::Struct(self.parse_struct()?) },
+        TokenKind::Enum = TokenKind::Enum => { module::ItemKind::Enum(self.parse_enum()?) },
+        TokenKind::Trait = TokenKind::Trait => { module::ItemKind::Trait(self.parse_trait()?) },
+        TokenKind::Impl = TokenKind::Impl => { self.parse_impl()? },
+        TokenKind::Operator(_) = TokenKind::Operator("".to_string()) => { module::ItemKind::Operator(self.parse_operator()?) },
+        TokenKind::Const = TokenKind::Const => { self.parse_const()? }
+      }?,
+    };
 
     self.expect(vec![TokenKind::Semicolon])?;
 
-    Ok(module::Item { modifiers, kind })
+    Ok(module::Item {
+      docs,
+      modifiers,
+      kind,
+      span: self.span(&start),
+    })
+  }
+
+  fn parse_const(&mut self) -> Result<module::ItemKind<Type>, Error<ParserError>> {
+    self.expect(vec![TokenKind::Const])?;
+    let name = self.expect_identifier()?;
+    let ty = self.parse_type_annotation(false)?.unwrap();
+    self.expect_operator(vec!["="])?;
+    let value = self.parse_expression()?;
+
+    if let Some(n) = value.const_eval_with(&|name| self.constants.get(name).copied()) {
+      self.constants.insert(name.clone(), n);
+    }
+
+    Ok(module::ItemKind::Const { name, ty, value })
+  }
+
+  // an optional `mut`, after `let` or `&`
+  fn parse_mut(&mut self) -> bool {
+    let mutable = matches!(self.tokens.peek(), Some((_, TokenKind::Mut)));
+    if mutable {
+      self.tokens.next();
+    }
+
+    mutable
+  }
+
+  fn parse_field(&mut self) -> Result<module::ItemKind<Type>, Error<ParserError>> {
+    let name = self.expect_identifier()?;
+    let ty = self.parse_type_annotation(false)?.unwrap();
+
+    let default = match self.tokens.peek() {
+      Some((_, TokenKind::Operator(operator))) if operator == "=" => {
+        self.tokens.next();
+        Some(self.parse_expression()?)
+      }
+      _ => None,
+    };
+
+    Ok(module::ItemKind::Field { name, ty, default })
   }
 
   fn parse_function(&mut self) -> Result<function::Function<Type>, Error<ParserError>> {
@@ -239,10 +469,30 @@ impl Parser<'_> {
     self.expect(vec![TokenKind::LeftParen])?;
 
     let parameters = self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
-      parser.parse_parameter()
+      let mut parameter = parser.parse_parameter()?;
+
+      match parser.tokens.peek() {
+        Some((_, TokenKind::Operator(operator))) if operator == "=" => {
+          parser.tokens.next();
+          parameter.default = Some(parser.parse_expression()?);
+        }
+        // a rest parameter takes the remaining arguments, so it has to be the last one
+        Some((_, TokenKind::Operator(operator))) if operator == "..." => {
+          parser.tokens.next();
+          parameter.rest = true;
+
+          match parser.tokens.peek().copied() {
+            Some((_, TokenKind::RightParen)) => (),
+            token => Err(parser.unexpected_token(token, vec![TokenKind::RightParen]))?,
+          }
+        }
+        _ => (),
+      }
+
+      Ok(parameter)
     })?;
 
-    let ty = self.parse_type_annotation(true)?;
+    let ty = self.parse_return_type(true)?;
 
     let header = function::Header {
       name,
@@ -256,21 +506,39 @@ impl Parser<'_> {
     Ok(function::Function { header, body })
   }
 
-  fn parse_struct(&mut self) -> Result<r#struct::Struct<Type>, Error<ParserError>> {
-    self.expect(vec![TokenKind::Struct])?;
+  fn parse_impl(&mut self) -> Result<module::ItemKind<Type>, Error<ParserError>> {
+    self.expect(vec![TokenKind::Impl])?;
 
-    let name = self.expect_identifier()?;
-    // let type_parameters = self.parse_type_parameter()?;
-    let traits = self.parse_traits()?;
+    // the struct is named by its path, with names for its type parameters as the arguments. they
+    // stand for whatever the struct is instantiated with, so nothing more specific can be given
+    let path = self.expect_identifier_list(TokenKind::Operator("::".to_string()))?;
+    let ty = Type::Named(
+      path,
+      match self.tokens.peek() {
+        Some((_, TokenKind::Operator(operator))) if operator == "<" => {
+          self.tokens.next();
+          self.expect_list(
+            TokenKind::Operator(">".to_string()),
+            TokenKind::Comma,
+            |parser| Ok(Type::Named(vec![parser.expect_identifier()?], vec![])),
+          )?
+        }
+        _ => Vec::new(),
+      },
+    );
 
-    let header = r#struct::Header {
-      name,
-      // type_parameters,
-      traits,
-    };
+    Ok(module::ItemKind::Impl {
+      ty,
+      module: self.parse_struct_body()?,
+    })
+  }
 
+  // the items between the braces of a struct or an impl block
+  // the constants declared in the body are only in scope there
+  fn parse_struct_body(&mut self) -> Result<module::Module<Type>, Error<ParserError>> {
     self.expect(vec![TokenKind::LeftBrace])?;
 
+    let outer = self.constants.clone();
     let mut items = Vec::new();
 
     loop {
@@ -283,9 +551,27 @@ impl Parser<'_> {
       }
     }
 
+    self.constants = outer;
+
+    Ok(module::Module { items })
+  }
+
+  fn parse_struct(&mut self) -> Result<r#struct::Struct<Type>, Error<ParserError>> {
+    self.expect(vec![TokenKind::Struct])?;
+
+    let name = self.expect_identifier()?;
+    let type_parameters = self.parse_type_parameter()?;
+    let traits = self.parse_traits()?;
+
+    let header = r#struct::Header {
+      name,
+      type_parameters,
+      traits,
+    };
+
     Ok(r#struct::Struct {
       header,
-      module: module::Module { items },
+      module: self.parse_struct_body()?,
     })
   }
 
@@ -308,20 +594,38 @@ impl Parser<'_> {
     Ok(r#enum::Enum { header, variants })
   }
 
+  // variants don't keep their docs
   fn parse_enum_variant(&mut self) -> Result<r#enum::Variant<Type>, Error<ParserError>> {
     let name = self.expect_identifier()?;
 
-    let fields = match self.tokens.peek() {
+    let (names, fields) = match self.tokens.peek() {
       Some((_, TokenKind::LeftParen)) => {
         self.tokens.next();
-        self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
+        let fields = self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
           parser.parse_type()
-        })?
+        })?;
+
+        (Vec::new(), fields)
+      }
+      Some((_, TokenKind::LeftBrace)) => {
+        self.tokens.next();
+        self
+          .expect_list(TokenKind::RightBrace, TokenKind::Comma, |parser| {
+            let name = parser.expect_identifier()?;
+            let ty = parser.parse_type_annotation(false)?.unwrap();
+            Ok((name, ty))
+          })?
+          .into_iter()
+          .unzip()
       }
-      _ => Vec::new(),
+      _ => (Vec::new(), Vec::new()),
     };
 
-    Ok(r#enum::Variant { name, fields })
+    Ok(r#enum::Variant {
+      name,
+      fields,
+      names,
+    })
   }
 
   fn parse_trait(&mut self) -> Result<r#trait::Trait<Type>, Error<ParserError>> {
@@ -347,7 +651,10 @@ impl Parser<'_> {
           self.tokens.next();
           break;
         }
-        _ => items.push((self.parse_modifiers(true), self.parse_trait_item()?)),
+        _ => {
+          // TODO: keep docs on trait items
+          items.push((self.parse_modifiers(true), self.parse_trait_item()?))
+        }
       }
     }
 
@@ -368,7 +675,7 @@ impl Parser<'_> {
           parser.parse_type()
         })?;
 
-        let ty = self.parse_type_annotation(false)?.unwrap();
+        let ty = self.parse_return_type(false)?.unwrap();
 
         r#trait::Item::Function(r#trait::Function { name, parameters, ty })
       },
@@ -413,28 +720,29 @@ impl Parser<'_> {
           _ => r#trait::Item::Trait(r#trait::Trait_ { name, traits })
         }
       },
-      TokenKind::Operator(_) = TokenKind::Operator("".to_string()) => {
-        let operator = match self.tokens.next() {
-          Some((_, TokenKind::Operator(operator))) => operator,
-          token => Err(self.unexpected_token(token, vec![TokenKind::Operator("".to_string())]))?,
-        }
-        .to_string();
+      TokenKind::Operator(operator) = TokenKind::Operator("".to_string()) => {
+        let operator = operator.to_string();
         // let type_parameters = self.parse_type_parameter()?;
 
         self.expect(vec![TokenKind::LeftParen])?;
 
         let a = self.parse_type()?;
-        let b = match self.tokens.peek() {
-          Some((_, TokenKind::Comma)) => {
+        let b = match (self.tokens.peek().copied(), self.tokens.clone().nth(1)) {
+          (Some((_, TokenKind::Comma)), Some((_, TokenKind::RightParen))) => None,
+          (Some((_, TokenKind::Comma)), _) => {
             self.tokens.next();
             Some(self.parse_type()?)
           }
           _ => None,
         };
 
+        // the operands may be followed by a comma
+        if let Some((_, TokenKind::Comma)) = self.tokens.peek() {
+          self.tokens.next();
+        }
         self.expect(vec![TokenKind::RightParen])?;
 
-        let result = self.parse_type_annotation(false)?.unwrap();
+        let result = self.parse_return_type(false)?.unwrap();
         r#trait::Item::Operator(match b {
           Some(b) => r#trait::Operator::Infix {
             operator,
@@ -467,17 +775,22 @@ impl Parser<'_> {
     self.expect(vec![TokenKind::LeftParen])?;
 
     let a = self.parse_parameter()?;
-    let b = match self.tokens.peek() {
-      Some((_, TokenKind::Comma)) => {
+    let b = match (self.tokens.peek().copied(), self.tokens.clone().nth(1)) {
+      (Some((_, TokenKind::Comma)), Some((_, TokenKind::RightParen))) => None,
+      (Some((_, TokenKind::Comma)), _) => {
         self.tokens.next();
         Some(self.parse_parameter()?)
       }
       _ => None,
     };
 
+    // the operands may be followed by a comma
+    if let Some((_, TokenKind::Comma)) = self.tokens.peek() {
+      self.tokens.next();
+    }
     self.expect(vec![TokenKind::RightParen])?;
 
-    let result = self.parse_type_annotation(false)?.unwrap();
+    let result = self.parse_return_type(false)?.unwrap();
     let header = match b {
       Some(b) => operator::Header::Infix(operator::Infix {
         operator,
@@ -503,18 +816,117 @@ impl Parser<'_> {
 
     let ty = self.parse_type()?;
 
-    Ok(util::Parameter { name, ty })
+    Ok(util::Parameter {
+      name,
+      ty,
+      default: None,
+      rest: false,
+    })
+  }
+
+  fn parse_argument(&mut self) -> Result<util::Argument<Type>, Error<ParserError>> {
+    let name = match (self.tokens.peek().copied(), self.tokens.clone().nth(1)) {
+      (Some((_, TokenKind::Identifier(name))), Some((_, TokenKind::Operator(operator))))
+        if operator == ":" =>
+      {
+        self.tokens.next();
+        self.tokens.next();
+        Some(name.clone())
+      }
+      _ => None,
+    };
+
+    Ok(util::Argument {
+      name,
+      value: self.parse_expression()?,
+    })
   }
 
-  fn parse_expression(&mut self) -> Result<util::Expression<Type>, Error<ParserError>> {
+  pub fn parse_expression(&mut self) -> Result<util::Expression<Type>, Error<ParserError>> {
     self.parse_expression_with_precedence(0)
   }
 
+  // the expression after `if`, `while`, `in` or `match`, which is followed by a body
+  fn parse_condition(&mut self) -> Result<util::Expression<Type>, Error<ParserError>> {
+    self.with_struct_literals(false, |parser| parser.parse_expression())
+  }
+
+  // struct literals are allowed again between delimiters, even in a condition
+  fn with_struct_literals<R>(&mut self, allowed: bool, f: impl FnOnce(&mut Self) -> R) -> R {
+    let previous = std::mem::replace(&mut self.struct_literals, allowed);
+    let result = f(self);
+    self.struct_literals = previous;
+    result
+  }
+
+  fn parse_struct_literal(
+    &mut self,
+    path: util::Path,
+  ) -> Result<util::Expression<Type>, Error<ParserError>> {
+    self.expect(vec![TokenKind::LeftBrace])?;
+
+    let fields = self.with_struct_literals(true, |parser| {
+      parser.expect_list(TokenKind::RightBrace, TokenKind::Comma, |parser| {
+        let name = parser.expect_identifier()?;
+
+        let value = match parser.tokens.peek() {
+          Some((_, TokenKind::Operator(operator))) if operator == ":" => {
+            parser.tokens.next();
+            parser.parse_expression()?
+          }
+          _ => util::Expression::Identifier(name.clone()),
+        };
+
+        Ok((name, value))
+      })
+    })?;
+
+    Ok(util::Expression::StructLiteral { path, fields })
+  }
+
+  // the span of the tokens consumed since `start`, if spans are kept
+  fn span(&self, start: &Tokens) -> Option<std::ops::Range<usize>> {
+    match (self.spans, start.peek(), self.tokens.last) {
+      (true, Some((first, _)), Some((last, _))) if start.len() > self.tokens.len() => {
+        Some(first.start..last.end)
+      }
+      _ => None,
+    }
+  }
+
+  // wraps an expression in the span of the tokens consumed since `start`
+  fn spanned(&self, start: &Tokens, expression: util::Expression<Type>) -> util::Expression<Type> {
+    match self.span(start) {
+      Some(span) => util::Expression::Spanned(span, Box::new(expression)),
+      None => expression,
+    }
+  }
+
   fn parse_expression_with_precedence(
     &mut self,
     precedence: u8,
   ) -> Result<util::Expression<Type>, Error<ParserError>> {
+    let start = self.tokens.clone();
     let mut expression = match self.tokens.next() {
+      // like in types, `&&x` borrows a borrow
+      Some((_, TokenKind::Operator(operator))) if operator == "&" || operator == "&&" => {
+        let double = operator == "&&";
+        let borrow = if self.parse_mut() { "&mut" } else { "&" };
+        let precedence = TokenKind::Operator(operator.clone()).prefix_precedence();
+        let expression = util::Expression::Prefix {
+          operator: borrow.to_string(),
+          operand: Box::new(self.parse_expression_with_precedence(precedence)?),
+        };
+
+        Ok(if double {
+          util::Expression::Prefix {
+            operator: "&".to_string(),
+            operand: Box::new(expression),
+          }
+        } else {
+          expression
+        })
+      }
       Some((_, TokenKind::Operator(operator))) => {
         let operator = operator.to_string();
         let op = TokenKind::Operator(operator.clone());
@@ -523,10 +935,51 @@ impl Parser<'_> {
           operand: Box::new(self.parse_expression_with_precedence(op.prefix_precedence())?),
         })
       }
-      Some((_, TokenKind::Identifier(name))) => Ok(util::Expression::Identifier(name.clone())),
-      Some((_, TokenKind::Let)) => Ok(util::Expression::Declaration(self.expect_identifier()?)),
+      // `_` only ever appears in patterns, since there's nothing bound to it
+      Some(token @ (_, TokenKind::Identifier(name))) if name == "_" => {
+        Err(self.error(Some(token), ParserError::WildcardExpression))
+      }
+      Some(token @ (_, TokenKind::FatArrow)) => {
+        Err(self.error(Some(token), ParserError::FatArrowExpression))
+      }
+      Some((_, TokenKind::Identifier(name))) => {
+        let path = match self.tokens.peek() {
+          Some((_, TokenKind::Operator(operator))) if operator == "::" => {
+            self.tokens.next();
+            let mut path = vec![name.clone()];
+            path.append(&mut self.expect_identifier_list(TokenKind::Operator("::".to_string()))?);
+            path
+          }
+          _ => vec![name.clone()],
+        };
+
+        match self.tokens.peek() {
+          Some((_, TokenKind::LeftBrace)) if self.struct_literals => {
+            self.parse_struct_literal(path)
+          }
+          _ if path.len() > 1 => Ok(util::Expression::Path(path)),
+          _ => Ok(util::Expression::Identifier(name.clone())),
+        }
+      }
+      // `self` can't be declared by hand, so it's safe to treat as an identifier
+      Some((_, TokenKind::Self_)) => Ok(util::Expression::Identifier("self".to_string())),
+      Some((_, TokenKind::Let)) => {
+        let mutable = self.parse_mut();
+
+        let pattern = self.parse_pattern()?;
+        let ty = self.parse_type_annotation(true)?;
+        self.expect_operator(vec!["="])?;
+        let value = Box::new(self.parse_expression()?);
+
+        Ok(util::Expression::Let {
+          mutable,
+          pattern,
+          ty,
+          value,
+        })
+      }
       Some((_, TokenKind::If)) => {
-        let condition = Box::new(self.parse_expression()?);
+        let condition = Box::new(self.parse_condition()?);
         let consequence = Box::new(self.parse_expression()?);
         let alternative = match self.tokens.peek() {
           Some((_, TokenKind::Else)) => {
@@ -542,14 +995,51 @@ impl Parser<'_> {
           alternative,
         })
       }
-      Some((_, TokenKind::While)) => {
-        let condition = Box::new(self.parse_expression()?);
-        let body = Box::new(self.parse_expression()?);
+      Some((_, kind @ (TokenKind::Loop | TokenKind::While | TokenKind::For))) => {
+        self.parse_loop(kind, None)
+      }
+      Some((_, TokenKind::Label(label))) => {
+        self.expect_operator(vec![":"])?;
+        let (_, kind) = self.expect(vec![TokenKind::Loop, TokenKind::While, TokenKind::For])?;
+        self.parse_loop(&kind, Some(label.clone()))
+      }
+      Some((_, TokenKind::Match)) => {
+        let scrutinee = Box::new(self.parse_condition()?);
+        self.expect(vec![TokenKind::LeftBrace])?;
+
+        let arms = self.expect_list(TokenKind::RightBrace, TokenKind::Comma, |parser| {
+          let pattern = parser.parse_pattern()?;
+          parser.expect(vec![TokenKind::FatArrow])?;
+          Ok((pattern, parser.parse_expression()?))
+        })?;
 
-        Ok(util::Expression::While { condition, body })
+        Ok(util::Expression::Match { scrutinee, arms })
       }
-      Some((_, TokenKind::Return)) => {
-        Ok(util::Expression::Return(Box::new(self.parse_expression()?)))
+      Some((_, TokenKind::Return)) => Ok(util::Expression::Return(self.parse_optional_value()?)),
+      Some((_, TokenKind::Break)) => Ok(util::Expression::Break {
+        label: self.parse_label(),
+        value: self.parse_optional_value()?,
+      }),
+      Some((_, TokenKind::Continue)) => Ok(util::Expression::Continue {
+        label: self.parse_label(),
+      }),
+      // `{ x: ...` can't start a block, so it's a record
+      Some((_, TokenKind::LeftBrace))
+        if matches!(
+          (self.tokens.peek().copied(), self.tokens.clone().nth(1)),
+          (Some((_, TokenKind::Identifier(_))), Some((_, TokenKind::Operator(operator))))
+            if operator == ":"
+        ) =>
+      {
+        let fields = self.with_struct_literals(true, |parser| {
+          parser.expect_list(TokenKind::RightBrace, TokenKind::Comma, |parser| {
+            let name = parser.expect_identifier()?;
+            parser.expect_operator(vec![":"])?;
+            Ok((name, parser.parse_expression()?))
+          })
+        })?;
+
+        Ok(util::Expression::Literal(util::Literal::Record(fields)))
       }
       Some((_, TokenKind::LeftBrace)) => {
         let mut ended = false;
@@ -593,9 +1083,9 @@ impl Parser<'_> {
       Some((_, TokenKind::StringLiteral(value))) => Ok(util::Expression::Literal(
         util::Literal::String(value.clone()),
       )),
-      Some((_, TokenKind::CharLiteral(value))) => Ok(util::Expression::Literal(
-        util::Literal::Char(value.clone()),
-      )),
+      Some((_, TokenKind::CharLiteral(value))) => {
+        Ok(util::Expression::Literal(util::Literal::Char(*value)))
+      }
       Some((span, TokenKind::NumberLiteral(value, ty))) => Ok(util::Expression::Literal(
         util::NumberLiteral::from_string(value, ty.clone()).map_err(|e| {
           self.error(
@@ -611,10 +1101,20 @@ impl Parser<'_> {
         self.expect(vec![TokenKind::LeftParen])?;
 
         let parameters = self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
-          parser.parse_parameter()
+          let name = parser.expect_identifier()?;
+
+          let ty = match parser.tokens.peek() {
+            Some((_, TokenKind::Operator(operator))) if operator == ":" => {
+              parser.tokens.next();
+              Some(parser.parse_type()?)
+            }
+            _ => None,
+          };
+
+          Ok(util::ClosureParameter { name, ty })
         })?;
 
-        let ty = self.parse_type_annotation(true)?;
+        let ty = self.parse_return_type(true)?;
         let body = self.parse_expression()?;
 
         Ok(util::Expression::Literal(util::Literal::Closure {
@@ -624,13 +1124,17 @@ impl Parser<'_> {
         }))
       }
       Some((_, TokenKind::LeftParen)) => Ok(util::Expression::Literal(util::Literal::Tuple(
-        self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
-          parser.parse_expression()
+        self.with_struct_literals(true, |parser| {
+          parser.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
+            parser.parse_expression()
+          })
         })?,
       ))),
       Some((_, TokenKind::LeftBracket)) => Ok(util::Expression::Literal(util::Literal::Array(
-        self.expect_list(TokenKind::RightBracket, TokenKind::Comma, |parser| {
-          parser.parse_expression()
+        self.with_struct_literals(true, |parser| {
+          parser.expect_list(TokenKind::RightBracket, TokenKind::Comma, |parser| {
+            parser.parse_expression()
+          })
         })?,
       ))),
       Some((_, TokenKind::True)) => Ok(util::Expression::Literal(util::Literal::Bool(true))),
@@ -639,28 +1143,40 @@ impl Parser<'_> {
         token,
         vec![
           TokenKind::Identifier("".to_string()),
+          TokenKind::Self_,
           TokenKind::If,
+          TokenKind::Loop,
           TokenKind::While,
+          TokenKind::For,
+          TokenKind::Label("".to_string()),
+          TokenKind::Match,
           TokenKind::Return,
+          TokenKind::Break,
+          TokenKind::Continue,
           TokenKind::Let,
           TokenKind::LeftBrace,
           TokenKind::StringLiteral("".to_string()),
           TokenKind::Fn,
           TokenKind::LeftParen,
           TokenKind::LeftBracket,
+          TokenKind::True,
+          TokenKind::False,
         ],
       ))?,
     }?;
+    expression = self.spanned(&start, expression);
 
     loop {
-      match self.tokens.peek() {
+      match self.tokens.peek().copied() {
         // calling and indexing have the highest precedence
         Some((_, TokenKind::LeftParen)) => {
           self.tokens.next();
           expression = util::Expression::Call {
             expression: Box::new(expression),
-            arguments: self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
-              parser.parse_expression()
+            arguments: self.with_struct_literals(true, |parser| {
+              parser.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
+                parser.parse_argument()
+              })
             })?,
           };
         }
@@ -668,24 +1184,81 @@ impl Parser<'_> {
           self.tokens.next();
           expression = util::Expression::Index {
             expression: Box::new(expression),
-            index: Box::new(self.parse_expression()?),
+            index: Box::new(self.with_struct_literals(true, |parser| parser.parse_expression())?),
           };
           self.expect(vec![TokenKind::RightBracket])?;
         }
+        Some((_, TokenKind::Question)) => {
+          self.tokens.next();
+          expression = util::Expression::Try(Box::new(expression));
+        }
+        // so is projecting out of a tuple
+        Some((_, TokenKind::Operator(operator)))
+          if operator == "."
+            && matches!(
+              self.tokens.clone().nth(1),
+              Some((_, TokenKind::NumberLiteral(_, _)))
+            ) =>
+        {
+          self.tokens.next();
+          for index in self.parse_tuple_index()? {
+            expression = util::Expression::TupleIndex {
+              expression: Box::new(expression),
+              index,
+            };
+          }
+        }
+        // and accessing a field, so that `a.b(c)` calls `a.b`
+        Some((_, TokenKind::Operator(operator)))
+          if operator == "."
+            && matches!(
+              self.tokens.clone().nth(1),
+              Some((_, TokenKind::Identifier(_)))
+            ) =>
+        {
+          self.tokens.next();
+          expression = util::Expression::Field {
+            expression: Box::new(expression),
+            field: self.expect_identifier()?,
+          };
+        }
+        // nothing an expression is followed by starts with `=>`, which only belongs in match arms
+        Some(token @ (_, TokenKind::FatArrow)) => {
+          Err(self.error(Some(token), ParserError::FatArrowExpression))?
+        }
+        Some((_, TokenKind::As)) if TokenKind::As.infix_precedence() >= precedence => {
+          self.tokens.next();
+          expression = util::Expression::Cast {
+            value: Box::new(expression),
+            ty: self.parse_type()?,
+          };
+        }
         Some((_, TokenKind::Operator(operator))) => {
           // https://news.ycombinator.com/item?id=13915458
           // TODO: conditional chaining (x == y == z etc.)
           let op = TokenKind::Operator(operator.to_string());
           expression = if op.infix_precedence() >= precedence {
             self.tokens.next();
-            util::Expression::Infix {
-              operator: operator.to_string(),
-              operands: (
-                Box::new(expression),
-                Box::new(self.parse_expression_with_precedence(
-                  op.infix_precedence() + if op.left_associative() { 1 } else { 0 },
-                )?),
-              ),
+            let a = Box::new(expression);
+            let b = Box::new(self.parse_expression_with_precedence(
+              op.infix_precedence() + if op.left_associative() { 1 } else { 0 },
+            )?);
+
+            match operator.as_str() {
+              ".." | "..<" => util::Expression::Range {
+                start: a,
+                end: b,
+                kind: util::RangeKind::Exclusive,
+              },
+              "..=" => util::Expression::Range {
+                start: a,
+                end: b,
+                kind: util::RangeKind::Inclusive,
+              },
+              _ => util::Expression::Infix {
+                operator: operator.to_string(),
+                operands: (a, b),
+              },
             }
           } else {
             break;
@@ -693,33 +1266,130 @@ impl Parser<'_> {
         }
         _ => break,
       };
+
+      expression = self.spanned(&start, expression);
     }
 
     Ok(expression)
   }
 
-  /*
-  fn parse_type_parameter(&mut self) -> Result<Vec<util::TypeParameter>, Error<ParserError>> {
-    match self.tokens.peek() {
-      Some((_, TokenKind::Operator(operator))) if operator == "<" => {
-        self.tokens.next();
-        let parameters = self.expect_list(
-          TokenKind::Operator(">".to_string()),
-          TokenKind::Comma,
-          |parser| {
-            let name = parser.expect_identifier()?;
-            Ok(util::TypeParameter {
-              name,
-              traits: parser.parse_traits()?,
-            })
-          },
-        )?;
-        Ok(parameters)
+  fn parse_pattern(&mut self) -> Result<util::Pattern<Type>, Error<ParserError>> {
+    match self.tokens.next() {
+      Some((_, TokenKind::Identifier(name))) if name == "_" => Ok(util::Pattern::Wildcard),
+      Some((_, TokenKind::Identifier(name))) => {
+        let mut path = vec![name.clone()];
+
+        if let Some((_, TokenKind::Operator(operator))) = self.tokens.peek() {
+          if operator == "::" {
+            self.tokens.next();
+            path.append(&mut self.expect_identifier_list(TokenKind::Operator("::".to_string()))?);
+          }
+        }
+
+        match self.tokens.peek() {
+          Some((_, TokenKind::LeftParen)) => {
+            self.tokens.next();
+            let fields = self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
+              parser.parse_pattern()
+            })?;
+
+            Ok(util::Pattern::Variant { path, fields })
+          }
+          Some((_, TokenKind::LeftBrace)) => {
+            self.tokens.next();
+            let fields = self.expect_list(TokenKind::RightBrace, TokenKind::Comma, |parser| {
+              let name = parser.expect_identifier()?;
+
+              match parser.tokens.peek() {
+                Some((_, TokenKind::Operator(operator))) if operator == ":" => {
+                  parser.tokens.next();
+                  Ok((name, parser.parse_pattern()?))
+                }
+                _ => Ok((name.clone(), util::Pattern::Identifier(name))),
+              }
+            })?;
+
+            Ok(util::Pattern::Struct { path, fields })
+          }
+          _ if path.len() > 1 => Ok(util::Pattern::Variant {
+            path,
+            fields: Vec::new(),
+          }),
+          _ => Ok(util::Pattern::Identifier(name.clone())),
+        }
+      }
+      Some((_, TokenKind::LeftParen)) => Ok(util::Pattern::Tuple(self.expect_list(
+        TokenKind::RightParen,
+        TokenKind::Comma,
+        |parser| parser.parse_pattern(),
+      )?)),
+      Some((_, TokenKind::StringLiteral(value))) => {
+        Ok(util::Pattern::Literal(util::Literal::String(value.clone())))
+      }
+      Some((_, TokenKind::CharLiteral(value))) => {
+        Ok(util::Pattern::Literal(util::Literal::Char(*value)))
+      }
+      Some((span, TokenKind::NumberLiteral(value, ty))) => Ok(util::Pattern::Literal(
+        util::NumberLiteral::from_string(value, ty.clone()).map_err(|e| {
+          self.error(
+            Some(&(
+              span.clone(),
+              TokenKind::NumberLiteral(value.clone(), ty.clone()),
+            )),
+            e,
+          )
+        })?,
+      )),
+      Some((_, TokenKind::True)) => Ok(util::Pattern::Literal(util::Literal::Bool(true))),
+      Some((_, TokenKind::False)) => Ok(util::Pattern::Literal(util::Literal::Bool(false))),
+      token => Err(self.unexpected_token(
+        token,
+        vec![
+          TokenKind::Identifier("".to_string()),
+          TokenKind::LeftParen,
+          TokenKind::StringLiteral("".to_string()),
+          TokenKind::CharLiteral('\0'),
+          TokenKind::NumberLiteral("".to_string(), NumericType::Integer),
+          TokenKind::True,
+          TokenKind::False,
+        ],
+      )),
+    }
+  }
+
+  fn parse_type_parameter(&mut self) -> Result<Vec<util::TypeParameter>, Error<ParserError>> {
+    match self.tokens.peek() {
+      Some((_, TokenKind::Operator(operator))) if operator == "<" => {
+        self.tokens.next();
+        let parameters = self.expect_list(
+          TokenKind::Operator(">".to_string()),
+          TokenKind::Comma,
+          |parser| {
+            let name = parser.expect_identifier()?;
+            Ok(util::TypeParameter {
+              name,
+              traits: parser.parse_bounds()?,
+            })
+          },
+        )?;
+        Ok(parameters)
+      }
+      _ => Ok(Vec::new()),
+    }
+  }
+
+  // bounds are separated by `+` since a comma would start the next type parameter
+  fn parse_bounds(&mut self) -> Result<Vec<util::Path>, Error<ParserError>> {
+    match self.tokens.peek() {
+      Some((_, TokenKind::Operator(operator))) if operator == ":" => {
+        self.tokens.next();
+        self.expect_list_without_end(TokenKind::Operator("+".to_string()), |parser| {
+          parser.expect_identifier_list(TokenKind::Operator("::".to_string()))
+        })
       }
       _ => Ok(Vec::new()),
     }
   }
-  */
 
   fn parse_type(&mut self) -> Result<Type, Error<ParserError>> {
     let expected = vec![
@@ -740,7 +1410,9 @@ impl Parser<'_> {
       TokenKind::Numeric(NumericType::F64),
       TokenKind::LeftParen,
       TokenKind::LeftBracket,
+      TokenKind::LeftBrace,
       TokenKind::Fn,
+      TokenKind::Operator("&".to_string()),
     ];
 
     let ty = match self.tokens.next() {
@@ -761,12 +1433,11 @@ impl Parser<'_> {
             match self.tokens.peek() {
               Some((_, TokenKind::Operator(operator))) if operator == "<" => {
                 self.tokens.next();
-                let parameters = self.expect_list(
+                self.expect_list(
                   TokenKind::Operator(">".to_string()),
                   TokenKind::Comma,
                   |parser| parser.parse_type(),
-                )?;
-                parameters
+                )?
               }
               _ => Vec::new(),
             },
@@ -805,10 +1476,46 @@ impl Parser<'_> {
             _ => Type::Tuple(list),
           }
         }
+        TokenKind::LeftBrace => {
+          Type::Record(
+            self.expect_list(TokenKind::RightBrace, TokenKind::Comma, |parser| {
+              let name = parser.expect_identifier()?;
+              parser.expect_operator(vec![":"])?;
+              Ok((name, parser.parse_type()?))
+            })?,
+          )
+        }
         TokenKind::LeftBracket => {
           let ty = Box::new(self.parse_type()?);
-          self.expect(vec![TokenKind::RightBracket])?;
-          Type::Array(ty)
+
+          match self.tokens.next() {
+            Some((_, TokenKind::RightBracket)) => Type::Array(ty),
+            // the length is folded here, since types don't carry expressions
+            Some((_, TokenKind::Semicolon)) => {
+              let token = self.tokens.peek().copied();
+              let length = self
+                .parse_expression()?
+                .const_eval_with(&|name| self.constants.get(name).copied())
+                .and_then(|n| usize::try_from(n).ok())
+                .ok_or_else(|| self.error(token, ParserError::InvalidArrayLength))?;
+
+              self.expect(vec![TokenKind::RightBracket])?;
+              Type::FixedArray(ty, length)
+            }
+            token => Err(
+              self.unexpected_token(token, vec![TokenKind::RightBracket, TokenKind::Semicolon]),
+            )?,
+          }
+        }
+        // `&&T` is lexed as one operator, but is a reference to a reference
+        TokenKind::Operator(operator) if operator == "&" || operator == "&&" => {
+          let mutable = self.parse_mut();
+          let ty = Type::Reference(Box::new(self.parse_type()?), mutable);
+
+          match operator.as_str() {
+            "&&" => Type::Reference(Box::new(ty), false),
+            _ => ty,
+          }
         }
         TokenKind::Fn => {
           self.expect(vec![TokenKind::LeftParen])?;
@@ -816,7 +1523,7 @@ impl Parser<'_> {
           let parameters = self.expect_list(TokenKind::RightParen, TokenKind::Comma, |parser| {
             parser.parse_type()
           })?;
-          let return_type = self.parse_type_annotation(false)?.unwrap();
+          let return_type = self.parse_return_type(false)?.unwrap();
 
           Type::Function(parameters, Box::new(return_type))
         }
@@ -832,22 +1539,10 @@ impl Parser<'_> {
           "|" => {
             self.tokens.next();
 
-            let mut types = BTreeSet::new();
-
-            match ty {
-              Type::Union(mut union) => types.append(&mut union),
-              ty => {
-                types.insert(ty);
-              }
-            }
+            let mut types = vec![ty];
 
             loop {
-              match self.parse_type()? {
-                Type::Union(mut union) => types.append(&mut union),
-                ty => {
-                  types.insert(ty);
-                }
-              }
+              types.push(self.parse_type()?);
 
               match self.tokens.peek() {
                 Some((_, TokenKind::Operator(operator))) if operator == "|" => {
@@ -857,7 +1552,7 @@ impl Parser<'_> {
               }
             }
 
-            Type::Union(types)
+            Type::union(types)
           }
           _ => ty,
         }
@@ -883,6 +1578,31 @@ impl Parser<'_> {
     }
   }
 
+  // both `:` and `->` introduce a return type. right after a parameter list `->` is always read as
+  // the return type marker rather than a prefix operator, so a body that starts with `->` has to be
+  // wrapped in a block; everywhere else `->` is an ordinary operator
+  fn parse_return_type(&mut self, optional: bool) -> Result<Option<Type>, Error<ParserError>> {
+    match self.tokens.peek().copied() {
+      Some((_, TokenKind::Operator(operator))) if operator == ":" || operator == "->" => {
+        self.tokens.next();
+        Ok(Some(self.parse_type()?))
+      }
+      token => {
+        if optional {
+          Ok(None)
+        } else {
+          Err(self.unexpected_token(
+            token,
+            vec![
+              TokenKind::Operator(":".to_string()),
+              TokenKind::Operator("->".to_string()),
+            ],
+          ))
+        }
+      }
+    }
+  }
+
   fn parse_traits(&mut self) -> Result<Vec<util::Path>, Error<ParserError>> {
     match self.tokens.peek() {
       Some((_, TokenKind::Operator(operator))) if operator == ":" => {
@@ -895,3 +1615,1251 @@ impl Parser<'_> {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lexer::lexer::Lexer;
+
+  fn parse_expression(source: &str) -> Result<util::Expression<Type>, Error<ParserError>> {
+    let tokens = Lexer::new(source).lex(false).unwrap();
+    Parser::new(tokens.iter()).parse_expression()
+  }
+
+  fn parse(source: &str) -> Result<module::Module<Type>, Error<ParserError>> {
+    let tokens = Lexer::new(source).lex(false).unwrap();
+    Parser::new(tokens.iter()).parse()
+  }
+
+  #[test]
+  fn test_type_parameters() {
+    let module = parse("struct Foo<T: A + B::C, U>: D {};").unwrap();
+
+    assert_eq!(
+      module.items[0].kind,
+      module::ItemKind::Struct(r#struct::Struct {
+        header: r#struct::Header {
+          name: "Foo".to_string(),
+          type_parameters: vec![
+            util::TypeParameter {
+              name: "T".to_string(),
+              traits: vec![
+                vec!["A".to_string()],
+                vec!["B".to_string(), "C".to_string()]
+              ],
+            },
+            util::TypeParameter {
+              name: "U".to_string(),
+              traits: vec![],
+            },
+          ],
+          traits: vec![vec!["D".to_string()]],
+        },
+        module: module::Module { items: vec![] },
+      })
+    );
+  }
+
+  #[test]
+  fn test_docs() {
+    let module = parse("/// adds\n/// things\n// not docs\nfn add() (); fn sub() ();").unwrap();
+
+    assert_eq!(
+      module.items[0].docs,
+      vec![" adds".to_string(), " things".to_string()]
+    );
+    assert_eq!(module.items[1].docs, Vec::<String>::new());
+
+    assert!(parse("trait T { /// docs\n fn f(): (); };").is_ok());
+
+    // doc comments that don't come before an item are dropped
+    assert_eq!(
+      parse("fn f(): i32 { /// doc\n1 };").unwrap(),
+      parse("fn f(): i32 { 1 };").unwrap()
+    );
+    assert_eq!(
+      parse("fn f(): () { 1; /// doc\n};").unwrap(),
+      parse("fn f(): () { 1; };").unwrap()
+    );
+    assert_eq!(
+      parse("enum E { /// doc\nA, B { /// doc\nx: i32 } };").unwrap(),
+      parse("enum E { A, B { x: i32 } };").unwrap()
+    );
+    assert_eq!(
+      parse("struct S { fn f() (); /// doc\n}; /// doc\n").unwrap(),
+      parse("struct S { fn f() (); };").unwrap()
+    );
+
+    // they're trivia anywhere else, rather than something out of place
+    let cases = [
+      (
+        "fn f(x: i32): i32 match x { /// doc\n1 => 2, /// doc\n_ => 3 };",
+        "fn f(x: i32): i32 match x { 1 => 2, _ => 3 };",
+      ),
+      (
+        "fn f(): [i32] [/// doc\n1, /// doc\n2];",
+        "fn f(): [i32] [1, 2];",
+      ),
+      (
+        "fn f(/// doc\nx: i32, /// doc\ny: i32): i32 x;",
+        "fn f(x: i32, y: i32): i32 x;",
+      ),
+      ("fn f(): i32 1 + /// doc\n2;", "fn f(): i32 1 + 2;"),
+      ("fn f(): i32 /// doc\n1;", "fn f(): i32 1;"),
+    ];
+    for (documented, plain) in cases {
+      assert_eq!(parse(documented).unwrap(), parse(plain).unwrap());
+    }
+  }
+
+  #[test]
+  fn test_return_type() {
+    assert_eq!(
+      parse("fn f(x: i32) -> i32 x;").unwrap(),
+      parse("fn f(x: i32): i32 x;").unwrap()
+    );
+
+    assert_eq!(
+      parse("fn f() -> fn(i32) -> bool fn(x: i32) -> bool true;").unwrap(),
+      parse("fn f(): fn(i32): bool fn(x: i32): bool true;").unwrap()
+    );
+
+    // the marker is only special after a parameter list
+    assert_eq!(
+      parse_expression("a -> b").unwrap(),
+      util::Expression::Infix {
+        operator: "->".to_string(),
+        operands: (
+          Box::new(util::Expression::Identifier("a".to_string())),
+          Box::new(util::Expression::Identifier("b".to_string())),
+        ),
+      }
+    );
+
+    assert_eq!(
+      parse("fn f() { -> a };").unwrap().items[0].kind,
+      module::ItemKind::Function(function::Function {
+        header: function::Header {
+          name: "f".to_string(),
+          parameters: vec![],
+          ty: None,
+        },
+        body: util::Expression::Block {
+          expressions: vec![util::Expression::Prefix {
+            operator: "->".to_string(),
+            operand: Box::new(util::Expression::Identifier("a".to_string())),
+          }],
+          has_value: true,
+        },
+      })
+    );
+  }
+
+  #[test]
+  fn test_if() {
+    let identifier = |name: &str| Box::new(util::Expression::Identifier(name.to_string()));
+
+    assert_eq!(
+      parse_expression("if a { b } else if c { d } else { e }").unwrap(),
+      util::Expression::If {
+        condition: identifier("a"),
+        consequence: Box::new(util::Expression::Block {
+          expressions: vec![*identifier("b")],
+          has_value: true,
+        }),
+        alternative: Some(Box::new(util::Expression::If {
+          condition: identifier("c"),
+          consequence: Box::new(util::Expression::Block {
+            expressions: vec![*identifier("d")],
+            has_value: true,
+          }),
+          alternative: Some(Box::new(util::Expression::Block {
+            expressions: vec![*identifier("e")],
+            has_value: true,
+          })),
+        })),
+      }
+    );
+  }
+
+  #[test]
+  fn test_try() {
+    let identifier = |name: &str| Box::new(util::Expression::Identifier(name.to_string()));
+
+    // `?` binds as tightly as a call, before the field access after it and the operator before it
+    assert_eq!(
+      parse_expression("-f(x)?.y").unwrap(),
+      util::Expression::Prefix {
+        operator: "-".to_string(),
+        operand: Box::new(util::Expression::Field {
+          expression: Box::new(util::Expression::Try(Box::new(util::Expression::Call {
+            expression: identifier("f"),
+            arguments: vec![util::Argument {
+              name: None,
+              value: *identifier("x"),
+            }],
+          }))),
+          field: "y".to_string(),
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn test_if_let() {
+    let identifier = |name: &str| Box::new(util::Expression::Identifier(name.to_string()));
+
+    // the condition is a `let`, and the braces after it are the body rather than a struct literal
+    assert_eq!(
+      parse_expression("while let Some(x) = y { x }").unwrap(),
+      util::Expression::While {
+        label: None,
+        condition: Box::new(util::Expression::Let {
+          mutable: false,
+          pattern: util::Pattern::Variant {
+            path: vec!["Some".to_string()],
+            fields: vec![util::Pattern::Identifier("x".to_string())],
+          },
+          ty: None,
+          value: identifier("y"),
+        }),
+        body: Box::new(util::Expression::Block {
+          expressions: vec![*identifier("x")],
+          has_value: true,
+        }),
+      }
+    );
+  }
+
+  #[test]
+  fn test_assignment() {
+    let identifier = |name: &str| Box::new(util::Expression::Identifier(name.to_string()));
+
+    assert_eq!(
+      parse_expression("a += b -= c + d").unwrap(),
+      util::Expression::Infix {
+        operator: "+=".to_string(),
+        operands: (
+          identifier("a"),
+          Box::new(util::Expression::Infix {
+            operator: "-=".to_string(),
+            operands: (
+              identifier("b"),
+              Box::new(util::Expression::Infix {
+                operator: "+".to_string(),
+                operands: (identifier("c"), identifier("d")),
+              }),
+            ),
+          }),
+        ),
+      }
+    );
+  }
+
+  #[test]
+  fn test_tuple_index() {
+    let t = || Box::new(util::Expression::Identifier("t".to_string()));
+
+    assert_eq!(
+      parse_expression("t.0").unwrap(),
+      util::Expression::TupleIndex {
+        expression: t(),
+        index: 0
+      }
+    );
+
+    assert_eq!(
+      parse_expression("t.0.12").unwrap(),
+      util::Expression::TupleIndex {
+        expression: Box::new(util::Expression::TupleIndex {
+          expression: t(),
+          index: 0
+        }),
+        index: 12
+      }
+    );
+
+    assert_eq!(
+      parse_expression("t.1.x").unwrap(),
+      util::Expression::Field {
+        expression: Box::new(util::Expression::TupleIndex {
+          expression: t(),
+          index: 1
+        }),
+        field: "x".to_string()
+      }
+    );
+
+    assert_eq!(
+      parse_expression("t.0u8").unwrap_err(),
+      Error(2..5, ParserError::InvalidTupleIndex("0".to_string()))
+    );
+
+    assert_eq!(
+      parse_expression("t.1e5").unwrap_err(),
+      Error(2..5, ParserError::InvalidTupleIndex("1e5".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_field() {
+    assert_eq!(
+      parse_expression("a.b(c).d").unwrap(),
+      util::Expression::Field {
+        expression: Box::new(util::Expression::Call {
+          expression: Box::new(util::Expression::Field {
+            expression: Box::new(util::Expression::Identifier("a".to_string())),
+            field: "b".to_string()
+          }),
+          arguments: vec![util::Argument {
+            name: None,
+            value: util::Expression::Identifier("c".to_string())
+          }]
+        }),
+        field: "d".to_string()
+      }
+    );
+  }
+
+  #[test]
+  fn test_impl() {
+    let module = parse("impl Box<T> { pub fn get(): Self self; };").unwrap();
+    let ty = Type::Named(
+      vec!["Box".to_string()],
+      vec![Type::Named(vec!["T".to_string()], vec![])],
+    );
+
+    match &module.items[0].kind {
+      module::ItemKind::Impl { ty: target, module } => {
+        assert_eq!(*target, ty);
+        assert_eq!(module.items.len(), 1);
+        assert!(module.items[0].modifiers.public);
+
+        // `Self` is left for the typechecker, which knows the struct the block is for
+        match &module.items[0].kind {
+          module::ItemKind::Function(f) => assert_eq!(
+            f.header.ty,
+            Some(Type::Named(vec!["Self".to_string()], vec![]))
+          ),
+          kind => panic!("expected a function, found {:?}", kind),
+        }
+      }
+      kind => panic!("expected an impl block, found {:?}", kind),
+    }
+
+    assert!(parse("impl (i32) {};").is_err());
+    assert!(parse("impl A::B<T, U> {};").is_ok());
+
+    // only names can stand for the struct's type parameters
+    assert!(parse("impl A | bool {};").is_err());
+    assert!(parse("impl Box<i32> {};").is_err());
+    assert!(parse("impl Box<Vec<T>> {};").is_err());
+  }
+
+  #[test]
+  fn test_trait_operator() {
+    let module = parse("trait Num { -(i32): i32; +(i32, i32): i32; };").unwrap();
+
+    match &module.items[0].kind {
+      module::ItemKind::Trait(t) => assert_eq!(
+        t.items
+          .iter()
+          .map(|(_, item)| item.clone())
+          .collect::<Vec<_>>(),
+        vec![
+          r#trait::Item::Operator(r#trait::Operator::Prefix {
+            operator: "-".to_string(),
+            operand: Type::I32,
+            result: Type::I32,
+          }),
+          r#trait::Item::Operator(r#trait::Operator::Infix {
+            operator: "+".to_string(),
+            operands: (Type::I32, Type::I32),
+            result: Type::I32,
+          }),
+        ]
+      ),
+      kind => panic!("expected a trait, found {:?}", kind),
+    }
+  }
+
+  #[test]
+  fn test_record() {
+    assert_eq!(
+      parse_expression("{ x: 1, y: { y } }").unwrap(),
+      util::Expression::Literal(util::Literal::Record(vec![
+        (
+          "x".to_string(),
+          util::Expression::Literal(util::Literal::Number(util::NumberLiteral::Integer(1)))
+        ),
+        (
+          "y".to_string(),
+          util::Expression::Block {
+            expressions: vec![util::Expression::Identifier("y".to_string())],
+            has_value: true,
+          }
+        ),
+      ]))
+    );
+
+    // the fields of a record type are kept as written, but records compare equal in any order
+    assert_eq!(
+      parse_expression("let r: { y: bool, x: i32 } = r").unwrap(),
+      util::Expression::Let {
+        mutable: false,
+        pattern: util::Pattern::Identifier("r".to_string()),
+        ty: Some(Type::Record(vec![
+          ("x".to_string(), Type::I32),
+          ("y".to_string(), Type::Bool),
+        ])),
+        value: Box::new(util::Expression::Identifier("r".to_string())),
+      }
+    );
+
+    assert!(parse_expression("{ x: 1 y: 2 }").is_err());
+    assert!(parse_expression("let r: { x } = r").is_err());
+  }
+
+  #[test]
+  fn test_struct_literal() {
+    let module = parse("struct P { x: i32; pub y: i32 = 0; fn f(): i32 1; };").unwrap();
+
+    match &module.items[0].kind {
+      module::ItemKind::Struct(s) => {
+        assert_eq!(
+          s.module.items[0].kind,
+          module::ItemKind::Field {
+            name: "x".to_string(),
+            ty: Type::I32,
+            default: None,
+          }
+        );
+        assert!(s.module.items[1].modifiers.public);
+        assert_eq!(
+          s.module.items[1].kind,
+          module::ItemKind::Field {
+            name: "y".to_string(),
+            ty: Type::I32,
+            default: Some(util::Expression::Literal(util::Literal::Number(
+              util::NumberLiteral::Integer(0)
+            ))),
+          }
+        );
+      }
+      _ => unreachable!(),
+    }
+
+    // fields only make sense in structs, and belong to instances
+    assert!(parse("x: i32;").is_err());
+    assert!(parse("struct P { static x: i32; };").is_err());
+
+    assert_eq!(
+      parse_expression("a::P { x: 1, y }").unwrap(),
+      util::Expression::StructLiteral {
+        path: vec!["a".to_string(), "P".to_string()],
+        fields: vec![
+          (
+            "x".to_string(),
+            util::Expression::Literal(util::Literal::Number(util::NumberLiteral::Integer(1)))
+          ),
+          (
+            "y".to_string(),
+            util::Expression::Identifier("y".to_string())
+          ),
+        ],
+      }
+    );
+
+    assert_eq!(
+      parse_expression("P {}").unwrap(),
+      util::Expression::StructLiteral {
+        path: vec!["P".to_string()],
+        fields: vec![],
+      }
+    );
+
+    // in a condition the brace starts the body, unless the literal is parenthesized
+    assert_eq!(
+      parse_expression("if x { y }").unwrap(),
+      util::Expression::If {
+        condition: Box::new(util::Expression::Identifier("x".to_string())),
+        consequence: Box::new(util::Expression::Block {
+          expressions: vec![util::Expression::Identifier("y".to_string())],
+          has_value: true,
+        }),
+        alternative: None,
+      }
+    );
+
+    assert!(matches!(
+      parse_expression("while (P { x }).x {}").unwrap(),
+      util::Expression::While { condition, .. }
+        if matches!(condition.as_ref(), util::Expression::Field { .. })
+    ));
+
+    assert!(matches!(
+      parse_expression("for p in f(P { x }) {}").unwrap(),
+      util::Expression::For { body, .. }
+        if matches!(body.as_ref(), util::Expression::Block { .. })
+    ));
+  }
+
+  #[test]
+  fn test_range() {
+    assert_eq!(
+      parse_expression("a..b + 1").unwrap(),
+      util::Expression::Range {
+        start: Box::new(util::Expression::Identifier("a".to_string())),
+        end: Box::new(util::Expression::Infix {
+          operator: "+".to_string(),
+          operands: (
+            Box::new(util::Expression::Identifier("b".to_string())),
+            Box::new(util::Expression::Literal(util::Literal::Number(
+              util::NumberLiteral::Integer(1)
+            ))),
+          ),
+        }),
+        kind: util::RangeKind::Exclusive
+      }
+    );
+
+    assert_eq!(parse_expression("a..<b"), parse_expression("a..b"));
+
+    assert!(matches!(
+      parse_expression("a..=b").unwrap(),
+      util::Expression::Range {
+        kind: util::RangeKind::Inclusive,
+        ..
+      }
+    ));
+  }
+
+  #[test]
+  fn test_reference() {
+    let x = || Box::new(util::Expression::Identifier("x".to_string()));
+    let prefix = |operator: &str, operand| util::Expression::Prefix {
+      operator: operator.to_string(),
+      operand: Box::new(operand),
+    };
+
+    assert_eq!(
+      parse_expression("&mut x.y").unwrap(),
+      prefix(
+        "&mut",
+        util::Expression::Field {
+          expression: x(),
+          field: "y".to_string()
+        }
+      )
+    );
+    assert_eq!(
+      parse_expression("*&x + 1").unwrap(),
+      util::Expression::Infix {
+        operator: "+".to_string(),
+        operands: (
+          Box::new(prefix("*", prefix("&", *x()))),
+          Box::new(util::Expression::Literal(util::Literal::Number(
+            util::NumberLiteral::Integer(1)
+          )))
+        )
+      }
+    );
+    // `&&` is lexed as one operator
+    assert_eq!(
+      parse_expression("&&mut x").unwrap(),
+      prefix("&", prefix("&mut", *x()))
+    );
+    assert_eq!(
+      parse_expression("a && b").unwrap(),
+      util::Expression::Infix {
+        operator: "&&".to_string(),
+        operands: (
+          Box::new(util::Expression::Identifier("a".to_string())),
+          Box::new(util::Expression::Identifier("b".to_string()))
+        )
+      }
+    );
+
+    let ty = |source: &str| {
+      let tokens = Lexer::new(source).lex(false).unwrap();
+      Parser::new(tokens.iter()).parse_type()
+    };
+
+    assert_eq!(
+      ty("&mut [i32]").unwrap(),
+      Type::Reference(Box::new(Type::Array(Box::new(Type::I32))), true)
+    );
+    assert_eq!(
+      ty("&&char").unwrap(),
+      Type::Reference(
+        Box::new(Type::Reference(Box::new(Type::Char), false)),
+        false
+      )
+    );
+    assert_eq!(
+      ty("&i32 | char").unwrap(),
+      Type::Reference(Box::new(crate::union!(Type::I32, Type::Char)), false)
+    );
+  }
+
+  #[test]
+  fn test_cast() {
+    assert_eq!(
+      parse_expression("a + b as u8 == c").unwrap(),
+      util::Expression::Infix {
+        operator: "==".to_string(),
+        operands: (
+          Box::new(util::Expression::Cast {
+            value: Box::new(util::Expression::Infix {
+              operator: "+".to_string(),
+              operands: (
+                Box::new(util::Expression::Identifier("a".to_string())),
+                Box::new(util::Expression::Identifier("b".to_string())),
+              ),
+            }),
+            ty: Type::U8
+          }),
+          Box::new(util::Expression::Identifier("c".to_string())),
+        ),
+      }
+    );
+  }
+
+  #[test]
+  fn test_path() {
+    assert_eq!(
+      parse_expression("a::b::c(d)").unwrap(),
+      util::Expression::Call {
+        expression: Box::new(util::Expression::Path(vec![
+          "a".to_string(),
+          "b".to_string(),
+          "c".to_string()
+        ])),
+        arguments: vec![util::Argument {
+          name: None,
+          value: util::Expression::Identifier("d".to_string())
+        }],
+      }
+    );
+
+    assert!(parse_expression("a::").is_err());
+  }
+
+  #[test]
+  fn test_number_suffix() {
+    assert_eq!(
+      parse_expression("42u8").unwrap(),
+      util::Expression::Literal(util::Literal::Number(util::NumberLiteral::U8(42)))
+    );
+
+    assert_eq!(
+      parse_expression("3.0f32").unwrap(),
+      util::Expression::Literal(util::Literal::Number(util::NumberLiteral::F32(3.0)))
+    );
+
+    // the suffix has to agree with how the literal is written
+    assert_eq!(
+      parse_expression("1.5u8").unwrap_err().1,
+      ParserError::InvalidNumber("1.5".to_string(), NumericType::U8)
+    );
+
+    assert_eq!(
+      parse_expression("1e5i64").unwrap_err().1,
+      ParserError::InvalidNumber("1e5".to_string(), NumericType::I64)
+    );
+
+    assert_eq!(
+      parse_expression("256u8").unwrap_err().1,
+      ParserError::NumberOverflow("256".to_string(), NumericType::U8)
+    );
+  }
+
+  #[test]
+  fn test_number_overflow() {
+    assert_eq!(
+      parse_expression("12").unwrap(),
+      util::Expression::Literal(util::Literal::Number(util::NumberLiteral::Integer(12)))
+    );
+
+    assert_eq!(
+      parse_expression("340282366920938463463374607431768211455u128").unwrap(),
+      util::Expression::Literal(util::Literal::Number(util::NumberLiteral::U128(u128::MAX)))
+    );
+
+    assert_eq!(
+      parse_expression("340282366920938463463374607431768211456u128")
+        .unwrap_err()
+        .1,
+      ParserError::NumberOverflow(
+        "340282366920938463463374607431768211456".to_string(),
+        NumericType::U128
+      )
+    );
+
+    assert_eq!(
+      parse_expression("0x100000000i32").unwrap_err().1,
+      ParserError::NumberOverflow("0x100000000".to_string(), NumericType::I32)
+    );
+
+    // an unsuffixed literal only has to fit somewhere, and is checked against its type later
+    assert_eq!(
+      parse_expression("5000000000").unwrap(),
+      util::Expression::Literal(util::Literal::Number(util::NumberLiteral::Integer(
+        5000000000
+      )))
+    );
+    assert_eq!(
+      parse_expression("1i32").unwrap(),
+      util::Expression::Literal(util::Literal::Number(util::NumberLiteral::I32(1)))
+    );
+
+    // floats don't silently become infinite, but ones too small still round to zero
+    assert_eq!(
+      parse_expression("1e39f32").unwrap_err().1,
+      ParserError::NumberOverflow("1e39".to_string(), NumericType::F32)
+    );
+
+    assert_eq!(
+      parse_expression("1e400").unwrap_err().1,
+      ParserError::NumberOverflow("1e400".to_string(), NumericType::F64)
+    );
+
+    assert_eq!(
+      parse_expression("1e-400").unwrap(),
+      util::Expression::Literal(util::Literal::Number(util::NumberLiteral::F64(0.0)))
+    );
+  }
+
+  #[test]
+  fn test_default_parameters() {
+    let module = parse("fn f(x: i32, y: i32 = 1 + 2) x;").unwrap();
+
+    match &module.items[0].kind {
+      module::ItemKind::Function(f) => assert_eq!(
+        f.header.parameters,
+        vec![
+          util::Parameter {
+            name: "x".to_string(),
+            ty: Type::I32,
+            default: None,
+            rest: false
+          },
+          util::Parameter {
+            name: "y".to_string(),
+            ty: Type::I32,
+            default: Some(util::Expression::Infix {
+              operator: "+".to_string(),
+              operands: (
+                Box::new(util::Expression::Literal(util::Literal::Number(
+                  util::NumberLiteral::Integer(1)
+                ))),
+                Box::new(util::Expression::Literal(util::Literal::Number(
+                  util::NumberLiteral::Integer(2)
+                ))),
+              ),
+            }),
+            rest: false
+          },
+        ]
+      ),
+      _ => unreachable!(),
+    }
+
+    // closures don't take defaults
+    assert!(parse_expression("fn(x: i32 = 1) x").is_err());
+  }
+
+  #[test]
+  fn test_rest_parameter() {
+    let module = parse("fn f(x: i32, ys: char...) x;").unwrap();
+
+    match &module.items[0].kind {
+      module::ItemKind::Function(f) => assert_eq!(
+        f.header.parameters[1],
+        util::Parameter {
+          name: "ys".to_string(),
+          ty: Type::Char,
+          default: None,
+          rest: true
+        }
+      ),
+      _ => unreachable!(),
+    }
+
+    assert!(parse("fn f(xs: i32..., y: i32) y;").is_err());
+    assert!(parse("fn f(xs: i32... = []) y;").is_err());
+  }
+
+  #[test]
+  fn test_const() {
+    let module = parse("const N: i32 = 2; fn f(x: [i32; N * 2]) x;").unwrap();
+
+    assert_eq!(
+      module.items[0].kind,
+      module::ItemKind::Const {
+        name: "N".to_string(),
+        ty: Type::I32,
+        value: util::Expression::Literal(util::Literal::Number(util::NumberLiteral::Integer(2))),
+      }
+    );
+
+    match &module.items[1].kind {
+      module::ItemKind::Function(f) => assert_eq!(
+        f.header.parameters[0].ty,
+        Type::FixedArray(Box::new(Type::I32), 4)
+      ),
+      _ => unreachable!(),
+    }
+
+    // constants have to be declared before the lengths that use them
+    assert!(parse("fn f(x: [i32; N]) x; const N: i32 = 2;").is_err());
+
+    // and be in scope, so a struct's constants stay in its body
+    assert!(parse("struct A { static const N: i32 = 2; fn f(x: [i32; N]): i32 0; };").is_ok());
+    assert!(parse("struct A { static const N: i32 = 2; }; fn f(x: [i32; N]): i32 0;").is_err());
+    assert!(parse("const N: i32 = 2; struct A { fn f(x: [i32; N]): i32 0; };").is_ok());
+  }
+
+  #[test]
+  fn test_trailing_commas() {
+    for (trailing, plain) in [
+      ("(1, 2,)", "(1, 2)"),
+      ("[1, 2,]", "[1, 2]"),
+      ("f(a, y: b,)", "f(a, y: b)"),
+      ("fn(x: i32,) x", "fn(x: i32) x"),
+      ("match x { 1 => a, _ => b, }", "match x { 1 => a, _ => b }"),
+      (
+        "match x { e::f(a, b,) => a }",
+        "match x { e::f(a, b) => a }",
+      ),
+      ("let (a, b,) = c", "let (a, b) = c"),
+    ] {
+      assert_eq!(parse_expression(trailing), parse_expression(plain));
+    }
+
+    for (trailing, plain) in [
+      ("fn f(x: i32, y: i32,) x;", "fn f(x: i32, y: i32) x;"),
+      (
+        "struct s<T, U,> { + (a: T, b: U,): T a; - (a: T,): T a; };",
+        "struct s<T, U> { + (a: T, b: U): T a; - (a: T): T a; };",
+      ),
+      (
+        "enum e { f(i32, char,), g, };",
+        "enum e { f(i32, char), g };",
+      ),
+      (
+        "trait t { fn f(i32,): (); + (i32, i32,): i32; };",
+        "trait t { fn f(i32): (); + (i32, i32): i32; };",
+      ),
+      ("fn f(): (i32, char,) x;", "fn f(): (i32, char) x;"),
+    ] {
+      assert_eq!(parse(trailing), parse(plain));
+    }
+
+    assert!(parse_expression("f(a,,b)").is_err());
+    assert!(parse_expression("[1,,]").is_err());
+    assert!(parse_expression("(,)").is_err());
+    assert!(parse("fn f(,) x;").is_err());
+  }
+
+  #[test]
+  fn test_spans() {
+    let tokens = Lexer::new("-a + f(bc)").lex(false).unwrap();
+    let mut parser = Parser::new(tokens.iter());
+    parser.spans = true;
+
+    let spanned = |span, expression| util::Expression::Spanned(span, Box::new(expression));
+    let identifier =
+      |span, name: &str| spanned(span, util::Expression::Identifier(name.to_string()));
+
+    assert_eq!(
+      parser.parse_expression().unwrap(),
+      spanned(
+        0..10,
+        util::Expression::Infix {
+          operator: "+".to_string(),
+          operands: (
+            Box::new(spanned(
+              0..2,
+              util::Expression::Prefix {
+                operator: "-".to_string(),
+                operand: Box::new(identifier(1..2, "a")),
+              }
+            )),
+            Box::new(spanned(
+              5..10,
+              util::Expression::Call {
+                expression: Box::new(identifier(5..6, "f")),
+                arguments: vec![util::Argument {
+                  name: None,
+                  value: identifier(7..9, "bc"),
+                }],
+              }
+            )),
+          ),
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn test_fixed_array() {
+    assert_eq!(
+      parse_expression("let x: [[i32; 2]; 1 + 2] = y").unwrap(),
+      util::Expression::Let {
+        mutable: false,
+        pattern: util::Pattern::Identifier("x".to_string()),
+        ty: Some(Type::FixedArray(
+          Box::new(Type::FixedArray(Box::new(Type::I32), 2)),
+          3
+        )),
+        value: Box::new(util::Expression::Identifier("y".to_string())),
+      }
+    );
+
+    assert_eq!(
+      parse_expression("let x: [i32; n] = y").unwrap_err(),
+      Error(13..14, ParserError::InvalidArrayLength)
+    );
+    assert_eq!(
+      parse_expression("let x: [i32; -1] = y").unwrap_err(),
+      Error(13..14, ParserError::InvalidArrayLength)
+    );
+  }
+
+  #[test]
+  fn test_named_arguments() {
+    let identifier = |name: &str| util::Expression::Identifier(name.to_string());
+
+    assert_eq!(
+      parse_expression("f(a, y: b, c)").unwrap(),
+      util::Expression::Call {
+        expression: Box::new(identifier("f")),
+        arguments: vec![
+          util::Argument {
+            name: None,
+            value: identifier("a")
+          },
+          util::Argument {
+            name: Some("y".to_string()),
+            value: identifier("b")
+          },
+          util::Argument {
+            name: None,
+            value: identifier("c")
+          },
+        ],
+      }
+    );
+  }
+
+  #[test]
+  fn test_wildcard() {
+    assert_eq!(
+      parse_expression("let (_, b) = c").unwrap(),
+      util::Expression::Let {
+        mutable: false,
+        pattern: util::Pattern::Tuple(vec![
+          util::Pattern::Wildcard,
+          util::Pattern::Identifier("b".to_string()),
+        ]),
+        ty: None,
+        value: Box::new(util::Expression::Identifier("c".to_string())),
+      }
+    );
+
+    assert_eq!(
+      parse_expression("1 + _").unwrap_err(),
+      Error(4..5, ParserError::WildcardExpression)
+    );
+  }
+
+  #[test]
+  fn test_let() {
+    assert_eq!(
+      parse_expression("let mut x = 1").unwrap(),
+      util::Expression::Let {
+        mutable: true,
+        pattern: util::Pattern::Identifier("x".to_string()),
+        ty: None,
+        value: Box::new(util::Expression::Literal(util::Literal::Number(
+          util::NumberLiteral::Integer(1)
+        ))),
+      }
+    );
+
+    assert_eq!(
+      parse_expression("let x: i32 = y = 1").unwrap(),
+      util::Expression::Let {
+        mutable: false,
+        pattern: util::Pattern::Identifier("x".to_string()),
+        ty: Some(Type::I32),
+        value: Box::new(util::Expression::Infix {
+          operator: "=".to_string(),
+          operands: (
+            Box::new(util::Expression::Identifier("y".to_string())),
+            Box::new(util::Expression::Literal(util::Literal::Number(
+              util::NumberLiteral::Integer(1)
+            ))),
+          ),
+        }),
+      }
+    );
+
+    assert_eq!(
+      parse_expression("let (a, (b, c)) = d").unwrap(),
+      util::Expression::Let {
+        mutable: false,
+        pattern: util::Pattern::Tuple(vec![
+          util::Pattern::Identifier("a".to_string()),
+          util::Pattern::Tuple(vec![
+            util::Pattern::Identifier("b".to_string()),
+            util::Pattern::Identifier("c".to_string()),
+          ]),
+        ]),
+        ty: None,
+        value: Box::new(util::Expression::Identifier("d".to_string())),
+      }
+    );
+
+    assert_eq!(
+      parse_expression("let x").unwrap_err(),
+      Error(
+        5..5,
+        ParserError::UnexpectedToken(
+          Some(TokenKind::Eof),
+          vec![TokenKind::Operator("=".to_string())]
+        )
+      )
+    );
+  }
+
+  #[test]
+  fn test_break() {
+    assert_eq!(
+      parse_expression("{ break; break 'a'; continue }").unwrap(),
+      util::Expression::Block {
+        expressions: vec![
+          util::Expression::Break {
+            label: None,
+            value: None
+          },
+          util::Expression::Break {
+            label: None,
+            value: Some(Box::new(util::Expression::Literal(util::Literal::Char(
+              'a'
+            ))))
+          },
+          util::Expression::Continue { label: None },
+        ],
+        has_value: true,
+      }
+    );
+
+    // a label comes before the value, and `'a'` is still a char
+    assert_eq!(
+      parse_expression("'a: loop { break 'a 'a'; continue 'a }").unwrap(),
+      util::Expression::Loop {
+        label: Some("a".to_string()),
+        body: Box::new(util::Expression::Block {
+          expressions: vec![
+            util::Expression::Break {
+              label: Some("a".to_string()),
+              value: Some(Box::new(util::Expression::Literal(util::Literal::Char(
+                'a'
+              ))))
+            },
+            util::Expression::Continue {
+              label: Some("a".to_string())
+            },
+          ],
+          has_value: true,
+        }),
+      }
+    );
+
+    assert!(parse_expression("'a: f()").is_err());
+  }
+
+  #[test]
+  fn test_block() {
+    let a = || util::Expression::Literal(util::Literal::Char('a'));
+
+    assert_eq!(
+      parse_expression("{ 'a' }").unwrap(),
+      util::Expression::Block {
+        expressions: vec![a()],
+        has_value: true,
+      }
+    );
+
+    // a trailing semicolon makes the last expression a statement
+    assert_eq!(
+      parse_expression("{ 'a'; }").unwrap(),
+      util::Expression::Block {
+        expressions: vec![a()],
+        has_value: false,
+      }
+    );
+
+    assert_eq!(
+      parse_expression("{ 'a'; 'a' }").unwrap(),
+      util::Expression::Block {
+        expressions: vec![a(), a()],
+        has_value: true,
+      }
+    );
+
+    assert_eq!(
+      parse_expression("{}").unwrap(),
+      util::Expression::Block {
+        expressions: vec![],
+        has_value: false,
+      }
+    );
+
+    // only the last expression can go without a semicolon
+    assert!(parse_expression("{ 'a' 'a' }").is_err());
+  }
+
+  #[test]
+  fn test_for() {
+    assert_eq!(
+      parse_expression("for c in ['a', 'b'] f(c)").unwrap(),
+      util::Expression::For {
+        label: None,
+        pattern: util::Pattern::Identifier("c".to_string()),
+        iterable: Box::new(util::Expression::Literal(util::Literal::Array(vec![
+          util::Expression::Literal(util::Literal::Char('a')),
+          util::Expression::Literal(util::Literal::Char('b')),
+        ]))),
+        body: Box::new(util::Expression::Call {
+          expression: Box::new(util::Expression::Identifier("f".to_string())),
+          arguments: vec![util::Argument {
+            name: None,
+            value: util::Expression::Identifier("c".to_string())
+          }],
+        }),
+      }
+    );
+
+    assert_eq!(
+      parse_expression("for c ['a'] c").unwrap_err(),
+      Error(
+        6..7,
+        ParserError::UnexpectedToken(Some(TokenKind::LeftBracket), vec![TokenKind::In])
+      )
+    );
+  }
+
+  #[test]
+  fn test_named_variant() {
+    let module = parse("enum E { V { x: i32, y: bool }, W };").unwrap();
+
+    assert_eq!(
+      module.items[0].kind,
+      module::ItemKind::Enum(r#enum::Enum {
+        header: r#enum::Header {
+          name: "E".to_string()
+        },
+        variants: vec![
+          r#enum::Variant {
+            name: "V".to_string(),
+            fields: vec![Type::I32, Type::Bool],
+            names: vec!["x".to_string(), "y".to_string()],
+          },
+          r#enum::Variant {
+            name: "W".to_string(),
+            fields: vec![],
+            names: vec![],
+          },
+        ],
+      })
+    );
+
+    match parse_expression("match e { E::V { x, y: _ } => x }").unwrap() {
+      util::Expression::Match { arms, .. } => assert_eq!(
+        arms[0].0,
+        util::Pattern::Struct {
+          path: vec!["E".to_string(), "V".to_string()],
+          fields: vec![
+            ("x".to_string(), util::Pattern::Identifier("x".to_string())),
+            ("y".to_string(), util::Pattern::Wildcard),
+          ],
+        }
+      ),
+      expression => panic!("expected a match, found {:?}", expression),
+    }
+  }
+
+  #[test]
+  fn test_match() {
+    assert_eq!(
+      parse_expression("match x { true => 'a', 1 => 'b', y => y }").unwrap(),
+      util::Expression::Match {
+        scrutinee: Box::new(util::Expression::Identifier("x".to_string())),
+        arms: vec![
+          (
+            util::Pattern::Literal(util::Literal::Bool(true)),
+            util::Expression::Literal(util::Literal::Char('a')),
+          ),
+          (
+            util::Pattern::Literal(util::Literal::Number(util::NumberLiteral::Integer(1))),
+            util::Expression::Literal(util::Literal::Char('b')),
+          ),
+          (
+            util::Pattern::Identifier("y".to_string()),
+            util::Expression::Identifier("y".to_string()),
+          ),
+        ],
+      }
+    );
+
+    assert_eq!(
+      parse_expression("match x { y -> y }").unwrap_err(),
+      Error(
+        12..14,
+        ParserError::UnexpectedToken(
+          Some(TokenKind::Operator("->".to_string())),
+          vec![TokenKind::FatArrow]
+        )
+      )
+    );
+
+    // `=>` doesn't mean anything outside of a match arm
+    assert_eq!(
+      parse_expression("a => b").unwrap_err(),
+      Error(2..4, ParserError::FatArrowExpression)
+    );
+    assert_eq!(
+      parse_expression("=> b").unwrap_err(),
+      Error(0..2, ParserError::FatArrowExpression)
+    );
+
+    let pattern = |source: &str| {
+      let tokens = Lexer::new(source).lex(false).unwrap();
+      Parser::new(tokens.iter()).parse_pattern()
+    };
+
+    assert_eq!(
+      pattern("Circle(r)").unwrap(),
+      util::Pattern::Variant {
+        path: vec!["Circle".to_string()],
+        fields: vec![util::Pattern::Identifier("r".to_string())],
+      }
+    );
+
+    assert_eq!(
+      pattern("Shape::Empty").unwrap(),
+      util::Pattern::Variant {
+        path: vec!["Shape".to_string(), "Empty".to_string()],
+        fields: vec![],
+      }
+    );
+
+    assert_eq!(
+      pattern("Empty").unwrap(),
+      util::Pattern::Identifier("Empty".to_string())
+    );
+  }
+}
@@ -0,0 +1,714 @@
+use super::{
+  scope::{Item, ItemKind},
+  typechecker::{renames, resolve_self, Tagged, Typechecker},
+};
+use crate::{error::TypecheckerError, map0, parser::ast};
+use std::{collections::BTreeMap, marker::PhantomData};
+
+type Type = ast::util::Type<Vec<String>>;
+
+// lowering runs on a module that has already been typechecked, so every name is known to resolve.
+// names are looked up in `declarations` from the innermost enclosing declaration outwards, which is
+// the order the scopes are searched in while typechecking
+impl Typechecker {
+  pub fn lower(
+    &self,
+    module: ast::module::Module<Type>,
+  ) -> Result<ast::module::Module<Tagged>, TypecheckerError<Type>> {
+    self.lower_module(&[], module)
+  }
+
+  fn lower_module(
+    &self,
+    context: &[String],
+    module: ast::module::Module<Type>,
+  ) -> Result<ast::module::Module<Tagged>, TypecheckerError<Type>> {
+    let items = resolve_self(module)
+      .items
+      .into_iter()
+      .map(|item| {
+        Ok(ast::module::Item {
+          docs: item.docs,
+          modifiers: item.modifiers,
+          kind: self.lower_item(context, item.kind)?,
+          span: item.span,
+        })
+      })
+      .collect::<Result<_, _>>()?;
+
+    Ok(ast::module::Module { items })
+  }
+
+  fn lower_item(
+    &self,
+    context: &[String],
+    kind: ast::module::ItemKind<Type>,
+  ) -> Result<ast::module::ItemKind<Tagged>, TypecheckerError<Type>> {
+    Ok(match kind {
+      ast::module::ItemKind::Function(f) => {
+        ast::module::ItemKind::Function(self.lower_function(context, f)?)
+      }
+      ast::module::ItemKind::Struct(s) => {
+        let inner = [context, std::slice::from_ref(&s.header.name)].concat();
+
+        ast::module::ItemKind::Struct(ast::r#struct::Struct {
+          module: self.lower_module(&inner, s.module)?,
+          header: s.header,
+        })
+      }
+      // the items resolve as if they were declared in the struct, and use the struct's names for
+      // its type parameters, as they were checked. `Self` is the struct the block is for
+      ast::module::ItemKind::Impl { ty, module } => {
+        let (inner, mut names) = match &ty {
+          Type::Named(path, arguments) => {
+            let inner = [context, path].concat();
+            let names = match self.lower_type(&inner, &Type::Named(path.clone(), vec![]))? {
+              Tagged::Named(id, _) => match self.types.get(&id) {
+                Some(Item(_, ItemKind::Struct(s))) => renames(arguments, &s.header.type_parameters),
+                _ => BTreeMap::new(),
+              },
+              _ => BTreeMap::new(),
+            };
+
+            (inner, names)
+          }
+          _ => Err(TypecheckerError::UnresolvedType {
+            name: ty.to_string(),
+          })?,
+        };
+        let ty = ty.substitute(&names);
+        names.insert(vec!["Self".to_string()], ty.clone());
+        let module = module.substitute(&names);
+
+        ast::module::ItemKind::Impl {
+          ty: self.lower_type(&inner, &ty)?,
+          module: self.lower_module(&inner, module)?,
+        }
+      }
+      ast::module::ItemKind::Enum(e) => ast::module::ItemKind::Enum(ast::r#enum::Enum {
+        header: e.header,
+        variants: e
+          .variants
+          .into_iter()
+          .map(|variant| {
+            Ok(ast::r#enum::Variant {
+              name: variant.name,
+              fields: self.lower_types(context, &variant.fields)?,
+              names: variant.names,
+            })
+          })
+          .collect::<Result<_, _>>()?,
+      }),
+      ast::module::ItemKind::Trait(t) => {
+        ast::module::ItemKind::Trait(self.lower_trait(context, t)?)
+      }
+      ast::module::ItemKind::Operator(o) => {
+        let header = match o.header {
+          ast::operator::Header::Prefix(prefix) => {
+            ast::operator::Header::Prefix(ast::operator::Prefix {
+              operator: prefix.operator,
+              operand: self.lower_parameter(context, prefix.operand)?,
+              result: self.lower_type(context, &prefix.result)?,
+            })
+          }
+          ast::operator::Header::Infix(infix) => {
+            ast::operator::Header::Infix(ast::operator::Infix {
+              operator: infix.operator,
+              operands: (
+                self.lower_parameter(context, infix.operands.0)?,
+                self.lower_parameter(context, infix.operands.1)?,
+              ),
+              result: self.lower_type(context, &infix.result)?,
+            })
+          }
+        };
+
+        ast::module::ItemKind::Operator(ast::operator::Operator {
+          header,
+          body: self.lower_expression(context, o.body)?,
+        })
+      }
+      ast::module::ItemKind::Const { name, ty, value } => ast::module::ItemKind::Const {
+        name,
+        ty: self.lower_type(context, &ty)?,
+        value: self.lower_expression(context, value)?,
+      },
+      ast::module::ItemKind::Field { name, ty, default } => ast::module::ItemKind::Field {
+        name,
+        ty: self.lower_type(context, &ty)?,
+        default: default
+          .map(|default| self.lower_expression(context, default))
+          .transpose()?,
+      },
+    })
+  }
+
+  fn lower_function(
+    &self,
+    context: &[String],
+    function: ast::function::Function<Type>,
+  ) -> Result<ast::function::Function<Tagged>, TypecheckerError<Type>> {
+    Ok(ast::function::Function {
+      header: ast::function::Header {
+        name: function.header.name,
+        parameters: function
+          .header
+          .parameters
+          .into_iter()
+          .map(|parameter| self.lower_parameter(context, parameter))
+          .collect::<Result<_, _>>()?,
+        ty: function
+          .header
+          .ty
+          .map(|ty| self.lower_type(context, &ty))
+          .transpose()?,
+      },
+      body: self.lower_expression(context, function.body)?,
+    })
+  }
+
+  // `Self` is declared under the trait, and child traits see it through their parent
+  fn lower_trait(
+    &self,
+    context: &[String],
+    r#trait: ast::r#trait::Trait<Type>,
+  ) -> Result<ast::r#trait::Trait<Tagged>, TypecheckerError<Type>> {
+    let inner = [context, std::slice::from_ref(&r#trait.header.name)].concat();
+
+    let items = r#trait
+      .items
+      .into_iter()
+      .map(|(modifiers, item)| {
+        let item = match item {
+          ast::r#trait::Item::Function(f) => ast::r#trait::Item::Function(ast::r#trait::Function {
+            name: f.name,
+            parameters: self.lower_types(&inner, &f.parameters)?,
+            ty: self.lower_type(&inner, &f.ty)?,
+          }),
+          ast::r#trait::Item::Struct(s) => ast::r#trait::Item::Struct(s),
+          ast::r#trait::Item::Enum(e) => ast::r#trait::Item::Enum(e),
+          ast::r#trait::Item::Trait(t) => ast::r#trait::Item::Trait(t),
+          ast::r#trait::Item::Child(t) => ast::r#trait::Item::Child(self.lower_trait(&inner, t)?),
+          ast::r#trait::Item::Operator(o) => ast::r#trait::Item::Operator(match o {
+            ast::r#trait::Operator::Prefix {
+              operator,
+              operand,
+              result,
+            } => ast::r#trait::Operator::Prefix {
+              operator,
+              operand: self.lower_type(&inner, &operand)?,
+              result: self.lower_type(&inner, &result)?,
+            },
+            ast::r#trait::Operator::Infix {
+              operator,
+              operands,
+              result,
+            } => ast::r#trait::Operator::Infix {
+              operator,
+              operands: (
+                self.lower_type(&inner, &operands.0)?,
+                self.lower_type(&inner, &operands.1)?,
+              ),
+              result: self.lower_type(&inner, &result)?,
+            },
+          }),
+        };
+
+        Ok((modifiers, item))
+      })
+      .collect::<Result<_, _>>()?;
+
+    Ok(ast::r#trait::Trait {
+      header: r#trait.header,
+      items,
+    })
+  }
+
+  fn lower_parameter(
+    &self,
+    context: &[String],
+    parameter: ast::util::Parameter<Type>,
+  ) -> Result<ast::util::Parameter<Tagged>, TypecheckerError<Type>> {
+    Ok(ast::util::Parameter {
+      name: parameter.name,
+      ty: self.lower_type(context, &parameter.ty)?,
+      default: parameter
+        .default
+        .map(|default| self.lower_expression(context, default))
+        .transpose()?,
+      rest: parameter.rest,
+    })
+  }
+
+  // expressions can't declare types, so everything in a body resolves where its item is declared
+  fn lower_expression(
+    &self,
+    context: &[String],
+    expression: ast::util::Expression<Type>,
+  ) -> Result<ast::util::Expression<Tagged>, TypecheckerError<Type>> {
+    let lower = |expression: Box<ast::util::Expression<Type>>| {
+      self.lower_expression(context, *expression).map(Box::new)
+    };
+
+    Ok(match expression {
+      ast::util::Expression::Block {
+        expressions,
+        has_value,
+      } => ast::util::Expression::Block {
+        expressions: self.lower_expressions(context, expressions)?,
+        has_value,
+      },
+      ast::util::Expression::If {
+        condition,
+        consequence,
+        alternative,
+      } => ast::util::Expression::If {
+        condition: lower(condition)?,
+        consequence: lower(consequence)?,
+        alternative: alternative.map(lower).transpose()?,
+      },
+      ast::util::Expression::Loop { label, body } => ast::util::Expression::Loop {
+        label,
+        body: lower(body)?,
+      },
+      ast::util::Expression::While {
+        label,
+        condition,
+        body,
+      } => ast::util::Expression::While {
+        label,
+        condition: lower(condition)?,
+        body: lower(body)?,
+      },
+      ast::util::Expression::For {
+        label,
+        pattern,
+        iterable,
+        body,
+      } => ast::util::Expression::For {
+        label,
+        pattern: lower_pattern(pattern),
+        iterable: lower(iterable)?,
+        body: lower(body)?,
+      },
+      ast::util::Expression::Match { scrutinee, arms } => ast::util::Expression::Match {
+        scrutinee: lower(scrutinee)?,
+        arms: arms
+          .into_iter()
+          .map(|(pattern, arm)| Ok((lower_pattern(pattern), self.lower_expression(context, arm)?)))
+          .collect::<Result<_, _>>()?,
+      },
+      ast::util::Expression::Return(value) => {
+        ast::util::Expression::Return(value.map(lower).transpose()?)
+      }
+      ast::util::Expression::Break { label, value } => ast::util::Expression::Break {
+        label,
+        value: value.map(lower).transpose()?,
+      },
+      ast::util::Expression::Continue { label } => ast::util::Expression::Continue { label },
+      ast::util::Expression::Let {
+        mutable,
+        pattern,
+        ty,
+        value,
+      } => ast::util::Expression::Let {
+        mutable,
+        pattern: lower_pattern(pattern),
+        ty: ty.map(|ty| self.lower_type(context, &ty)).transpose()?,
+        value: lower(value)?,
+      },
+      ast::util::Expression::Call {
+        expression,
+        arguments,
+      } => ast::util::Expression::Call {
+        expression: lower(expression)?,
+        arguments: arguments
+          .into_iter()
+          .map(|argument| {
+            Ok(ast::util::Argument {
+              name: argument.name,
+              value: self.lower_expression(context, argument.value)?,
+            })
+          })
+          .collect::<Result<_, _>>()?,
+      },
+      ast::util::Expression::Index { expression, index } => ast::util::Expression::Index {
+        expression: lower(expression)?,
+        index: lower(index)?,
+      },
+      ast::util::Expression::TupleIndex { expression, index } => {
+        ast::util::Expression::TupleIndex {
+          expression: lower(expression)?,
+          index,
+        }
+      }
+      ast::util::Expression::Field { expression, field } => ast::util::Expression::Field {
+        expression: lower(expression)?,
+        field,
+      },
+      ast::util::Expression::Cast { value, ty } => ast::util::Expression::Cast {
+        value: lower(value)?,
+        ty: self.lower_type(context, &ty)?,
+      },
+      ast::util::Expression::Try(expression) => ast::util::Expression::Try(lower(expression)?),
+      ast::util::Expression::Range { start, end, kind } => ast::util::Expression::Range {
+        start: lower(start)?,
+        end: lower(end)?,
+        kind,
+      },
+      ast::util::Expression::Literal(literal) => {
+        ast::util::Expression::Literal(self.lower_literal(context, literal)?)
+      }
+      ast::util::Expression::Infix {
+        operator,
+        operands: (a, b),
+      } => ast::util::Expression::Infix {
+        operator,
+        operands: (lower(a)?, lower(b)?),
+      },
+      ast::util::Expression::Prefix { operator, operand } => ast::util::Expression::Prefix {
+        operator,
+        operand: lower(operand)?,
+      },
+      ast::util::Expression::Identifier(name) => ast::util::Expression::Identifier(name),
+      ast::util::Expression::Path(path) => ast::util::Expression::Path(path),
+      ast::util::Expression::StructLiteral { path, fields } => {
+        ast::util::Expression::StructLiteral {
+          path,
+          fields: fields
+            .into_iter()
+            .map(|(name, value)| Ok((name, self.lower_expression(context, value)?)))
+            .collect::<Result<_, _>>()?,
+        }
+      }
+      ast::util::Expression::Spanned(span, expression) => {
+        ast::util::Expression::Spanned(span, lower(expression)?)
+      }
+    })
+  }
+
+  fn lower_expressions(
+    &self,
+    context: &[String],
+    expressions: Vec<ast::util::Expression<Type>>,
+  ) -> Result<Vec<ast::util::Expression<Tagged>>, TypecheckerError<Type>> {
+    expressions
+      .into_iter()
+      .map(|expression| self.lower_expression(context, expression))
+      .collect()
+  }
+
+  fn lower_literal(
+    &self,
+    context: &[String],
+    literal: ast::util::Literal<Type>,
+  ) -> Result<ast::util::Literal<Tagged>, TypecheckerError<Type>> {
+    Ok(match literal {
+      ast::util::Literal::Char(c) => ast::util::Literal::Char(c),
+      ast::util::Literal::String(s) => ast::util::Literal::String(s),
+      ast::util::Literal::Tuple(expressions) => {
+        ast::util::Literal::Tuple(self.lower_expressions(context, expressions)?)
+      }
+      ast::util::Literal::Record(fields) => ast::util::Literal::Record(
+        fields
+          .into_iter()
+          .map(|(name, value)| Ok((name, self.lower_expression(context, value)?)))
+          .collect::<Result<_, _>>()?,
+      ),
+      ast::util::Literal::Number(n) => ast::util::Literal::Number(lower_number(n)),
+      ast::util::Literal::Array(expressions) => {
+        ast::util::Literal::Array(self.lower_expressions(context, expressions)?)
+      }
+      ast::util::Literal::Bool(b) => ast::util::Literal::Bool(b),
+      ast::util::Literal::Closure {
+        parameters,
+        ty,
+        body,
+      } => ast::util::Literal::Closure {
+        parameters: parameters
+          .into_iter()
+          .map(|parameter| {
+            Ok(ast::util::ClosureParameter {
+              name: parameter.name,
+              ty: parameter
+                .ty
+                .map(|ty| self.lower_type(context, &ty))
+                .transpose()?,
+            })
+          })
+          .collect::<Result<_, _>>()?,
+        ty: ty.map(|ty| self.lower_type(context, &ty)).transpose()?,
+        body: Box::new(self.lower_expression(context, *body)?),
+      },
+    })
+  }
+
+  fn lower_types(
+    &self,
+    context: &[String],
+    types: &[Type],
+  ) -> Result<Vec<Tagged>, TypecheckerError<Type>> {
+    types
+      .iter()
+      .map(|ty| self.lower_type(context, ty))
+      .collect()
+  }
+
+  // the first segment of a path is found in the nearest declaration that has it, and the rest of
+  // the path is followed from there, like `lookup_type` does
+  pub fn lower_type(
+    &self,
+    context: &[String],
+    ty: &Type,
+  ) -> Result<Tagged, TypecheckerError<Type>> {
+    Ok(match ty {
+      Type::Named(path, arguments) => {
+        let unresolved = || TypecheckerError::UnresolvedType {
+          name: path.join("::"),
+        };
+
+        let scope = (0..=context.len())
+          .rev()
+          .map(|i| &context[..i])
+          .find(|scope| {
+            self
+              .declarations
+              .contains_key(&[scope, &path[..1]].concat())
+          })
+          .ok_or_else(unresolved)?;
+
+        let id = self
+          .declarations
+          .get(&[scope, &path[..]].concat())
+          .ok_or_else(unresolved)?;
+
+        Tagged::Named(*id, self.lower_types(context, arguments)?)
+      }
+      Type::Function(parameters, ty) => Tagged::Function(
+        self.lower_types(context, parameters)?,
+        Box::new(self.lower_type(context, ty)?),
+      ),
+      Type::Tuple(types) => Tagged::Tuple(self.lower_types(context, types)?),
+      Type::Record(fields) => Tagged::Record(
+        fields
+          .iter()
+          .map(|(name, ty)| Ok((name.clone(), self.lower_type(context, ty)?)))
+          .collect::<Result<_, _>>()?,
+      ),
+      Type::Array(ty) => Tagged::Array(Box::new(self.lower_type(context, ty)?)),
+      Type::FixedArray(ty, n) => Tagged::FixedArray(Box::new(self.lower_type(context, ty)?), *n),
+      Type::Reference(ty, mutable) => {
+        Tagged::Reference(Box::new(self.lower_type(context, ty)?), *mutable)
+      }
+      // two paths can name the same item, so the members are merged again once they're tagged
+      Type::Union(types) => Tagged::union(
+        types
+          .iter()
+          .map(|ty| self.lower_type(context, ty))
+          .collect::<Result<Vec<_>, _>>()?,
+      ),
+      Type::Infer(id) => Tagged::Infer(*id),
+      ty => map0!(
+        ty,
+        Type,
+        Tagged,
+        |ty| ty,
+        Bool,
+        I8,
+        I16,
+        I32,
+        I64,
+        I128,
+        U8,
+        U16,
+        U32,
+        U64,
+        U128,
+        F32,
+        F64,
+        Char,
+        Never
+      ),
+    })
+  }
+}
+
+// patterns have no types in them, only the parameter of the tree changes
+fn lower_pattern(pattern: ast::util::Pattern<Type>) -> ast::util::Pattern<Tagged> {
+  match pattern {
+    ast::util::Pattern::Wildcard => ast::util::Pattern::Wildcard,
+    ast::util::Pattern::Identifier(name) => ast::util::Pattern::Identifier(name),
+    ast::util::Pattern::Literal(literal) => ast::util::Pattern::Literal(match literal {
+      ast::util::Literal::Char(c) => ast::util::Literal::Char(c),
+      ast::util::Literal::String(s) => ast::util::Literal::String(s),
+      ast::util::Literal::Number(n) => ast::util::Literal::Number(lower_number(n)),
+      ast::util::Literal::Bool(b) => ast::util::Literal::Bool(b),
+      _ => unreachable!(),
+    }),
+    ast::util::Pattern::Tuple(patterns) => {
+      ast::util::Pattern::Tuple(patterns.into_iter().map(lower_pattern).collect())
+    }
+    ast::util::Pattern::Variant { path, fields } => ast::util::Pattern::Variant {
+      path,
+      fields: fields.into_iter().map(lower_pattern).collect(),
+    },
+    ast::util::Pattern::Struct { path, fields } => ast::util::Pattern::Struct {
+      path,
+      fields: fields
+        .into_iter()
+        .map(|(name, pattern)| (name, lower_pattern(pattern)))
+        .collect(),
+    },
+  }
+}
+
+fn lower_number(n: ast::util::NumberLiteral<Type>) -> ast::util::NumberLiteral<Tagged> {
+  match n {
+    ast::util::NumberLiteral::Integer(n) => ast::util::NumberLiteral::Integer(n),
+    ast::util::NumberLiteral::I8(n) => ast::util::NumberLiteral::I8(n),
+    ast::util::NumberLiteral::I16(n) => ast::util::NumberLiteral::I16(n),
+    ast::util::NumberLiteral::I32(n) => ast::util::NumberLiteral::I32(n),
+    ast::util::NumberLiteral::I64(n) => ast::util::NumberLiteral::I64(n),
+    ast::util::NumberLiteral::I128(n) => ast::util::NumberLiteral::I128(n),
+    ast::util::NumberLiteral::U8(n) => ast::util::NumberLiteral::U8(n),
+    ast::util::NumberLiteral::U16(n) => ast::util::NumberLiteral::U16(n),
+    ast::util::NumberLiteral::U32(n) => ast::util::NumberLiteral::U32(n),
+    ast::util::NumberLiteral::U64(n) => ast::util::NumberLiteral::U64(n),
+    ast::util::NumberLiteral::U128(n) => ast::util::NumberLiteral::U128(n),
+    ast::util::NumberLiteral::F32(n) => ast::util::NumberLiteral::F32(n),
+    ast::util::NumberLiteral::F64(n) => ast::util::NumberLiteral::F64(n),
+    ast::util::NumberLiteral::_PhantomData(_) => {
+      ast::util::NumberLiteral::_PhantomData(PhantomData)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{lexer::lexer::Lexer, parser::parser::Parser};
+
+  fn lower(source: &str) -> (Typechecker, ast::module::Module<Tagged>) {
+    let tokens = Lexer::new(source).lex(false).unwrap();
+    let module = Parser::new(tokens.iter()).parse().unwrap();
+    let mut typechecker = Typechecker::new();
+    typechecker.typecheck(module.clone()).unwrap();
+    let lowered = typechecker.lower(module).unwrap();
+    (typechecker, lowered)
+  }
+
+  fn id(typechecker: &Typechecker, path: &[&str]) -> uuid::Uuid {
+    let path: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+    typechecker.declarations[&path]
+  }
+
+  fn items(module: &ast::module::Module<Tagged>, name: &str) -> ast::module::Module<Tagged> {
+    module
+      .items
+      .iter()
+      .find_map(|item| match &item.kind {
+        ast::module::ItemKind::Struct(s) if s.header.name == name => Some(s.module.clone()),
+        _ => None,
+      })
+      .unwrap()
+  }
+
+  fn field(module: &ast::module::Module<Tagged>, name: &str) -> Tagged {
+    module
+      .items
+      .iter()
+      .find_map(|item| match &item.kind {
+        ast::module::ItemKind::Field { name: n, ty, .. } if n == name => Some(ty.clone()),
+        _ => None,
+      })
+      .unwrap()
+  }
+
+  #[test]
+  fn test_lower() {
+    let (typechecker, module) =
+      lower("struct Point { x: i32; }; struct Line { a: Point; b: &[Point]; };");
+    let point = id(&typechecker, &["Point"]);
+    let line = items(&module, "Line");
+
+    assert_eq!(field(&line, "a"), Tagged::Named(point, vec![]));
+    assert_eq!(
+      field(&line, "b"),
+      Tagged::Reference(
+        Box::new(Tagged::Array(Box::new(Tagged::Named(point, vec![])))),
+        false
+      )
+    );
+    assert_eq!(field(&items(&module, "Point"), "x"), Tagged::I32);
+    assert!(matches!(
+      typechecker.types[&point].1,
+      crate::typechecker::scope::ItemKind::Struct(_)
+    ));
+
+    // type parameters and nested structs are tagged with their own ids, and a nested name shadows
+    // an outer one
+    let (typechecker, module) = lower(
+      "struct Inner {}; struct Box<T> { pub struct Inner {}; a: T; b: Inner; c: Box<T>; }; struct Other { d: Box<Box::Inner>; };",
+    );
+    let r#box = items(&module, "Box");
+    let t = Tagged::Named(id(&typechecker, &["Box", "T"]), vec![]);
+    let nested = Tagged::Named(id(&typechecker, &["Box", "Inner"]), vec![]);
+
+    assert_eq!(field(&r#box, "a"), t);
+    assert_eq!(field(&r#box, "b"), nested);
+    assert_eq!(
+      field(&r#box, "c"),
+      Tagged::Named(id(&typechecker, &["Box"]), vec![t])
+    );
+    assert_eq!(
+      field(&items(&module, "Other"), "d"),
+      Tagged::Named(id(&typechecker, &["Box"]), vec![nested])
+    );
+    assert_ne!(
+      id(&typechecker, &["Inner"]),
+      id(&typechecker, &["Box", "Inner"])
+    );
+
+    // an impl block's own names for the type parameters are the struct's
+    let (typechecker, module) =
+      lower("struct Box<T> { value: T; }; impl Box<U> { pub fn get(): U self.value; };");
+    let t = Tagged::Named(id(&typechecker, &["Box", "T"]), vec![]);
+    match &module.items[1].kind {
+      ast::module::ItemKind::Impl { ty, module } => {
+        assert_eq!(
+          *ty,
+          Tagged::Named(id(&typechecker, &["Box"]), vec![t.clone()])
+        );
+        match &module.items[0].kind {
+          ast::module::ItemKind::Function(f) => assert_eq!(f.header.ty, Some(t)),
+          kind => panic!("expected a function, found {:?}", kind),
+        }
+      }
+      kind => panic!("expected an impl block, found {:?}", kind),
+    }
+
+    // `Self` is the struct it's written in, or the one an impl block is for
+    let (typechecker, module) = lower(
+      "struct Box<T> { next: &Self; pub struct Inner { outer: &Self; }; }; impl Box<U> { pub fn me(): Self self; };",
+    );
+    let t = Tagged::Named(id(&typechecker, &["Box", "T"]), vec![]);
+    let r#box = Tagged::Named(id(&typechecker, &["Box"]), vec![t]);
+    let inner = Tagged::Named(id(&typechecker, &["Box", "Inner"]), vec![]);
+
+    assert_eq!(
+      field(&items(&module, "Box"), "next"),
+      Tagged::Reference(Box::new(r#box.clone()), false)
+    );
+    assert_eq!(
+      field(&items(&items(&module, "Box"), "Inner"), "outer"),
+      Tagged::Reference(Box::new(inner), false)
+    );
+    match &module.items[1].kind {
+      ast::module::ItemKind::Impl { module, .. } => match &module.items[0].kind {
+        ast::module::ItemKind::Function(f) => assert_eq!(f.header.ty, Some(r#box)),
+        kind => panic!("expected a function, found {:?}", kind),
+      },
+      kind => panic!("expected an impl block, found {:?}", kind),
+    }
+  }
+}
@@ -1,2 +1,3 @@
+pub mod lower;
 pub mod scope;
 pub mod typechecker;
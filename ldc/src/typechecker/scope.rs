@@ -1,5 +1,9 @@
 use crate::parser::ast;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+  cell::RefCell,
+  collections::{BTreeMap, HashMap},
+  rc::Rc,
+};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -16,28 +20,84 @@ impl Scope {
     }
   }
 
+  // inserting a name that is already bound in this scope shadows the earlier binding
   pub fn insert(&mut self, name: String, item: Item) {
     self.items.insert(name, item);
   }
 
+  // the enclosing scopes, innermost first. scopes can be nested arbitrarily deep, so they're walked
+  // in a loop rather than recursively
+  pub fn ancestors(&self) -> impl Iterator<Item = Rc<RefCell<Scope>>> {
+    std::iter::successors(self.parent.clone(), |scope| scope.borrow().parent.clone())
+  }
+
+  // the number of enclosing scopes
+  pub fn depth(&self) -> usize {
+    self.ancestors().count()
+  }
+
+  // replaces the nearest binding of the name, or binds it in the outermost scope if there is none
   pub fn set(&mut self, name: String, item: Item) {
-    match self.items.get_mut(&name) {
-      Some(i) => *i = item,
-      None => match &self.parent {
-        Some(parent) => parent.borrow_mut().set(name, item),
-        None => self.insert(name, item),
-      },
+    if let Some(i) = self.items.get_mut(&name) {
+      *i = item;
+      return;
+    }
+
+    let ancestors = self.ancestors().collect::<Vec<_>>();
+    match ancestors
+      .iter()
+      .find(|scope| scope.borrow().items.contains_key(&name))
+      .or(ancestors.last())
+    {
+      Some(scope) => scope.borrow_mut().insert(name, item),
+      None => self.insert(name, item),
     }
   }
 
   // TODO: don't clone
   pub fn get(&self, name: &str) -> Option<Item> {
-    match self.items.get(name) {
-      Some(item) => Some(item.clone()),
-      None => match &self.parent {
-        Some(parent) => parent.borrow().get(name),
-        None => None,
-      },
+    self.get_local(name).or_else(|| {
+      self
+        .ancestors()
+        .find_map(|scope| scope.borrow().get_local(name))
+    })
+  }
+
+  pub fn get_local(&self, name: &str) -> Option<Item> {
+    self.items.get(name).cloned()
+  }
+
+  // every name visible from this scope, sorted, with the nearest binding of each
+  pub fn all_names(&self) -> Vec<(String, ItemKind)> {
+    let mut names = BTreeMap::new();
+    let mut insert = |items: &HashMap<String, Item>| {
+      for (name, Item(_, kind)) in items {
+        names.insert(name.clone(), kind.clone());
+      }
+    };
+
+    // outermost first, so that the nearer bindings replace the ones they shadow
+    let ancestors = self.ancestors().collect::<Vec<_>>();
+    for scope in ancestors.iter().rev() {
+      insert(&scope.borrow().items);
+    }
+    insert(&self.items);
+
+    names.into_iter().collect()
+  }
+}
+
+// dropping a scope drops its parent if nothing else holds it, and so on up the chain, which would
+// recurse as deep as the scopes are nested. the parents are unlinked one at a time instead
+impl Drop for Scope {
+  fn drop(&mut self) {
+    let mut parent = self.parent.take();
+
+    while let Some(scope) = parent {
+      parent = match Rc::try_unwrap(scope) {
+        Ok(scope) => scope.into_inner().parent.take(),
+        Err(_) => None,
+      };
     }
   }
 }
@@ -51,6 +111,8 @@ impl Item {
   }
 }
 
+// items hold whole declarations (function bodies included), so the variants are far apart in size
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum ItemKind {
   Function(ast::function::Function<ast::util::Type<Vec<String>>>),
@@ -59,5 +121,101 @@ pub enum ItemKind {
   Trait(ast::r#trait::Trait<ast::util::Type<Vec<String>>>),
   PrefixOperator(ast::operator::Prefix<ast::util::Type<Vec<String>>>),
   InfixOperator(ast::operator::Infix<ast::util::Type<Vec<String>>>),
-  Variable(ast::util::Type<Vec<String>>),
+  Variable(ast::util::Type<Vec<String>>, bool), // the type, and whether it can be assigned to
+  Const(ast::util::Type<Vec<String>>, i128),    // the declared type and the folded value
+  TypeParameter(ast::util::TypeParameter),
+  Variant(Uuid, ast::r#enum::Variant<ast::util::Type<Vec<String>>>), // the enum's id and the variant
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  type Type = ast::util::Type<Vec<String>>;
+
+  fn variable(ty: Type) -> Item {
+    Item::new(ItemKind::Variable(ty, false))
+  }
+
+  #[test]
+  fn test_shadowing() {
+    let parent = Rc::new(RefCell::new(Scope::default()));
+    let a = variable(Type::Bool);
+    parent.borrow_mut().insert("a".to_string(), a.clone());
+
+    let mut scope = Scope::new(Some(parent.clone()));
+    assert_eq!(scope.get("a"), Some(a.clone()));
+    assert_eq!(scope.get_local("a"), None);
+
+    let shadow = variable(Type::Char);
+    scope.insert("a".to_string(), shadow.clone());
+    assert_eq!(scope.get("a"), Some(shadow.clone()));
+    assert_eq!(scope.get_local("a"), Some(shadow));
+    assert_eq!(parent.borrow().get("a"), Some(a));
+
+    let again = variable(Type::I32);
+    scope.insert("a".to_string(), again.clone());
+    assert_eq!(scope.get("a"), Some(again));
+
+    assert_eq!(scope.get("b"), None);
+  }
+
+  #[test]
+  fn test_all_names() {
+    let root = Rc::new(RefCell::new(Scope::default()));
+    root
+      .borrow_mut()
+      .insert("a".to_string(), variable(Type::Bool));
+    root
+      .borrow_mut()
+      .insert("b".to_string(), variable(Type::Bool));
+
+    let middle = Rc::new(RefCell::new(Scope::new(Some(root))));
+    middle
+      .borrow_mut()
+      .insert("b".to_string(), variable(Type::Char));
+    middle
+      .borrow_mut()
+      .insert("c".to_string(), variable(Type::Char));
+
+    let mut scope = Scope::new(Some(middle));
+    scope.insert("c".to_string(), variable(Type::I32));
+    scope.insert("d".to_string(), variable(Type::I32));
+
+    assert_eq!(
+      scope.all_names(),
+      vec![
+        ("a".to_string(), ItemKind::Variable(Type::Bool, false)),
+        ("b".to_string(), ItemKind::Variable(Type::Char, false)),
+        ("c".to_string(), ItemKind::Variable(Type::I32, false)),
+        ("d".to_string(), ItemKind::Variable(Type::I32, false)),
+      ]
+    );
+
+    assert_eq!(Scope::default().all_names(), vec![]);
+  }
+
+  #[test]
+  fn test_deep_nesting() {
+    let root = Rc::new(RefCell::new(Scope::default()));
+    let a = variable(Type::Bool);
+    root.borrow_mut().insert("a".to_string(), a.clone());
+
+    let mut scope = root.clone();
+    for _ in 0..100_000 {
+      scope = Rc::new(RefCell::new(Scope::new(Some(scope))));
+    }
+
+    let mut scope = scope.borrow_mut();
+    assert_eq!(scope.depth(), 100_000);
+    assert_eq!(root.borrow().depth(), 0);
+    assert_eq!(scope.get("a"), Some(a));
+    assert_eq!(scope.get("b"), None);
+
+    // assigning reaches the binding at the root too
+    let b = variable(Type::Char);
+    scope.set("a".to_string(), b.clone());
+    assert_eq!(root.borrow().get_local("a"), Some(b));
+    assert_eq!(scope.all_names().len(), 1);
+  }
 }
@@ -1,65 +1,316 @@
 use super::scope::{Item, ItemKind, Scope};
-use crate::{error::TypecheckerError, parser::ast, union};
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use crate::{
+  error::{CompileError, Error, TypecheckerError, TypecheckerWarning},
+  lexer::lexer::Lexer,
+  parser::{ast, parser::Parser},
+  union,
+};
+use std::{
+  cell::RefCell,
+  collections::{BTreeMap, HashMap, HashSet},
+  ops::Range,
+  rc::Rc,
+};
 use uuid::Uuid;
 
 type Type = ast::util::Type<Vec<String>>;
-// type Tagged = ast::util::Type<Uuid>;
+// a type whose names have been resolved to the items that declare them
+pub type Tagged = ast::util::Type<Uuid>;
+
+#[derive(Debug, Clone)]
+pub enum Loop {
+  // `while` and `for` produce unit, so that's all they can break with
+  Unit,
+  // the types that a `loop` has been broken with so far
+  Infinite(Vec<Type>),
+}
+
+// an inference variable, as what it's been solved with so far and the closure parameter it was made
+// for, if there is one
+#[derive(Debug, Clone)]
+pub struct Inference {
+  pub ty: Option<Type>,
+  pub parameter: Option<String>,
+}
+
+// what a match arm covers of the enum being matched on
+#[derive(Debug, Clone)]
+enum Coverage {
+  All,
+  Variant(String),
+  // some values of a variant, like a literal in one of its fields
+  Partial,
+}
 
 #[derive(Debug, Clone)]
 pub struct Typechecker {
   pub types: HashMap<Uuid, Item>,
+  // the ids in `types` by the path they're declared at from the top-level module, with a struct's
+  // type parameters and a trait's `Self` under its own path
+  pub declarations: HashMap<ast::util::Path, Uuid>,
+  // the declared return types of the enclosing functions and closures, innermost last
+  pub return_types: Vec<Option<Type>>,
+  // the enclosing loops and their labels, innermost last. functions and closures push `None`, since a
+  // loop outside of them can't be broken out of
+  pub loops: Vec<Option<(Loop, Option<String>)>>,
+  pub warnings: Vec<TypecheckerWarning>,
+  // every variable declared so far, and the ones that have been read
+  pub variables: Vec<(Uuid, String)>,
+  pub used: HashSet<Uuid>,
+  // the outer variables read by each closure, in the order the closures are checked
+  pub captures: Vec<Vec<(Uuid, String)>>,
+  // the enclosing closures, innermost last, as their index in `captures` and the number of
+  // variables declared before them
+  pub closures: Vec<(usize, usize)>,
+  // the structs being checked, whose private members are accessible
+  pub structs: Vec<ast::r#struct::Struct<Type>>,
+  // results of `satisfies`, keyed on reduced types. it only depends on the types themselves, so
+  // substituted types get their own entries and nothing has to be invalidated
+  pub satisfied: HashMap<(Type, Type), bool>,
+  // the span of every identifier read, and the item it refers to
+  pub references: Vec<(Range<usize>, Item)>,
+  // every named type that's been resolved, as it was written, and the id of what it names
+  pub type_references: Vec<(ast::util::Path, Uuid)>,
+  // the type of every spanned expression
+  pub expression_types: HashMap<Range<usize>, Type>,
+  // the inference variables by id. each function has to solve the ones made while checking it
+  pub inferences: Vec<Inference>,
+  // the innermost spanned expression that the error being returned came from, until the module
+  // checking the item it's in collects it
+  pub span: Option<Range<usize>>,
+}
+
+impl Default for Typechecker {
+  fn default() -> Typechecker {
+    Typechecker::new()
+  }
 }
 
 impl Typechecker {
   pub fn new() -> Typechecker {
     Typechecker {
       types: HashMap::new(),
+      declarations: HashMap::new(),
+      return_types: Vec::new(),
+      loops: Vec::new(),
+      warnings: Vec::new(),
+      variables: Vec::new(),
+      used: HashSet::new(),
+      captures: Vec::new(),
+      closures: Vec::new(),
+      structs: Vec::new(),
+      satisfied: HashMap::new(),
+      references: Vec::new(),
+      type_references: Vec::new(),
+      expression_types: HashMap::new(),
+      inferences: Vec::new(),
+      span: None,
+    }
+  }
+
+  pub fn satisfies(&mut self, a: &Type, b: &Type) -> bool {
+    let (a, b) = (self.resolve(a), self.resolve(b));
+
+    // comparing solves the variables, so those results can't be kept
+    if !a.inferences().is_empty() || !b.inferences().is_empty() {
+      return self.unify(&a, &b);
+    }
+
+    *self
+      .satisfied
+      .entry((a.reduce(), b.reduce()))
+      .or_insert_with(|| a.satisfies(&b))
+  }
+
+  pub fn fresh(&mut self, parameter: Option<String>) -> Type {
+    self.inferences.push(Inference {
+      ty: None,
+      parameter,
+    });
+    Type::Infer(self.inferences.len() - 1)
+  }
+
+  // fills in the inference variables that have been solved so far
+  pub fn resolve(&self, ty: &Type) -> Type {
+    ty.fill(&|id| self.inferences[id].ty.clone())
+  }
+
+  // whether `found` can satisfy `expected`, solving the inference variables in them where their
+  // shapes agree. a variable is solved with the type on the other side, so that the variables made
+  // for an empty array or a closure parameter take the type they're first used as. nothing is
+  // solved if they don't agree, so a failed attempt can be followed by another
+  pub fn unify(&mut self, found: &Type, expected: &Type) -> bool {
+    let inferences = self.inferences.clone();
+    let unified = self.solve(found, expected);
+    if !unified {
+      self.inferences = inferences;
+    }
+
+    unified
+  }
+
+  fn solve(&mut self, found: &Type, expected: &Type) -> bool {
+    let found = self.resolve(found).reduce();
+    let expected = self.resolve(expected).reduce();
+
+    if found.inferences().is_empty() && expected.inferences().is_empty() {
+      return self.satisfies(&found, &expected);
+    }
+
+    match (found, expected) {
+      (Type::Infer(a), Type::Infer(b)) if a == b => true,
+      (Type::Infer(id), ty) | (ty, Type::Infer(id)) => {
+        // a variable can't be solved with a type that contains it
+        if ty.inferences().contains(&id) {
+          return false;
+        }
+
+        self.inferences[id].ty = Some(ty);
+        true
+      }
+      (Type::Never, _) => true,
+      (Type::Named(a, x), Type::Named(b, y)) => {
+        a == b && x.len() == y.len() && x.iter().zip(&y).all(|(x, y)| self.solve(x, y))
+      }
+      (Type::Function(a, x), Type::Function(b, y)) => {
+        a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| self.solve(b, a)) && self.solve(&x, &y)
+      }
+      (Type::Tuple(a), Type::Tuple(b)) => {
+        a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| self.solve(a, b))
+      }
+      (Type::Record(a), Type::Record(b)) => {
+        ast::util::same_fields(&a, &b) && a.iter().zip(&b).all(|((_, a), (_, b))| self.solve(a, b))
+      }
+      (Type::Array(a) | Type::FixedArray(a, _), Type::Array(b)) => self.solve(&a, &b),
+      (Type::FixedArray(a, n), Type::FixedArray(b, m)) => n == m && self.solve(&a, &b),
+      (Type::Reference(a, x), Type::Reference(b, y)) => (x || !y) && self.solve(&a, &b),
+      (Type::Union(a), b) => a.iter().all(|a| self.solve(a, &b)),
+      // each member is tried on its own, so one that fails doesn't leave anything solved
+      (a, Type::Union(b)) => b.iter().any(|b| self.unify(&a, b)),
+      _ => false,
     }
   }
 
   pub fn typecheck(
     &mut self,
     module: ast::module::Module<Type>,
-  ) -> Result<(), TypecheckerError<Type>> {
+  ) -> Result<(), Vec<Error<TypecheckerError<Type>>>> {
     self
       .typecheck_module(Rc::new(RefCell::new(Scope::default())), module, false)
       .map(|_| ())
   }
 
+  // lexes, parses and typechecks a source string in one go
+  pub fn typecheck_str(&mut self, source: &str) -> Result<(), CompileError<Type>> {
+    let tokens = Lexer::new(source).lex(false)?;
+    let mut parser = Parser::new(tokens.iter());
+    parser.spans = true;
+    let module = parser.parse()?;
+    Ok(self.typecheck(module)?)
+  }
+
   pub fn typecheck_module(
     &mut self,
     scope: Rc<RefCell<Scope>>,
     module: ast::module::Module<Type>,
     static_: bool,
-  ) -> Result<(), TypecheckerError<Type>> {
+  ) -> Result<(), Vec<Error<TypecheckerError<Type>>>> {
+    // static and instance items live in separate scopes, so they may share names
+    let mut names = HashSet::new();
+    // items are independent of each other's bodies, so every one of them is checked
+    let mut errors = Vec::new();
+    // `Self` is resolved once the impl blocks are in their structs
+    let module = resolve_self(self.merge_impls(module, &mut errors));
+
     for item in &module.items {
       if static_ ^ item.modifiers.static_ {
         continue;
       }
 
+      let (name, kind) = match &item.kind {
+        ast::module::ItemKind::Function(f) => (&f.header.name, "function"),
+        ast::module::ItemKind::Struct(s) => (&s.header.name, "struct"),
+        ast::module::ItemKind::Enum(e) => (&e.header.name, "enum"),
+        ast::module::ItemKind::Trait(t) => (&t.header.name, "trait"),
+        ast::module::ItemKind::Const { name, .. } => (name, "const"),
+        ast::module::ItemKind::Field { name, .. } => (name, "field"),
+        // operators are found through the types of their operands, so they aren't named
+        ast::module::ItemKind::Operator(_) => continue,
+        ast::module::ItemKind::Impl { .. } => unreachable!(),
+      };
+
+      if !names.insert(name.clone()) {
+        errors.push(self.error(
+          &item.span,
+          TypecheckerError::DuplicateDefinition {
+            name: name.clone(),
+            kind,
+          },
+        ));
+        continue;
+      }
+
       match &item.kind {
+        // functions aren't types, so they're kept by id without being declared under a path
         ast::module::ItemKind::Function(f) => {
-          scope.borrow_mut().insert(
-            f.header.name.clone(),
-            Item::new(ItemKind::Function(f.clone())),
-          );
+          let item = Item::new(ItemKind::Function(f.clone()));
+          self.types.insert(item.0, item.clone());
+          scope.borrow_mut().insert(f.header.name.clone(), item);
         }
         ast::module::ItemKind::Struct(s) => {
-          let item = Item::new(ItemKind::Struct(s.clone()));
-          scope
-            .borrow_mut()
-            .insert(s.header.name.clone(), item.clone());
-          self.types.insert(item.0, item);
+          let item = self.declare(vec![s.header.name.clone()], ItemKind::Struct(s.clone()));
+          self.declare_nested(vec![s.header.name.clone()], s);
+          scope.borrow_mut().insert(s.header.name.clone(), item);
         }
         ast::module::ItemKind::Enum(e) => {
-          let item = Item::new(ItemKind::Enum(e.clone()));
+          for variant in &e.variants {
+            let mut fields = HashSet::new();
+            for name in variant.names.iter().filter(|name| !fields.insert(*name)) {
+              errors.push(self.error(
+                &item.span,
+                TypecheckerError::DuplicateDefinition {
+                  name: name.clone(),
+                  kind: "field",
+                },
+              ));
+            }
+          }
+
+          let item = self.declare(vec![e.header.name.clone()], ItemKind::Enum(e.clone()));
           scope
             .borrow_mut()
             .insert(e.header.name.clone(), item.clone());
-          self.types.insert(item.0, item);
+
+          // variants are constructors in the same scope as the enum
+          for variant in &e.variants {
+            scope.borrow_mut().insert(
+              variant.name.clone(),
+              Item::new(ItemKind::Variant(item.0, variant.clone())),
+            );
+          }
+        }
+        ast::module::ItemKind::Trait(t) => {
+          let item = self.declare(vec![t.header.name.clone()], ItemKind::Trait(t.clone()));
+          scope.borrow_mut().insert(t.header.name.clone(), item);
         }
-        _ => todo!(),
+        // the value is folded here, so a constant can only refer to the ones declared before it
+        ast::module::ItemKind::Const { name, ty, value } => {
+          let kind = match value.const_eval_with(&|name| constant(&scope, name)) {
+            Some(value) => ItemKind::Const(ty.clone(), value),
+            None => {
+              errors.push(self.error(
+                &item.span,
+                TypecheckerError::NonConstantValue { name: name.clone() },
+              ));
+              ItemKind::Variable(ty.clone(), false)
+            }
+          };
+
+          scope.borrow_mut().insert(name.clone(), Item::new(kind));
+        }
+        // fields are only reached through an instance, so they aren't in scope themselves
+        ast::module::ItemKind::Field { .. } => (),
+        ast::module::ItemKind::Operator(_) | ast::module::ItemKind::Impl { .. } => unreachable!(),
       }
     }
 
@@ -68,224 +319,5576 @@ impl Typechecker {
         continue;
       }
 
-      match &item.kind {
-        ast::module::ItemKind::Function(f) => {
-          self.typecheck_function(scope.clone(), f.clone())?;
-        }
+      self.span = None;
+      let result = match &item.kind {
+        ast::module::ItemKind::Function(f) => self
+          .typecheck_function(scope.clone(), f.clone())
+          .map_err(|error| vec![self.error(&item.span, error)]),
         ast::module::ItemKind::Struct(s) => {
-          self.typecheck_struct(scope.clone(), s.clone())?;
+          self.typecheck_struct(scope.clone(), s.clone(), &item.span)
+        }
+        ast::module::ItemKind::Enum(e) => self
+          .typecheck_enum(scope.clone(), e.clone())
+          .map_err(|error| vec![self.error(&item.span, error)]),
+        ast::module::ItemKind::Trait(t) => self
+          .typecheck_trait(scope.clone(), t.clone())
+          .map_err(|error| vec![self.error(&item.span, error)]),
+        ast::module::ItemKind::Operator(o) => self
+          .typecheck_operator(scope.clone(), o.clone())
+          .map_err(|error| vec![self.error(&item.span, error)]),
+        ast::module::ItemKind::Const { ty, value, .. } => self
+          .typecheck_const(scope.clone(), ty, value.clone())
+          .map_err(|error| vec![self.error(&item.span, error)]),
+        ast::module::ItemKind::Field { ty, default, .. } => match default {
+          Some(default) => self.typecheck_const(scope.clone(), ty, default.clone()),
+          None => self.resolve_type(scope.clone(), ty),
+        }
+        .map_err(|error| vec![self.error(&item.span, error)]),
+        ast::module::ItemKind::Impl { .. } => unreachable!(),
+      };
+
+      if let Err(mut item_errors) = result {
+        errors.append(&mut item_errors);
+      }
+    }
+
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors)
+    }
+  }
+
+  // an error is reported at the innermost spanned expression it came from, or the item it's in if
+  // there isn't one. without spans it's at the start of the source
+  fn error(
+    &mut self,
+    item: &Option<Range<usize>>,
+    error: TypecheckerError<Type>,
+  ) -> Error<TypecheckerError<Type>> {
+    Error(
+      self
+        .span
+        .take()
+        .or_else(|| item.clone())
+        .unwrap_or_default(),
+      error,
+    )
+  }
+
+  // moves the items of every impl block into the struct it names, which is declared in the same
+  // module or nested in one that is. a method defined twice is then a duplicate in the struct
+  fn merge_impls(
+    &self,
+    mut module: ast::module::Module<Type>,
+    errors: &mut Vec<Error<TypecheckerError<Type>>>,
+  ) -> ast::module::Module<Type> {
+    let (impls, items) = module
+      .items
+      .into_iter()
+      .partition::<Vec<_>, _>(|item| matches!(item.kind, ast::module::ItemKind::Impl { .. }));
+    module.items = items;
+
+    for item in impls {
+      let ast::module::ItemKind::Impl { ty, module: items } = item.kind else {
+        unreachable!()
+      };
+
+      let span = item.span.clone().unwrap_or_default();
+      let (path, arguments) = match &ty {
+        Type::Named(path, arguments) => (path, arguments),
+        _ => {
+          errors.push(Error(
+            span,
+            TypecheckerError::UnresolvedType {
+              name: ty.to_string(),
+            },
+          ));
+          continue;
+        }
+      };
+
+      match nested_struct(&mut module, path) {
+        None => errors.push(Error(
+          span,
+          TypecheckerError::UnresolvedType {
+            name: path.join("::"),
+          },
+        )),
+        Some(s) if s.header.type_parameters.len() != arguments.len() => errors.push(Error(
+          span,
+          TypecheckerError::InvalidTypeArguments {
+            name: path.join("::"),
+            expected: s.header.type_parameters.len(),
+            found: arguments.len(),
+          },
+        )),
+        // the block names the struct's type parameters however it likes, so its items are renamed
+        // to use the struct's names
+        Some(s) => {
+          let names = renames(arguments, &s.header.type_parameters);
+          s.module.items.extend(items.substitute(&names).items)
         }
-        _ => todo!(),
       }
     }
 
-    Ok(())
+    module
   }
 
   pub fn typecheck_function(
-    &self,
+    &mut self,
     parent: Rc<RefCell<Scope>>,
     function: ast::function::Function<Type>,
   ) -> Result<(), TypecheckerError<Type>> {
+    for parameter in &function.header.parameters {
+      self.resolve_type(parent.clone(), &parameter.ty)?;
+    }
+
+    if let Some(ty) = &function.header.ty {
+      self.resolve_type(parent.clone(), ty)?;
+    }
+
+    // defaults are checked outside of the function, so they can't refer to the other parameters
+    for parameter in &function.header.parameters {
+      if let Some(default) = &parameter.default {
+        let found = self.typecheck_argument(parent.clone(), default.clone(), &parameter.ty)?;
+        self.expect_type(default.span(), found, &parameter.ty)?;
+      }
+    }
+
     let mut scope = Scope::new(Some(parent));
+    let start = self.variables.len();
+    let inferences = self.inferences.len();
 
     for parameter in &function.header.parameters {
       scope.insert(
         parameter.name.clone(),
-        Item::new(ItemKind::Variable(parameter.ty.clone())),
+        self.variable(&parameter.name, parameter.binding(), false),
       );
     }
 
-    let body =
-      self.typecheck_expression(Rc::new(RefCell::new(scope.clone())), function.body.clone())?;
+    self.return_types.push(function.header.ty.clone());
+    self.loops.push(None);
+    let scope = Rc::new(RefCell::new(scope));
+    let body = match &function.header.ty {
+      Some(ty) => self.typecheck_argument(scope, function.body.clone(), ty),
+      None => self.typecheck_expression(scope, function.body.clone()),
+    };
+    self.loops.pop();
+    self.return_types.pop();
+    let body = body.inspect_err(|_| {
+      // the rest of the body wasn't checked, so unused variables can't be told apart
+      self.variables.truncate(start);
+    })?;
 
-    if let Some(ty) = &function.header.ty {
-      if !body.satisfies(ty) {
-        Err(TypecheckerError::InvalidType {
-          expected: ty.clone(),
-          found: body,
-        })?
+    // this covers the parameters and the bindings in the body, including those of closures
+    for (id, name) in self.variables.split_off(start) {
+      if !self.used.contains(&id) && !name.starts_with('_') {
+        self.warnings.push(TypecheckerWarning::UnusedVariable(name));
       }
     }
 
-    Ok(())
-  }
+    if let Some(ty) = &function.header.ty {
+      self.expect_type(function.body.span(), body, ty)?;
+    }
 
-  pub fn typecheck_struct(
-    &mut self,
-    parent: Rc<RefCell<Scope>>,
-    r#struct: ast::r#struct::Struct<Type>,
-  ) -> Result<(), TypecheckerError<Type>> {
-    let static_ = Rc::new(RefCell::new(Scope::new(Some(parent))));
-    self.typecheck_module(static_.clone(), r#struct.module.clone(), true)?;
+    // nothing outside of the function can use its variables, so they're as solved as they'll get
+    match self.inferences[inferences..]
+      .iter()
+      .find(|i| i.ty.is_none())
+    {
+      Some(Inference {
+        parameter: Some(name),
+        ..
+      }) => Err(TypecheckerError::CannotInferParameter { name: name.clone() }),
+      Some(_) => Err(TypecheckerError::CannotInfer),
+      None => Ok(()),
+    }
+  }
 
-    let instance = Rc::new(RefCell::new(Scope::new(Some(static_))));
-    self.typecheck_module(instance, r#struct.module, false)?;
+  // records a type-level item under its path, relative to the structs being checked. an item that
+  // was declared ahead of its struct being checked keeps the id it was given then
+  fn declare(&mut self, path: ast::util::Path, kind: ItemKind) -> Item {
+    let path = self
+      .structs
+      .iter()
+      .map(|s| s.header.name.clone())
+      .chain(path)
+      .collect::<Vec<_>>();
+    let id = self
+      .declarations
+      .get(&path)
+      .copied()
+      .unwrap_or_else(Uuid::new_v4);
 
-    Ok(())
+    let item = Item(id, kind);
+    self.declarations.insert(path, id);
+    self.types.insert(id, item.clone());
+    item
   }
 
-  pub fn typecheck_expression(
-    &self,
-    parent: Rc<RefCell<Scope>>,
-    expression: ast::util::Expression<Type>,
-  ) -> Result<Type, TypecheckerError<Type>> {
-    match expression {
-      ast::util::Expression::Block {
-        expressions,
-        has_value,
-      } => {
-        let mut value = None;
-
-        for expression in expressions {
-          value = Some(self.typecheck_expression(parent.clone(), expression)?);
+  // declares the structs and enums nested in a struct at `path`, so that they can be referred to
+  // before the struct is checked
+  fn declare_nested(&mut self, path: ast::util::Path, r#struct: &ast::r#struct::Struct<Type>) {
+    for item in &r#struct.module.items {
+      match &item.kind {
+        ast::module::ItemKind::Struct(s) => {
+          let path = [path.clone(), vec![s.header.name.clone()]].concat();
+          self.declare(path.clone(), ItemKind::Struct(s.clone()));
+          self.declare_nested(path, s);
         }
-
-        if has_value {
-          Ok(value.unwrap_or(Type::Tuple(vec![])))
-        } else {
-          Ok(Type::Tuple(vec![]))
+        ast::module::ItemKind::Enum(e) => {
+          let path = [path.clone(), vec![e.header.name.clone()]].concat();
+          self.declare(path, ItemKind::Enum(e.clone()));
         }
+        _ => (),
       }
-      ast::util::Expression::Call {
-        expression,
-        arguments,
-      } => {
-        let expression_type = self.typecheck_expression(parent.clone(), *expression)?;
+    }
+  }
 
-        match expression_type {
-          Type::Function(parameters, r#type) => {
-            if parameters.len() != arguments.len() {
-              Err(TypecheckerError::InvalidArguments {
-                expected: parameters.clone(),
-                found: arguments
-                  .iter()
-                  .map(|a| self.typecheck_expression(parent.clone(), a.clone()))
-                  .collect::<Result<_, _>>()?,
-              })?
-            }
+  // the type of an item in `types`: a struct or enum as a type over its own type parameters, or
+  // a function's type
+  pub fn type_of_item(&self, id: Uuid) -> Option<Type> {
+    let path = || self.declared_path(id);
 
-            for (parameter, argument) in parameters.iter().zip(arguments.clone()) {
-              let argument = self.typecheck_expression(parent.clone(), argument)?;
+    match &self.types.get(&id)?.1 {
+      ItemKind::Function(f) => Some(function_type(f)),
+      ItemKind::Struct(s) => Some(Type::Named(
+        path()?,
+        s.header
+          .type_parameters
+          .iter()
+          .map(|parameter| Type::Named(vec![parameter.name.clone()], vec![]))
+          .collect(),
+      )),
+      ItemKind::Enum(_) => Some(Type::Named(path()?, vec![])),
+      _ => None,
+    }
+  }
 
-              if !argument.satisfies(parameter) {
-                Err(TypecheckerError::InvalidArguments {
-                  expected: parameters.clone(),
-                  found: arguments
-                    .iter()
-                    .map(|a| self.typecheck_expression(parent.clone(), a.clone()))
-                    .collect::<Result<_, _>>()?,
-                })?
-              }
-            }
+  // the path an item in `declarations` is declared at
+  fn declared_path(&self, id: Uuid) -> Option<ast::util::Path> {
+    self
+      .declarations
+      .iter()
+      .find(|(_, declared)| **declared == id)
+      .map(|(path, _)| path.clone())
+  }
 
-            Ok(*r#type)
-          }
-          _ => todo!(),
-        }
+  // creates a variable item and records it so that it can be reported if it's never read
+  pub fn variable(&mut self, name: &str, ty: Type, mutable: bool) -> Item {
+    let item = Item::new(ItemKind::Variable(ty, mutable));
+    self.variables.push((item.0, name.to_string()));
+    item
+  }
+
+  // a variable declared before a closure started is captured by it
+  fn capture(&mut self, id: Uuid) {
+    let Some(index) = self.variables.iter().position(|(v, _)| *v == id) else {
+      return;
+    };
+
+    for &(closure, start) in &self.closures {
+      let captures = &mut self.captures[closure];
+      if index < start && !captures.iter().any(|(v, _)| *v == id) {
+        captures.push(self.variables[index].clone());
       }
-      // TODO: functions, etc.
-      ast::util::Expression::Identifier(name) => match parent.borrow().get(&name) {
-        Some(Item(_, ItemKind::Variable(ty))) => Ok(ty.clone()),
-        Some(Item(_, ItemKind::Function(f))) => Ok(Type::Function(
-          f.header.parameters.iter().map(|p| p.ty.clone()).collect(),
-          Box::new(f.header.ty.clone().unwrap_or(Type::Tuple(vec![]))),
-        )),
-        _ => Err(TypecheckerError::UnresolvedIdentifier(name))?,
-      },
-      ast::util::Expression::If {
-        condition,
-        consequence,
-        alternative,
-      } => {
-        let condition = self.typecheck_expression(parent.clone(), *condition)?;
+    }
+  }
+
+  // checks a pattern against the type of the value it matches, binding its names in `scope`
+  pub fn bind(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    pattern: ast::util::Pattern<Type>,
+    ty: Type,
+    mutable: bool,
+  ) -> Result<(), TypecheckerError<Type>> {
+    match self.resolve_pattern(scope.clone(), pattern) {
+      ast::util::Pattern::Wildcard => (),
+      ast::util::Pattern::Identifier(name) => {
+        let variable = self.variable(&name, ty, mutable);
+        scope.borrow_mut().insert(name, variable);
+      }
+      ast::util::Pattern::Literal(literal) => {
+        let found =
+          self.typecheck_expression(scope.clone(), ast::util::Expression::Literal(literal))?;
 
-        if !condition.satisfies(&Type::Bool) {
+        if !self.satisfies(&found, &ty) {
           Err(TypecheckerError::InvalidType {
-            expected: Type::Bool,
-            found: condition,
+            expected: ty,
+            found,
+          })?
+        }
+      }
+      // `(a)` is just `a`, the same way `(T)` is just `T`
+      ast::util::Pattern::Tuple(mut patterns) if patterns.len() == 1 => {
+        self.bind(scope, patterns.remove(0), ty, mutable)?
+      }
+      ast::util::Pattern::Tuple(patterns) => match ty.reduce() {
+        Type::Tuple(types) if types.len() == patterns.len() => {
+          for (pattern, ty) in patterns.into_iter().zip(types) {
+            self.bind(scope.clone(), pattern, ty, mutable)?;
+          }
+        }
+        found => Err(TypecheckerError::InvalidTuplePattern {
+          elements: patterns.len(),
+          found,
+        })?,
+      },
+      // like construction, a variant with named fields is only matched by its names
+      ast::util::Pattern::Variant { path, fields } => {
+        let (_, variant) = self.lookup_variant(scope.clone(), &path)?;
+        if !variant.names.is_empty() {
+          Err(TypecheckerError::NamedVariantFields {
+            variant: variant.name,
           })?
         }
 
-        let consequence: Type = self.typecheck_expression(parent.clone(), *consequence)?;
+        self.bind_variant(scope, path, fields, ty, mutable)?
+      }
+      ast::util::Pattern::Struct { path, fields } => {
+        let fields = self.positional_fields(scope.clone(), &path, fields)?;
+        self.bind_variant(scope, path, fields, ty, mutable)?
+      }
+    }
 
-        if let Some(alternative) = alternative {
-          Ok(union!(
-            consequence,
-            self.typecheck_expression(parent, *alternative)?
-          ))
-        } else {
-          Ok(consequence)
-        }
+    Ok(())
+  }
+
+  // binds the patterns for the fields of a variant, in the order the variant declares them
+  fn bind_variant(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    path: ast::util::Path,
+    fields: Vec<ast::util::Pattern<Type>>,
+    ty: Type,
+    mutable: bool,
+  ) -> Result<(), TypecheckerError<Type>> {
+    let (found, variant) = self.lookup_variant(scope.clone(), &path)?;
+
+    if !self.satisfies(&found, &ty) {
+      Err(TypecheckerError::InvalidType {
+        expected: ty,
+        found,
+      })?
+    }
+
+    if fields.len() != variant.fields.len() {
+      Err(TypecheckerError::InvalidVariantPattern {
+        variant: path.join("::"),
+        expected: variant.fields.len(),
+        found: fields.len(),
+      })?
+    }
+
+    for (pattern, ty) in fields.into_iter().zip(variant.fields) {
+      self.bind(scope.clone(), pattern, ty, mutable)?;
+    }
+
+    Ok(())
+  }
+
+  // the patterns for the named fields of a variant, in the order the variant declares them
+  fn positional_fields(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    path: &ast::util::Path,
+    fields: Vec<(String, ast::util::Pattern<Type>)>,
+  ) -> Result<Vec<ast::util::Pattern<Type>>, TypecheckerError<Type>> {
+    let (_, variant) = self.lookup_variant(scope, path)?;
+    let mut given = HashMap::new();
+
+    for (field, pattern) in fields {
+      if !variant.names.contains(&field) {
+        Err(TypecheckerError::UnknownField {
+          struct_name: path.join("::"),
+          field: field.clone(),
+        })?
       }
-      ast::util::Expression::Literal(literal) => match literal {
-        ast::util::Literal::Char(_) => Ok(Type::Char),
-        ast::util::Literal::String(_) => todo!(),
-        ast::util::Literal::Tuple(vec) => Ok(Type::Tuple(
-          vec
-            .into_iter()
-            .map(|e| self.typecheck_expression(parent.clone(), e))
-            .collect::<Result<_, _>>()?,
-        )),
-        ast::util::Literal::Number(n) => Ok(n.into()),
-        ast::util::Literal::Array(vec) => {
-          // TODO: inference
-          if vec.is_empty() {
-            todo!()
-          }
 
-          todo!()
-        }
-        ast::util::Literal::Bool(_) => Ok(Type::Bool),
-        ast::util::Literal::Closure {
-          parameters,
-          ty,
-          body,
-        } => {
-          let mut scope = Scope::new(Some(parent));
-          for parameter in &parameters {
-            scope.insert(
-              parameter.name.clone(),
-              Item::new(ItemKind::Variable(parameter.ty.clone())),
-            );
-          }
+      if given.insert(field.clone(), pattern).is_some() {
+        Err(TypecheckerError::DuplicateField {
+          struct_name: path.join("::"),
+          field,
+        })?
+      }
+    }
 
-          let body = self.typecheck_expression(Rc::new(RefCell::new(scope)), *body)?;
+    Ok(
+      variant
+        .names
+        .iter()
+        .map(|name| given.remove(name).unwrap_or(ast::util::Pattern::Wildcard))
+        .collect(),
+    )
+  }
 
-          if let Some(ty) = ty {
-            if !body.satisfies(&ty) {
-              Err(TypecheckerError::InvalidType {
-                expected: ty.clone(),
-                found: body.clone(),
-              })?
-            }
+  // a bare name in a pattern matches the variant without fields it refers to, if there is one, and
+  // binds a new variable otherwise
+  fn resolve_pattern(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    pattern: ast::util::Pattern<Type>,
+  ) -> ast::util::Pattern<Type> {
+    match pattern {
+      ast::util::Pattern::Identifier(name) => match scope.borrow().get(&name) {
+        Some(Item(_, ItemKind::Variant(_, variant))) if variant.fields.is_empty() => {
+          ast::util::Pattern::Variant {
+            path: vec![name],
+            fields: Vec::new(),
           }
-
-          Ok(Type::Function(
-            parameters.iter().map(|p| p.ty.clone()).collect(),
-            Box::new(body),
-          ))
         }
+        _ => ast::util::Pattern::Identifier(name),
       },
-      // TODO: make sure the types of return statements match with the type of blocks
-      ast::util::Expression::Return(expression) => {
-        self.typecheck_expression(parent.clone(), *expression)
-      }
-      ast::util::Expression::While { condition, body } => {
-        let condition = self.typecheck_expression(parent.clone(), *condition)?;
+      ast::util::Pattern::Tuple(patterns) => ast::util::Pattern::Tuple(
+        patterns
+          .into_iter()
+          .map(|pattern| self.resolve_pattern(scope.clone(), pattern))
+          .collect(),
+      ),
+      ast::util::Pattern::Variant { path, fields } => ast::util::Pattern::Variant {
+        path,
+        fields: fields
+          .into_iter()
+          .map(|pattern| self.resolve_pattern(scope.clone(), pattern))
+          .collect(),
+      },
+      ast::util::Pattern::Struct { path, fields } => ast::util::Pattern::Struct {
+        path,
+        fields: fields
+          .into_iter()
+          .map(|(name, pattern)| (name, self.resolve_pattern(scope.clone(), pattern)))
+          .collect(),
+      },
+      pattern => pattern,
+    }
+  }
 
-        if !condition.satisfies(&Type::Bool) {
-          Err(TypecheckerError::InvalidType {
-            expected: Type::Bool,
-            found: condition,
-          })?
-        }
+  // finds the variant a pattern names, along with the type of its enum
+  fn lookup_variant(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    path: &ast::util::Path,
+  ) -> Result<(Type, ast::r#enum::Variant<Type>), TypecheckerError<Type>> {
+    match path.split_last().unwrap() {
+      (name, []) => match scope.borrow().get(name) {
+        Some(Item(_, ItemKind::Variant(id, variant))) => match self.types.get(&id) {
+          Some(Item(_, ItemKind::Enum(e))) => {
+            Ok((Type::Named(vec![e.header.name.clone()], vec![]), variant))
+          }
+          _ => unreachable!(),
+        },
+        _ => Err(TypecheckerError::UnresolvedIdentifier(name.clone())),
+      },
+      (name, parents) => {
+        let unresolved = || TypecheckerError::UnresolvedPath {
+          path: path.join("::"),
+          segment: name.clone(),
+        };
 
-        Ok(Type::Array(Box::new(
-          self.typecheck_expression(parent.clone(), *body)?,
-        )))
+        match self.lookup_type(scope, &parents.to_vec())? {
+          ItemKind::Enum(e) => e
+            .variants
+            .into_iter()
+            .find(|variant| &variant.name == name)
+            .map(|variant| (Type::Named(parents.to_vec(), vec![]), variant))
+            .ok_or_else(unresolved),
+          _ => Err(unresolved()),
+        }
       }
-      _ => todo!(),
     }
   }
+
+  pub fn typecheck_const(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    ty: &Type,
+    value: ast::util::Expression<Type>,
+  ) -> Result<(), TypecheckerError<Type>> {
+    self.resolve_type(parent.clone(), ty)?;
+    let span = value.span();
+    let found = self.typecheck_argument(parent, value, ty)?;
+    self.expect_type(span, found, ty)
+  }
+
+  // a value that doesn't satisfy the type it's expected to have is reported at the value, rather
+  // than at what it's in
+  fn expect_type(
+    &mut self,
+    span: Option<Range<usize>>,
+    found: Type,
+    expected: &Type,
+  ) -> Result<(), TypecheckerError<Type>> {
+    if !self.satisfies(&found, expected) {
+      self.span = span;
+      Err(TypecheckerError::InvalidType {
+        expected: expected.clone(),
+        found,
+      })?
+    }
+
+    Ok(())
+  }
+
+  // looks for an operator declared in the struct of the first operand that accepts the operands,
+  // substituting the struct's type arguments. like trait methods, operators are always visible
+  pub fn overload(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    operator: &str,
+    operands: &[Type],
+  ) -> Result<Option<Type>, TypecheckerError<Type>> {
+    let (r#struct, arguments) = match &operands[0] {
+      Type::Named(path, arguments) => match self.lookup_type(scope, path)? {
+        ItemKind::Struct(s) => (s, arguments),
+        _ => return Ok(None),
+      },
+      _ => return Ok(None),
+    };
+
+    let arguments = r#struct
+      .header
+      .type_parameters
+      .iter()
+      .map(|parameter| vec![parameter.name.clone()])
+      .zip(arguments.iter().cloned())
+      .collect();
+
+    for item in &r#struct.module.items {
+      if let ast::module::ItemKind::Operator(o) = &item.kind {
+        let (name, parameters, result) = operator_signature(o);
+
+        if name == operator
+          && parameters.len() == operands.len()
+          && parameters
+            .iter()
+            .zip(operands)
+            .all(|(p, ty)| self.satisfies(ty, &p.ty.substitute(&arguments)))
+        {
+          return Ok(Some(result.substitute(&arguments)));
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  // an operator is checked as a function of its operands
+  pub fn typecheck_operator(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    operator: ast::operator::Operator<Type>,
+  ) -> Result<(), TypecheckerError<Type>> {
+    let (name, parameters, result) = operator_signature(&operator);
+    let header = ast::function::Header {
+      name: name.clone(),
+      parameters: parameters.into_iter().cloned().collect(),
+      ty: Some(result.clone()),
+    };
+
+    self.typecheck_function(
+      parent,
+      ast::function::Function {
+        header,
+        body: operator.body,
+      },
+    )
+  }
+
+  pub fn typecheck_struct(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    r#struct: ast::r#struct::Struct<Type>,
+    span: &Option<Range<usize>>,
+  ) -> Result<(), Vec<Error<TypecheckerError<Type>>>> {
+    let self_type = self_type(&r#struct.header);
+    let arguments = [(vec!["Self".to_string()], self_type.clone())]
+      .into_iter()
+      .collect();
+
+    for path in &r#struct.header.traits {
+      let r#trait = self
+        .resolve_trait(parent.clone(), path)
+        .map_err(|error| vec![self.error(span, error)])?;
+
+      for (modifiers, item) in &r#trait.items {
+        // TODO: the other kinds of trait items
+        if let ast::r#trait::Item::Operator(required) = item {
+          let (operator, operands, result) = match required {
+            ast::r#trait::Operator::Prefix {
+              operator,
+              operand,
+              result,
+            } => (operator, vec![operand], result),
+            ast::r#trait::Operator::Infix {
+              operator,
+              operands,
+              result,
+            } => (operator, vec![&operands.0, &operands.1], result),
+          };
+
+          let implemented = r#struct.module.items.iter().any(|item| {
+            let (name, parameters, ty) = match &item.kind {
+              ast::module::ItemKind::Operator(o) => operator_signature(o),
+              _ => return false,
+            };
+
+            name == operator
+              && parameters.len() == operands.len()
+              && parameters
+                .iter()
+                .zip(&operands)
+                .all(|(p, ty)| p.ty == ty.substitute(&arguments))
+              && *ty == result.substitute(&arguments)
+          });
+
+          if !implemented {
+            Err(vec![self.error(
+              span,
+              TypecheckerError::UnimplementedTraitMethod {
+                r#trait: path.join("::"),
+                method: operator.clone(),
+              },
+            )])?
+          }
+        }
+
+        if let ast::r#trait::Item::Function(required) = item {
+          let implemented = r#struct.module.items.iter().any(|item| match &item.kind {
+            ast::module::ItemKind::Function(f) => {
+              f.header.name == required.name
+                && item.modifiers.static_ == modifiers.static_
+                && f.header.parameters.len() == required.parameters.len()
+                && f
+                  .header
+                  .parameters
+                  .iter()
+                  .zip(&required.parameters)
+                  .all(|(p, ty)| p.binding() == ty.substitute(&arguments))
+                && f.header.ty.clone().unwrap_or(Type::Tuple(vec![]))
+                  == required.ty.substitute(&arguments)
+            }
+            _ => false,
+          });
+
+          if !implemented {
+            Err(vec![self.error(
+              span,
+              TypecheckerError::UnimplementedTraitMethod {
+                r#trait: path.join("::"),
+                method: required.name.clone(),
+              },
+            )])?
+          }
+        }
+      }
+    }
+
+    let static_ = Rc::new(RefCell::new(Scope::new(Some(parent.clone()))));
+
+    // type parameters are visible to every item in the struct
+    for parameter in &r#struct.header.type_parameters {
+      for path in &parameter.traits {
+        self
+          .resolve_trait(parent.clone(), path)
+          .map_err(|error| vec![self.error(span, error)])?;
+      }
+
+      let item = self.declare(
+        vec![r#struct.header.name.clone(), parameter.name.clone()],
+        ItemKind::TypeParameter(parameter.clone()),
+      );
+      static_.borrow_mut().insert(parameter.name.clone(), item);
+    }
+
+    self.structs.push(r#struct.clone());
+    let mut errors = self
+      .typecheck_module(static_.clone(), r#struct.module.clone(), true)
+      .err()
+      .unwrap_or_default();
+
+    // instance functions are methods, with the instance bound to `self`
+    let mut instance = Scope::new(Some(static_));
+    instance.insert(
+      "self".to_string(),
+      Item::new(ItemKind::Variable(self_type, false)),
+    );
+
+    if let Err(mut instance_errors) =
+      self.typecheck_module(Rc::new(RefCell::new(instance)), r#struct.module, false)
+    {
+      errors.append(&mut instance_errors);
+    }
+    self.structs.pop();
+
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors)
+    }
+  }
+
+  pub fn typecheck_trait(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    r#trait: ast::r#trait::Trait<Type>,
+  ) -> Result<(), TypecheckerError<Type>> {
+    // `Self` is whichever struct implements the trait
+    let item = self.declare(
+      vec![r#trait.header.name.clone(), "Self".to_string()],
+      ItemKind::TypeParameter(ast::util::TypeParameter {
+        name: "Self".to_string(),
+        traits: vec![vec![r#trait.header.name.clone()]],
+      }),
+    );
+    let mut scope = Scope::new(Some(parent));
+    scope.insert("Self".to_string(), item);
+    let scope = Rc::new(RefCell::new(scope));
+
+    for (_, item) in &r#trait.items {
+      if let ast::r#trait::Item::Function(f) = item {
+        for parameter in &f.parameters {
+          self.resolve_type(scope.clone(), parameter)?;
+        }
+
+        self.resolve_type(scope.clone(), &f.ty)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // looks up a trait by path, descending into the traits nested in it
+  pub fn resolve_trait(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    path: &ast::util::Path,
+  ) -> Result<ast::r#trait::Trait<Type>, TypecheckerError<Type>> {
+    let unresolved = || TypecheckerError::UnresolvedType {
+      name: path.join("::"),
+    };
+
+    let mut r#trait = match scope.borrow().get(&path[0]) {
+      Some(Item(_, ItemKind::Trait(t))) => t,
+      _ => Err(unresolved())?,
+    };
+
+    for name in &path[1..] {
+      r#trait = r#trait
+        .items
+        .iter()
+        .find_map(|(_, item)| match item {
+          ast::r#trait::Item::Child(t) if &t.header.name == name => Some(t.clone()),
+          _ => None,
+        })
+        .ok_or_else(unresolved)?;
+    }
+
+    Ok(r#trait)
+  }
+
+  // a struct implements the traits it declares, and a type parameter the traits it's bounded by
+  pub fn implements(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    ty: &Type,
+    r#trait: &ast::util::Path,
+  ) -> Result<bool, TypecheckerError<Type>> {
+    let required = self.resolve_trait(scope.clone(), r#trait)?;
+
+    let traits = match ty.reduce() {
+      Type::Named(path, _) => match self.lookup_type(scope.clone(), &path)? {
+        ItemKind::Struct(s) => s.header.traits,
+        ItemKind::TypeParameter(parameter) => parameter.traits,
+        _ => vec![],
+      },
+      _ => vec![],
+    };
+
+    Ok(traits.iter().any(|path| {
+      self
+        .resolve_trait(scope.clone(), path)
+        .is_ok_and(|t| t == required)
+    }))
+  }
+
+  pub fn typecheck_enum(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    r#enum: ast::r#enum::Enum<Type>,
+  ) -> Result<(), TypecheckerError<Type>> {
+    let mut names = HashSet::new();
+
+    for variant in &r#enum.variants {
+      if !names.insert(variant.name.clone()) {
+        Err(TypecheckerError::DuplicateVariant(variant.name.clone()))?
+      }
+
+      for field in &variant.fields {
+        self.resolve_type(parent.clone(), field)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // finds the struct, enum or type parameter that a path refers to
+  pub fn lookup_type(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    path: &ast::util::Path,
+  ) -> Result<ItemKind, TypecheckerError<Type>> {
+    let unresolved = || TypecheckerError::UnresolvedType {
+      name: path.join("::"),
+    };
+
+    let mut item = match scope.borrow().get(&path[0]) {
+      Some(Item(
+        _,
+        kind @ (ItemKind::Struct(_) | ItemKind::Enum(_) | ItemKind::TypeParameter(_)),
+      )) => kind,
+      _ => Err(unresolved())?,
+    };
+
+    for (i, name) in path.iter().enumerate().skip(1) {
+      item = match item {
+        ItemKind::Struct(s) => self
+          .nested_item(&s, name, &path[..=i])?
+          .ok_or_else(unresolved)?,
+        _ => Err(unresolved())?,
+      };
+    }
+
+    Ok(item)
+  }
+
+  // the id of the item that a path found by `lookup_type` refers to. the rest of the path is
+  // followed in `declarations`, where nested types are declared along with their struct
+  pub fn lookup_type_id(&self, scope: Rc<RefCell<Scope>>, path: &ast::util::Path) -> Option<Uuid> {
+    let Item(id, _) = scope.borrow().get(&path[0])?;
+
+    if path.len() == 1 {
+      return Some(id);
+    }
+
+    let declared = self.declared_path(id)?;
+
+    self
+      .declarations
+      .get(&[&declared, &path[1..]].concat())
+      .copied()
+  }
+
+  // a struct or enum declared in `r#struct`, which is only visible outside of it if it's `pub`
+  fn nested_item(
+    &self,
+    r#struct: &ast::r#struct::Struct<Type>,
+    name: &str,
+    path: &[String],
+  ) -> Result<Option<ItemKind>, TypecheckerError<Type>> {
+    let nested = r#struct
+      .module
+      .items
+      .iter()
+      .find_map(|item| match &item.kind {
+        ast::module::ItemKind::Struct(s) if s.header.name == name => {
+          Some((&item.modifiers, ItemKind::Struct(s.clone())))
+        }
+        ast::module::ItemKind::Enum(e) if e.header.name == name => {
+          Some((&item.modifiers, ItemKind::Enum(e.clone())))
+        }
+        _ => None,
+      });
+
+    match nested {
+      Some((modifiers, _)) if !modifiers.public && !self.structs.contains(r#struct) => {
+        Err(TypecheckerError::PrivateItem {
+          name: path.join("::"),
+        })
+      }
+      nested => Ok(nested.map(|(_, item)| item)),
+    }
+  }
+
+  // resolves `Foo::bar` to the static function or variant `bar` of the struct or enum `Foo`,
+  // where `Foo` may itself be a path through nested structs
+  pub fn resolve_path(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    path: &ast::util::Path,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    let unresolved = |segment: &String| TypecheckerError::UnresolvedPath {
+      path: path.join("::"),
+      segment: segment.clone(),
+    };
+
+    let (name, parents) = path.split_last().unwrap();
+
+    let mut item = match scope.borrow().get(&parents[0]) {
+      Some(Item(_, kind @ (ItemKind::Struct(_) | ItemKind::Enum(_)))) => kind,
+      _ => Err(unresolved(&parents[0]))?,
+    };
+
+    for (i, parent) in parents.iter().enumerate().skip(1) {
+      item = match item {
+        ItemKind::Struct(s) => self
+          .nested_item(&s, parent, &parents[..=i])?
+          .ok_or_else(|| unresolved(parent))?,
+        _ => Err(unresolved(parent))?,
+      };
+    }
+
+    match item {
+      ItemKind::Struct(s) => {
+        let (modifiers, function) = s
+          .module
+          .items
+          .iter()
+          .find_map(|item| match &item.kind {
+            ast::module::ItemKind::Function(f)
+              if &f.header.name == name && item.modifiers.static_ =>
+            {
+              Some((&item.modifiers, f))
+            }
+            _ => None,
+          })
+          .ok_or_else(|| unresolved(name))?;
+
+        if !modifiers.public && !self.structs.contains(&s) {
+          Err(TypecheckerError::PrivateItem {
+            name: path.join("::"),
+          })?
+        }
+
+        Ok(function_type(function))
+      }
+      ItemKind::Enum(e) => {
+        let variant = e
+          .variants
+          .iter()
+          .find(|variant| &variant.name == name)
+          .ok_or_else(|| unresolved(name))?;
+
+        constructor_type(Type::Named(parents.to_vec(), vec![]), variant.clone())
+      }
+      _ => unreachable!(),
+    }
+  }
+
+  // checks that every named type refers to a struct or enum in scope
+  // only the written type is walked, never the definitions it names, so recursive types can't loop
+  pub fn resolve_type(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    ty: &Type,
+  ) -> Result<(), TypecheckerError<Type>> {
+    match ty {
+      Type::Named(path, parameters) => {
+        let item = self.lookup_type(scope.clone(), path)?;
+
+        if let Some(id) = self.lookup_type_id(scope.clone(), path) {
+          self.type_references.push((path.clone(), id));
+        }
+
+        let expected = match &item {
+          ItemKind::Struct(s) => s.header.type_parameters.len(),
+          _ => 0,
+        };
+
+        if parameters.len() != expected {
+          Err(TypecheckerError::InvalidTypeArguments {
+            name: path.join("::"),
+            expected,
+            found: parameters.len(),
+          })?
+        }
+
+        for parameter in parameters {
+          self.resolve_type(scope.clone(), parameter)?;
+        }
+
+        if let ItemKind::Struct(s) = item {
+          for (parameter, argument) in s.header.type_parameters.iter().zip(parameters) {
+            for bound in &parameter.traits {
+              if !self.implements(scope.clone(), argument, bound)? {
+                Err(TypecheckerError::UnsatisfiedBound {
+                  type_arg: argument.clone(),
+                  trait_name: bound.join("::"),
+                })?
+              }
+            }
+          }
+        }
+
+        Ok(())
+      }
+      Type::Function(parameters, ty) => {
+        for parameter in parameters {
+          self.resolve_type(scope.clone(), parameter)?;
+        }
+
+        self.resolve_type(scope, ty)
+      }
+      Type::Tuple(types) => types
+        .iter()
+        .try_for_each(|ty| self.resolve_type(scope.clone(), ty)),
+      // a field can only be given once, whether the record is written as a type or a literal
+      Type::Record(fields) => {
+        for (i, (name, field)) in fields.iter().enumerate() {
+          if fields[..i].iter().any(|(other, _)| other == name) {
+            Err(TypecheckerError::DuplicateField {
+              struct_name: ty.to_string(),
+              field: name.clone(),
+            })?
+          }
+
+          self.resolve_type(scope.clone(), field)?;
+        }
+
+        Ok(())
+      }
+      Type::Array(ty) | Type::FixedArray(ty, _) | Type::Reference(ty, _) => {
+        self.resolve_type(scope, ty)
+      }
+      Type::Union(types) => types
+        .iter()
+        .try_for_each(|ty| self.resolve_type(scope.clone(), ty)),
+      _ => Ok(()),
+    }
+  }
+
+  // the function a call refers to, which is needed for its parameters' names and defaults since
+  // function types don't carry them. it's only known when the callee names a function directly
+  fn callee(
+    &self,
+    scope: Rc<RefCell<Scope>>,
+    callee: &ast::util::Expression<Type>,
+  ) -> Option<ast::function::Function<Type>> {
+    match callee.unspanned() {
+      ast::util::Expression::Identifier(name) => match scope.borrow().get(name) {
+        Some(Item(_, ItemKind::Function(f))) => Some(f),
+        _ => None,
+      },
+      ast::util::Expression::Path(path) => {
+        let (name, parents) = path.split_last().unwrap();
+
+        match self.lookup_type(scope, &parents.to_vec()) {
+          Ok(ItemKind::Struct(s)) => s.module.items.into_iter().find_map(|item| match item.kind {
+            ast::module::ItemKind::Function(f)
+              if &f.header.name == name && item.modifiers.static_ =>
+            {
+              Some(f)
+            }
+            _ => None,
+          }),
+          _ => None,
+        }
+      }
+      _ => None,
+    }
+  }
+
+  // a place is a variable, an element or field of one, or what a reference points to. it can only
+  // be changed if the variable is declared `mut`, or the reference is mutable. anything else isn't
+  // a place, which is `Ok(false)`
+  fn mutable_place(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    place: &ast::util::Expression<Type>,
+    immutable: impl Fn(String) -> TypecheckerError<Type>,
+  ) -> Result<bool, TypecheckerError<Type>> {
+    let mut root = place.unspanned();
+    while let ast::util::Expression::Index { expression, .. }
+    | ast::util::Expression::TupleIndex { expression, .. }
+    | ast::util::Expression::Field { expression, .. } = root
+    {
+      root = expression.unspanned();
+    }
+
+    match root {
+      // an unresolved name is reported when the place is checked
+      ast::util::Expression::Identifier(name) => match scope.borrow().get(name) {
+        Some(Item(_, ItemKind::Variable(_, true))) | None => Ok(true),
+        Some(_) => Err(immutable(name.clone())),
+      },
+      ast::util::Expression::Prefix { operator, operand } if operator == "*" => {
+        match self.typecheck_expression(scope, *operand.clone())?.reduce() {
+          Type::Reference(_, true) => Ok(true),
+          Type::Reference(_, false) => Err(TypecheckerError::ImmutableReference),
+          found => Err(TypecheckerError::NotDereferenceable { found }),
+        }
+      }
+      _ => Ok(false),
+    }
+  }
+
+  // the type of a built-in or overloaded binary operator applied to values of these types
+  fn infix(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    operator: &str,
+    mut left: Type,
+    mut right: Type,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    // an operand that's still being inferred takes the type of the other one
+    if matches!(left, Type::Infer(_)) != matches!(right, Type::Infer(_)) {
+      self.unify(&left, &right);
+      left = self.resolve(&left);
+      right = self.resolve(&right);
+    }
+
+    if let Some(result) = self.overload(parent.clone(), operator, &[left.clone(), right.clone()])? {
+      return Ok(result);
+    }
+
+    let invalid = || TypecheckerError::InvalidOperands {
+      operator: operator.to_string(),
+      left: left.clone(),
+      right: right.clone(),
+    };
+
+    match operator {
+      // both sides of a comparison have the same type, and only numbers are ordered
+      "<" | "<=" | ">" | ">=" if left == right && left.is_numeric() => Ok(Type::Bool),
+      "==" | "!="
+        if left == right && (left.is_numeric() || matches!(left, Type::Bool | Type::Char)) =>
+      {
+        Ok(Type::Bool)
+      }
+      "<" | "<=" | ">" | ">=" | "==" | "!=" => Err(invalid())?,
+      // strings are arrays of chars, so `+` doesn't concatenate them
+      "+" | "-" | "*" | "/" | "%" if left == right && left.is_numeric() => Ok(left),
+      "+" | "-" | "*" | "/" | "%" => Err(invalid())?,
+      "&&" | "||" if left == Type::Bool && right == Type::Bool => Ok(Type::Bool),
+      "&&" | "||" => Err(invalid())?,
+      // bitwise operators only work on integers, and a shift can be by any integer type
+      "&" | "^" | "|" if left == right && left.is_integer() => Ok(left),
+      "<<" | ">>" | ">>>" if left.is_integer() && right.is_integer() => Ok(left),
+      "&" | "^" | "|" | "<<" | ">>" | ">>>" => Err(invalid())?,
+      // `a ?? b` is `b` where `a` is unit, so unit is taken out of `a`'s type and `b` fills in
+      "??" => {
+        let unit = Type::Tuple(vec![]);
+        let value = match &left {
+          Type::Union(types) if types.contains(&unit) => {
+            Type::union(types.iter().filter(|ty| **ty != unit).cloned())
+          }
+          _ => Err(TypecheckerError::NotNullable(left.clone()))?,
+        };
+
+        if !self.satisfies(&right, &value) {
+          Err(TypecheckerError::InvalidType {
+            expected: value.clone(),
+            found: right,
+          })?
+        }
+
+        Ok(value)
+      }
+      _ => Err(invalid())?,
+    }
+  }
+
+  fn warn_division(
+    &mut self,
+    parent: &Rc<RefCell<Scope>>,
+    operator: &str,
+    divisor: &ast::util::Expression<Type>,
+  ) {
+    if matches!(operator, "/" | "%")
+      && (matches!(divisor.unspanned(), ast::util::Expression::Literal(ast::util::Literal::Number(n)) if n.is_zero())
+        || divisor.const_eval_with(&|name| constant(parent, name)) == Some(0))
+    {
+      self.warnings.push(TypecheckerWarning::DivisionByZero);
+    }
+  }
+
+  // `a = b`, or `a += b` and the like when there's an operator, which has to give back the type of
+  // `a`
+  fn assign(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    operator: Option<&str>,
+    target: ast::util::Expression<Type>,
+    value: ast::util::Expression<Type>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    if !self.mutable_place(scope.clone(), &target, |name| {
+      TypecheckerError::AssignToImmutable { name }
+    })? {
+      Err(TypecheckerError::InvalidAssignmentTarget)?
+    }
+
+    let expected = self.typecheck_expression(scope.clone(), target)?;
+    let mut found = self.typecheck_argument(scope.clone(), value, &expected)?;
+
+    if let Some(operator) = operator {
+      found = self.infix(scope, operator, expected.reduce(), found.reduce())?;
+    }
+
+    if self.satisfies(&found, &expected) {
+      Ok(Type::Tuple(vec![]))
+    } else {
+      Err(TypecheckerError::InvalidType { expected, found })
+    }
+  }
+
+  // every field without a default has to be given exactly once. the struct's type arguments are
+  // inferred from the types of the values
+  fn struct_literal(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    path: ast::util::Path,
+    fields: Vec<(String, ast::util::Expression<Type>)>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    let name = path.join("::");
+
+    if let Ok((ty, variant)) = self.lookup_variant(scope.clone(), &path) {
+      if !variant.names.is_empty() {
+        return self.variant_literal(scope, &name, ty, variant, fields);
+      }
+    }
+
+    let r#struct = match self.lookup_type(scope.clone(), &path)? {
+      ItemKind::Struct(s) => s,
+      _ => Err(TypecheckerError::UnresolvedType { name: name.clone() })?,
+    };
+
+    let declared = r#struct
+      .module
+      .items
+      .iter()
+      .filter_map(|item| match &item.kind {
+        ast::module::ItemKind::Field { name, ty, default } => {
+          Some((name, (ty, default.is_some(), item.modifiers.public)))
+        }
+        _ => None,
+      })
+      .collect::<BTreeMap<_, _>>();
+
+    let parameters = r#struct
+      .header
+      .type_parameters
+      .iter()
+      .map(|parameter| vec![parameter.name.clone()])
+      .collect::<Vec<_>>();
+    let mut arguments = BTreeMap::new();
+    let mut given = HashSet::new();
+    let mut values = Vec::new();
+
+    for (field, value) in fields {
+      let (ty, _, public) = declared
+        .get(&field)
+        .ok_or_else(|| TypecheckerError::UnknownField {
+          struct_name: name.clone(),
+          field: field.clone(),
+        })?;
+
+      if !public && !self.structs.contains(&r#struct) {
+        Err(TypecheckerError::PrivateField {
+          struct_name: name.clone(),
+          field: field.clone(),
+        })?
+      }
+
+      if !given.insert(field.clone()) {
+        Err(TypecheckerError::DuplicateField {
+          struct_name: name.clone(),
+          field,
+        })?
+      }
+
+      let found = self.typecheck_argument(scope.clone(), value, ty)?;
+      infer(&parameters, ty, &found, &mut arguments);
+      values.push(((*ty).clone(), found));
+    }
+
+    let missing = declared
+      .iter()
+      .filter(|(field, (_, default, _))| !default && !given.contains(**field))
+      .map(|(field, _)| field.to_string())
+      .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+      Err(TypecheckerError::MissingFields {
+        struct_name: name.clone(),
+        fields: missing,
+      })?
+    }
+
+    if let Some(parameter) = parameters.iter().find(|p| !arguments.contains_key(*p)) {
+      Err(TypecheckerError::CannotInferTypeArgument {
+        name: parameter.join("::"),
+      })?
+    }
+
+    for (ty, found) in values {
+      let expected = ty.substitute(&arguments);
+
+      if !self.satisfies(&found, &expected) {
+        Err(TypecheckerError::InvalidType { expected, found })?
+      }
+    }
+
+    let ty = Type::Named(
+      path,
+      parameters.iter().map(|p| arguments[p].clone()).collect(),
+    );
+    self.resolve_type(scope, &ty)?;
+    Ok(ty)
+  }
+
+  // `V { x: 1 }` for an enum variant with named fields, which have to be given exactly once each
+  fn variant_literal(
+    &mut self,
+    scope: Rc<RefCell<Scope>>,
+    name: &str,
+    ty: Type,
+    variant: ast::r#enum::Variant<Type>,
+    fields: Vec<(String, ast::util::Expression<Type>)>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    let declared = variant
+      .names
+      .iter()
+      .zip(&variant.fields)
+      .collect::<BTreeMap<_, _>>();
+    let mut given = HashSet::new();
+
+    for (field, value) in fields {
+      let expected = declared
+        .get(&field)
+        .ok_or_else(|| TypecheckerError::UnknownField {
+          struct_name: name.to_string(),
+          field: field.clone(),
+        })?;
+
+      if !given.insert(field.clone()) {
+        Err(TypecheckerError::DuplicateField {
+          struct_name: name.to_string(),
+          field,
+        })?
+      }
+
+      let span = value.span();
+      let found = self.typecheck_argument(scope.clone(), value, expected)?;
+      self.expect_type(span, found, expected)?;
+    }
+
+    let missing = variant
+      .names
+      .iter()
+      .filter(|field| !given.contains(*field))
+      .cloned()
+      .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+      Err(TypecheckerError::MissingFields {
+        struct_name: name.to_string(),
+        fields: missing,
+      })?
+    }
+
+    Ok(ty)
+  }
+
+  // the types in the `Ok` and `Err` variants of a result. any enum with exactly those two variants
+  // and one field in each is one
+  fn result_types(&self, scope: Rc<RefCell<Scope>>, ty: &Type) -> Option<(Type, Type)> {
+    let Type::Named(path, _) = ty.reduce() else {
+      return None;
+    };
+    let Ok(ItemKind::Enum(e)) = self.lookup_type(scope, &path) else {
+      return None;
+    };
+
+    let field = |name: &str| match &e.variants[..] {
+      [a, b] => [a, b]
+        .into_iter()
+        .find(|variant| {
+          variant.name == name && variant.fields.len() == 1 && variant.names.is_empty()
+        })
+        .map(|variant| variant.fields[0].clone()),
+      _ => None,
+    };
+
+    Some((field("Ok")?, field("Err")?))
+  }
+
+  // the index in `loops` of the loop that a `break` or `continue` leaves, which is the innermost one
+  // unless it has a label
+  fn enclosing_loop(&self, label: Option<String>) -> Result<usize, TypecheckerError<Type>> {
+    let mut loops = self
+      .loops
+      .iter()
+      .enumerate()
+      .rev()
+      .map_while(|(index, l)| Some((index, l.as_ref()?)));
+
+    match label {
+      Some(label) => loops
+        .find(|(_, (_, l))| l.as_ref() == Some(&label))
+        .map(|(index, _)| index)
+        .ok_or(TypecheckerError::UndefinedLabel { label }),
+      None => loops
+        .next()
+        .map(|(index, _)| index)
+        .ok_or(TypecheckerError::BreakOutsideLoop),
+    }
+  }
+
+  // the value of a `let`, which must satisfy its annotation if it has one
+  fn let_value(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    ty: Option<Type>,
+    value: ast::util::Expression<Type>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    match ty {
+      Some(ty) => {
+        self.resolve_type(parent.clone(), &ty)?;
+        let span = value.span();
+        let value = self.typecheck_argument(parent, value, &ty)?;
+        self.expect_type(span, value, &ty)?;
+
+        Ok(ty)
+      }
+      None => self.typecheck_expression(parent, value),
+    }
+  }
+
+  // checks the condition of an `if` or `while`, and returns the scope of the branch it guards. a
+  // condition is either a `bool`, or a `let` whose pattern may be refutable, and whose bindings are
+  // only in scope in the branch
+  fn typecheck_condition(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    mut condition: ast::util::Expression<Type>,
+  ) -> Result<Rc<RefCell<Scope>>, TypecheckerError<Type>> {
+    if !matches!(condition.unspanned(), ast::util::Expression::Let { .. }) {
+      let span = condition.span();
+      let condition = self.typecheck_expression(parent.clone(), condition)?;
+      self.expect_type(span, condition, &Type::Bool)?;
+
+      return Ok(parent);
+    }
+
+    while let ast::util::Expression::Spanned(_, expression) = condition {
+      condition = *expression;
+    }
+
+    let ast::util::Expression::Let {
+      mutable,
+      pattern,
+      ty,
+      value,
+    } = condition
+    else {
+      unreachable!()
+    };
+
+    let ty = self.let_value(parent.clone(), ty, *value)?;
+    let scope = Rc::new(RefCell::new(Scope::new(Some(parent))));
+    let pattern = self.resolve_pattern(scope.clone(), pattern);
+    self.bind(scope.clone(), pattern, ty, mutable)?;
+
+    Ok(scope)
+  }
+
+  // the item that the identifier at `offset` refers to, if the module was parsed with spans. offsets
+  // count bytes, like spans do. only identifiers are recorded, so whitespace and keywords have none
+  pub fn symbol_at(&self, offset: usize) -> Option<&Item> {
+    self
+      .references
+      .iter()
+      .find(|(span, _)| span.contains(&offset))
+      .map(|(_, item)| item)
+  }
+
+  // the type of the innermost expression around `offset`, if the module was parsed with spans.
+  // offsets count bytes
+  pub fn type_at(&self, offset: usize) -> Option<Type> {
+    self
+      .expression_types
+      .iter()
+      .filter(|(span, _)| span.contains(&offset))
+      .min_by_key(|(span, _)| span.len())
+      .map(|(_, ty)| ty.clone())
+  }
+
+  // a call with the wrong arguments is reported with all of them, not just the first one that's off
+  fn invalid_arguments(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    expected: Vec<Type>,
+    arguments: &[ast::util::Argument<Type>],
+  ) -> TypecheckerError<Type> {
+    let mut position = 0;
+    let found = arguments
+      .iter()
+      .map(|argument| match (&argument.name, expected.get(position)) {
+        (None, Some(ty)) => {
+          position += 1;
+          self.typecheck_argument(parent.clone(), argument.value.clone(), ty)
+        }
+        _ => self.typecheck_expression(parent.clone(), argument.value.clone()),
+      })
+      .collect();
+
+    match found {
+      Ok(found) => TypecheckerError::InvalidArguments { expected, found },
+      Err(error) => error,
+    }
+  }
+
+  // an expression that's expected to have a known type, which a closure's unannotated parameters
+  // are inferred from, and which an unsuffixed integer literal takes on if it fits in it. the
+  // expectation reaches the value of a block, the branches of an `if` or `match`, and the elements
+  // of an array
+  fn typecheck_argument(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    argument: ast::util::Expression<Type>,
+    expected: &Type,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    self.typecheck_value(parent, argument, Some(expected))
+  }
+
+  fn typecheck_closure(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    parameters: Vec<ast::util::ClosureParameter<Type>>,
+    ty: Option<Type>,
+    body: ast::util::Expression<Type>,
+    expected: Option<Vec<Type>>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    // an annotation has to accept whatever the parameter is expected to be given
+    let mut types = Vec::new();
+    for (index, parameter) in parameters.iter().enumerate() {
+      let expected = expected.as_ref().map(|expected| expected[index].clone());
+
+      types.push(match (&parameter.ty, expected) {
+        (Some(ty), expected) => {
+          self.resolve_type(parent.clone(), ty)?;
+
+          if let Some(expected) = expected {
+            if !self.satisfies(&expected, ty) {
+              Err(TypecheckerError::InvalidType {
+                expected,
+                found: ty.clone(),
+              })?
+            }
+          }
+
+          ty.clone()
+        }
+        (None, Some(expected)) => expected,
+        (None, None) => self.fresh(Some(parameter.name.clone())),
+      });
+    }
+
+    if let Some(ty) = &ty {
+      self.resolve_type(parent.clone(), ty)?;
+    }
+
+    self
+      .closures
+      .push((self.captures.len(), self.variables.len()));
+    self.captures.push(Vec::new());
+
+    let mut scope = Scope::new(Some(parent));
+    for (parameter, ty) in parameters.iter().zip(&types) {
+      scope.insert(
+        parameter.name.clone(),
+        self.variable(&parameter.name, ty.clone(), false),
+      );
+    }
+
+    self.return_types.push(ty.clone());
+    self.loops.push(None);
+    let span = body.span();
+    let body = self.typecheck_expression(Rc::new(RefCell::new(scope)), body);
+    self.loops.pop();
+    self.return_types.pop();
+    self.closures.pop();
+    let body = body?;
+
+    if let Some(ty) = ty {
+      self.expect_type(span, body.clone(), &ty)?;
+    }
+
+    Ok(Type::Function(types, Box::new(body)))
+  }
+
+  // the variables solved while checking an expression are filled in, so that its type is as exact as
+  // it can be by the time it's used
+  pub fn typecheck_expression(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    expression: ast::util::Expression<Type>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    self.typecheck_value(parent, expression, None)
+  }
+
+  fn typecheck_value(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    expression: ast::util::Expression<Type>,
+    expected: Option<&Type>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    let ty = self.check_expression(parent, expression, expected)?;
+    Ok(self.resolve(&ty))
+  }
+
+  fn check_expression(
+    &mut self,
+    parent: Rc<RefCell<Scope>>,
+    expression: ast::util::Expression<Type>,
+    expected: Option<&Type>,
+  ) -> Result<Type, TypecheckerError<Type>> {
+    // spanned literals are unwrapped first, so that their span is recorded. without an integer
+    // type to go by, a literal is an i32
+    if let (Some(value), false) = (
+      integer_literal(&expression),
+      matches!(expression, ast::util::Expression::Spanned(..)),
+    ) {
+      let ty = expected
+        .map(Type::reduce)
+        .filter(|ty| ty.is_integer())
+        .unwrap_or(Type::I32);
+
+      return if ty
+        .integer_range()
+        .is_some_and(|range| range.contains(&value))
+      {
+        Ok(ty)
+      } else {
+        Err(TypecheckerError::LiteralOutOfRange { value, ty })
+      };
+    }
+
+    match expression {
+      ast::util::Expression::Block {
+        expressions,
+        has_value,
+      } => {
+        let scope = Rc::new(RefCell::new(Scope::new(Some(parent))));
+        let mut value = None;
+        // whether an expression never produced a value, and the spans of the ones after it
+        let mut diverged = false;
+        let mut unreachable = Vec::new();
+        let count = expressions.len();
+
+        for (i, expression) in expressions.into_iter().enumerate() {
+          if diverged {
+            unreachable.push(match &expression {
+              ast::util::Expression::Spanned(span, _) => Some(span.clone()),
+              _ => None,
+            });
+          }
+
+          let expected = expected.filter(|_| has_value && i + 1 == count);
+          let ty = self.typecheck_value(scope.clone(), expression, expected)?;
+          diverged |= ty == Type::Never;
+          value = Some(ty);
+        }
+
+        if let (Some(first), Some(last)) = (unreachable.first(), unreachable.last()) {
+          let span = match (first, last) {
+            (Some(first), Some(last)) => Some(first.start..last.end),
+            _ => None,
+          };
+
+          self
+            .warnings
+            .push(TypecheckerWarning::UnreachableCode { span });
+        }
+
+        // the unreachable expressions are still checked, but the block never produces a value
+        if diverged {
+          Ok(Type::Never)
+        } else if has_value {
+          Ok(value.unwrap_or(Type::Tuple(vec![])))
+        } else {
+          Ok(Type::Tuple(vec![]))
+        }
+      }
+      ast::util::Expression::Call {
+        expression,
+        arguments,
+      } => {
+        let callee = self.callee(parent.clone(), &expression);
+        let expression_type = self.typecheck_expression(parent.clone(), *expression)?;
+
+        match expression_type {
+          Type::Function(parameters, r#type) => {
+            let names = match &callee {
+              Some(f) => f.header.parameters.iter().map(|p| p.name.clone()).collect(),
+              None => Vec::new(),
+            };
+
+            let rest = callee
+              .as_ref()
+              .and_then(|f| f.header.parameters.last())
+              .filter(|p| p.rest)
+              .map(|p| p.ty.clone());
+
+            // positional arguments fill the parameters in order, and named ones the parameter with
+            // their name. each binding is checked against the type it's expected to have
+            let mut bound = vec![false; parameters.len()];
+            let mut bindings = Vec::new();
+            let mut position = 0;
+
+            for argument in &arguments {
+              let index = match &argument.name {
+                Some(name) => names
+                  .iter()
+                  .position(|n| n == name)
+                  .ok_or_else(|| TypecheckerError::UnknownArgument { name: name.clone() })?,
+                None => {
+                  position += 1;
+                  position - 1
+                }
+              };
+
+              // positional arguments from the rest parameter on are its elements, unless it's
+              // already been given as an array by name
+              if let Some(element) = rest
+                .as_ref()
+                .filter(|_| argument.name.is_none() && index + 1 >= parameters.len())
+              {
+                if index + 1 == parameters.len() && bound[index] {
+                  Err(TypecheckerError::DuplicateArgument {
+                    name: names[index].clone(),
+                  })?
+                }
+
+                bound[parameters.len() - 1] = true;
+                bindings.push((element.clone(), argument.value.clone()));
+                continue;
+              }
+
+              match bound.get_mut(index) {
+                Some(false) => bound[index] = true,
+                Some(true) => Err(TypecheckerError::DuplicateArgument {
+                  name: names[index].clone(),
+                })?,
+                None => {
+                  Err(self.invalid_arguments(parent.clone(), parameters.clone(), &arguments))?
+                }
+              }
+
+              bindings.push((parameters[index].clone(), argument.value.clone()));
+            }
+
+            // a rest parameter may be given no elements at all
+            let defaulted = |index: usize| {
+              callee.as_ref().is_some_and(|f| {
+                f.header.parameters[index].default.is_some() || f.header.parameters[index].rest
+              })
+            };
+
+            if (0..parameters.len()).any(|index| !bound[index] && !defaulted(index)) {
+              Err(self.invalid_arguments(parent.clone(), parameters.clone(), &arguments))?
+            }
+
+            for (expected, value) in bindings {
+              let argument = self.typecheck_argument(parent.clone(), value, &expected)?;
+
+              if !self.satisfies(&argument, &expected) {
+                Err(self.invalid_arguments(parent.clone(), parameters.clone(), &arguments))?
+              }
+            }
+
+            Ok(*r#type)
+          }
+          found => Err(TypecheckerError::NotCallable { found })?,
+        }
+      }
+      ast::util::Expression::Index { expression, index } => {
+        // bounds are only known at runtime, but a negative constant is never in them
+        if index
+          .const_eval_with(&|name| constant(&parent, name))
+          .is_some_and(|n| n < 0)
+        {
+          Err(TypecheckerError::NegativeIndex)?
+        }
+
+        let found = self
+          .typecheck_expression(parent.clone(), *expression)?
+          .reduce();
+        let index = self.typecheck_expression(parent.clone(), *index)?.reduce();
+
+        if !index.is_integer() {
+          Err(TypecheckerError::InvalidIndex { found: index })?
+        }
+
+        match found {
+          Type::Array(element) | Type::FixedArray(element, _) => Ok(*element),
+          found => Err(TypecheckerError::NotIndexable { found })?,
+        }
+      }
+      // TODO: functions, etc.
+      ast::util::Expression::Identifier(name) => match parent.borrow().get(&name) {
+        Some(Item(id, ItemKind::Variable(ty, _))) => {
+          self.used.insert(id);
+          self.capture(id);
+          Ok(ty)
+        }
+        Some(Item(_, ItemKind::Function(f))) => Ok(function_type(&f)),
+        Some(Item(_, ItemKind::Const(ty, _))) => Ok(ty),
+        Some(Item(_, ItemKind::Variant(id, variant))) => {
+          let ty = match self.types.get(&id) {
+            Some(Item(_, ItemKind::Enum(e))) => Type::Named(vec![e.header.name.clone()], vec![]),
+            _ => unreachable!(),
+          };
+
+          constructor_type(ty, variant)
+        }
+        _ => Err(TypecheckerError::UnresolvedIdentifier(name))?,
+      },
+      ast::util::Expression::Path(path) => self.resolve_path(parent.clone(), &path),
+      ast::util::Expression::Spanned(span, expression) => {
+        if let ast::util::Expression::Identifier(name) = expression.as_ref() {
+          if let Some(item) = parent.borrow().get(name) {
+            self.references.push((span.clone(), item));
+          }
+        }
+
+        // the innermost expression an error came from is the one it's reported at
+        let ty = match self.typecheck_value(parent, *expression, expected) {
+          Ok(ty) => ty,
+          Err(error) => {
+            self.span.get_or_insert(span.clone());
+            Err(error)?
+          }
+        };
+        self.expression_types.insert(span, ty.clone());
+        Ok(ty)
+      }
+      ast::util::Expression::If {
+        condition,
+        consequence,
+        alternative,
+      } => {
+        let scope = self.typecheck_condition(parent.clone(), *condition)?;
+        let consequence: Type = self.typecheck_value(scope, *consequence, expected)?;
+
+        // without an alternative, the missing branch produces unit
+        if let Some(alternative) = alternative {
+          Ok(union!(
+            consequence,
+            self.typecheck_value(parent, *alternative, expected)?
+          ))
+        } else {
+          Ok(union!(consequence, Type::Tuple(vec![])))
+        }
+      }
+      ast::util::Expression::Literal(literal) => match literal {
+        ast::util::Literal::Char(_) => Ok(Type::Char),
+        // strings are arrays of chars
+        ast::util::Literal::String(_) => Ok(Type::Array(Box::new(Type::Char))),
+        ast::util::Literal::Tuple(vec) => Ok(Type::Tuple(
+          vec
+            .into_iter()
+            .map(|e| self.typecheck_expression(parent.clone(), e))
+            .collect::<Result<_, _>>()?,
+        )),
+        // each field is checked against the field of the same name in the expected record
+        ast::util::Literal::Record(fields) => {
+          let expected = match expected.map(Type::reduce) {
+            Some(Type::Record(expected)) => expected,
+            _ => Vec::new(),
+          };
+
+          let mut types = Vec::new();
+
+          for (name, value) in fields {
+            let expected = expected
+              .iter()
+              .find(|(field, _)| field == &name)
+              .map(|(_, ty)| ty);
+            types.push((name, self.typecheck_value(parent.clone(), value, expected)?));
+          }
+
+          let ty = Type::Record(types);
+          self.resolve_type(parent, &ty)?;
+          Ok(ty)
+        }
+        ast::util::Literal::Number(n) => Ok(n.into()),
+        // the first element decides the element type, and the rest must satisfy it. the length is
+        // known, so the literal can be used as a fixed-size array too
+        ast::util::Literal::Array(vec) => {
+          // a literal is only as long as it's written where a fixed length is asked for, so that
+          // it can otherwise be assigned an array of another length later
+          let length = vec.len();
+          let mut elements = vec.into_iter();
+          let (expected, fixed) = match expected.map(Type::reduce) {
+            Some(Type::Array(element)) => (Some(*element), false),
+            Some(Type::FixedArray(element, _)) => (Some(*element), true),
+            _ => (None, false),
+          };
+
+          let element = match elements.next() {
+            Some(element) => self.typecheck_value(parent.clone(), element, expected.as_ref())?,
+            None => self.fresh(None),
+          };
+
+          for other in elements {
+            let other = self.typecheck_value(parent.clone(), other, expected.as_ref())?;
+
+            if !self.satisfies(&other, &element) {
+              Err(TypecheckerError::InvalidType {
+                expected: element.clone(),
+                found: other,
+              })?
+            }
+          }
+
+          Ok(if fixed {
+            Type::FixedArray(Box::new(element), length)
+          } else {
+            Type::Array(Box::new(element))
+          })
+        }
+        ast::util::Literal::Bool(_) => Ok(Type::Bool),
+        ast::util::Literal::Closure {
+          parameters,
+          ty,
+          body,
+        } => match expected.map(Type::reduce) {
+          Some(Type::Function(expected, _)) if expected.len() == parameters.len() => {
+            self.typecheck_closure(parent, parameters, ty, *body, Some(expected))
+          }
+          _ => self.typecheck_closure(parent, parameters, ty, *body, None),
+        },
+      },
+      // a bare `return` returns unit. `return`, `break` and `continue` don't produce a value themselves
+      ast::util::Expression::Return(expression) => {
+        let ty = match (expression, self.return_types.last().cloned()) {
+          (Some(expression), Some(Some(expected))) => {
+            self.typecheck_argument(parent.clone(), *expression, &expected)?
+          }
+          (Some(expression), _) => self.typecheck_expression(parent.clone(), *expression)?,
+          (None, _) => Type::Tuple(vec![]),
+        };
+
+        if let Some(Some(expected)) = self.return_types.last().cloned() {
+          if !self.satisfies(&ty, &expected) {
+            Err(TypecheckerError::InvalidType {
+              expected,
+              found: ty.clone(),
+            })?
+          }
+        }
+
+        Ok(Type::Never)
+      }
+      ast::util::Expression::While {
+        label,
+        condition,
+        body,
+      } => {
+        let scope = self.typecheck_condition(parent.clone(), *condition)?;
+
+        self.loops.push(Some((Loop::Unit, label)));
+        let body = self.typecheck_expression(scope, *body);
+        self.loops.pop();
+        body?;
+
+        Ok(Type::Tuple(vec![]))
+      }
+      // a loop produces whatever it's broken with, and never finishes if it isn't
+      ast::util::Expression::Loop { label, body } => {
+        self.loops.push(Some((Loop::Infinite(Vec::new()), label)));
+        let body = self.typecheck_expression(parent.clone(), *body);
+        let types = match self.loops.pop() {
+          Some(Some((Loop::Infinite(types), _))) => types,
+          _ => unreachable!(),
+        };
+        body?;
+
+        Ok(
+          types
+            .into_iter()
+            .reduce(|a, b| union!(a, b))
+            .unwrap_or(Type::Never),
+        )
+      }
+      ast::util::Expression::Break { label, value } => {
+        let ty = match value {
+          Some(value) => self.typecheck_expression(parent.clone(), *value)?,
+          None => Type::Tuple(vec![]),
+        };
+
+        let index = self.enclosing_loop(label)?;
+        match &mut self.loops[index] {
+          Some((Loop::Infinite(types), _)) => types.push(ty.clone()),
+          _ => {
+            if !self.satisfies(&ty, &Type::Tuple(vec![])) {
+              Err(TypecheckerError::InvalidType {
+                expected: Type::Tuple(vec![]),
+                found: ty.clone(),
+              })?
+            }
+          }
+        }
+
+        Ok(Type::Never)
+      }
+      ast::util::Expression::Continue { label } => {
+        self.enclosing_loop(label)?;
+        Ok(Type::Never)
+      }
+      // numbers convert between each other, and chars to and from integers
+      ast::util::Expression::Cast { value, ty } => {
+        let from = self.typecheck_expression(parent.clone(), *value)?.reduce();
+        self.resolve_type(parent.clone(), &ty)?;
+        let to = ty.reduce();
+
+        let valid = self.satisfies(&from, &to)
+          || (from.is_numeric() && to.is_numeric())
+          || (from == Type::Char && to.is_integer())
+          || (from.is_integer() && to == Type::Char);
+
+        if valid {
+          Ok(to)
+        } else {
+          Err(TypecheckerError::InvalidCast { from, to })?
+        }
+      }
+      // the error is returned as the enclosing function's own, so its result has to be able to hold it
+      ast::util::Expression::Try(expression) => {
+        let found = self.typecheck_expression(parent.clone(), *expression)?;
+        let Some((ok, error)) = self.result_types(parent.clone(), &found) else {
+          Err(TypecheckerError::NotTryable(found))?
+        };
+
+        let returned = self.return_types.last().cloned().flatten();
+        let Some((_, expected)) = returned
+          .as_ref()
+          .and_then(|ty| self.result_types(parent.clone(), ty))
+        else {
+          Err(TypecheckerError::TryOutsideResult { found: returned })?
+        };
+
+        if !self.satisfies(&error, &expected) {
+          Err(TypecheckerError::InvalidType {
+            expected,
+            found: error,
+          })?
+        }
+
+        Ok(ok)
+      }
+      // fields are the instance functions in a struct's module
+      ast::util::Expression::Field { expression, field } => {
+        let found = self
+          .typecheck_expression(parent.clone(), *expression)?
+          .reduce();
+        let unknown = || TypecheckerError::UnknownField {
+          struct_name: found.to_string(),
+          field: field.clone(),
+        };
+
+        let (r#struct, arguments) = match &found {
+          Type::Record(fields) => {
+            return fields
+              .iter()
+              .find(|(name, _)| *name == field)
+              .map(|(_, ty)| ty.clone())
+              .ok_or_else(unknown);
+          }
+          Type::Named(path, arguments) => match self.lookup_type(parent.clone(), path)? {
+            ItemKind::Struct(s) => (s, arguments),
+            _ => Err(unknown())?,
+          },
+          _ => Err(unknown())?,
+        };
+
+        let (modifiers, ty) = r#struct
+          .module
+          .items
+          .iter()
+          .find_map(|item| match &item.kind {
+            ast::module::ItemKind::Function(f)
+              if f.header.name == field && !item.modifiers.static_ =>
+            {
+              Some((&item.modifiers, function_type(f)))
+            }
+            ast::module::ItemKind::Field { name, ty, .. } if *name == field => {
+              Some((&item.modifiers, ty.clone()))
+            }
+            _ => None,
+          })
+          .ok_or_else(unknown)?;
+
+        if !modifiers.public && !self.structs.contains(&r#struct) {
+          Err(TypecheckerError::PrivateField {
+            struct_name: found.to_string(),
+            field: field.clone(),
+          })?
+        }
+
+        let arguments = r#struct
+          .header
+          .type_parameters
+          .iter()
+          .map(|parameter| vec![parameter.name.clone()])
+          .zip(arguments.iter().cloned())
+          .collect();
+
+        Ok(ty.substitute(&arguments))
+      }
+      ast::util::Expression::StructLiteral { path, fields } => {
+        self.struct_literal(parent, path, fields)
+      }
+      ast::util::Expression::TupleIndex { expression, index } => {
+        let found = self
+          .typecheck_expression(parent.clone(), *expression)?
+          .reduce();
+
+        match &found {
+          Type::Tuple(types) if index < types.len() => Ok(types[index].clone()),
+          _ => Err(TypecheckerError::TupleIndexOutOfBounds { found, index })?,
+        }
+      }
+      // the value is checked before the name is bound, so `let x = x` refers to an outer `x`
+      ast::util::Expression::Let {
+        mutable,
+        pattern,
+        ty,
+        value,
+      } => {
+        if !pattern.irrefutable() {
+          Err(TypecheckerError::RefutablePattern)?
+        }
+
+        let ty = self.let_value(parent.clone(), ty, *value)?;
+        self.bind(parent, pattern, ty, mutable)?;
+
+        Ok(Type::Tuple(vec![]))
+      }
+      ast::util::Expression::For {
+        label,
+        pattern,
+        iterable,
+        body,
+      } => {
+        let element = match self.typecheck_expression(parent.clone(), *iterable)? {
+          Type::Array(element) | Type::FixedArray(element, _) => *element,
+          ty => Err(TypecheckerError::NotIterable(ty))?,
+        };
+
+        if !pattern.irrefutable() {
+          Err(TypecheckerError::RefutablePattern)?
+        }
+
+        let scope = Rc::new(RefCell::new(Scope::new(Some(parent))));
+        self.bind(scope.clone(), pattern, element, false)?;
+
+        self.loops.push(Some((Loop::Unit, label)));
+        let body = self.typecheck_expression(scope, *body);
+        self.loops.pop();
+        body?;
+
+        Ok(Type::Tuple(vec![]))
+      }
+      ast::util::Expression::Match { scrutinee, arms } => {
+        let scrutinee = self.typecheck_expression(parent.clone(), *scrutinee)?;
+        let mut result = None;
+
+        // the variants not covered so far, when matching on an enum
+        let mut missing = match scrutinee.reduce() {
+          Type::Named(path, _) => match self.lookup_type(parent.clone(), &path) {
+            Ok(ItemKind::Enum(e)) => Some(
+              e.variants
+                .into_iter()
+                .map(|variant| variant.name)
+                .collect::<Vec<_>>(),
+            ),
+            _ => None,
+          },
+          _ => None,
+        };
+        let mut exhaustive = false;
+
+        for (pattern, body) in arms {
+          let scope = Rc::new(RefCell::new(Scope::new(Some(parent.clone()))));
+          let pattern = self.resolve_pattern(scope.clone(), pattern);
+          self.bind(scope.clone(), pattern.clone(), scrutinee.clone(), false)?;
+
+          if exhaustive {
+            self.warnings.push(TypecheckerWarning::UnreachableArm);
+          }
+
+          match coverage(&pattern) {
+            Coverage::All => exhaustive = true,
+            Coverage::Variant(name) => {
+              if let Some(missing) = &mut missing {
+                match missing.iter().position(|variant| *variant == name) {
+                  Some(index) => {
+                    missing.remove(index);
+                  }
+                  None if !exhaustive => self.warnings.push(TypecheckerWarning::UnreachableArm),
+                  None => (),
+                }
+
+                exhaustive |= missing.is_empty();
+              }
+            }
+            Coverage::Partial => (),
+          }
+
+          let body = self.typecheck_value(scope, body, expected)?;
+
+          result = Some(match result {
+            Some(result) => union!(result, body),
+            None => body,
+          });
+        }
+
+        match missing {
+          Some(missing) if !exhaustive => Err(TypecheckerError::NonExhaustiveMatch { missing })?,
+          _ => Ok(result.unwrap_or(Type::Tuple(vec![]))),
+        }
+      }
+      ast::util::Expression::Infix {
+        operator,
+        operands: (a, b),
+      } if operator == "=" => self.assign(parent, None, *a, *b),
+      ast::util::Expression::Infix {
+        operator,
+        operands: (a, b),
+      } if COMPOUND_ASSIGNMENTS.contains(&operator.as_str()) => {
+        let operator = &operator[..operator.len() - 1];
+        self.warn_division(&parent, operator, &b);
+        self.assign(parent, Some(operator), *a, *b)
+      }
+      ast::util::Expression::Infix {
+        operator,
+        operands: (a, b),
+      } => {
+        self.warn_division(&parent, &operator, &b);
+
+        let left = self.typecheck_expression(parent.clone(), *a)?.reduce();
+        let right = self.typecheck_expression(parent.clone(), *b)?.reduce();
+        self.infix(parent, &operator, left, right)
+      }
+      // ranges are arrays of the integers between their endpoints, so they can be iterated over
+      ast::util::Expression::Range { start, end, kind } => {
+        let start = self.typecheck_expression(parent.clone(), *start)?.reduce();
+        let end = self.typecheck_expression(parent.clone(), *end)?.reduce();
+
+        if start == end && start.is_integer() {
+          Ok(Type::Array(Box::new(start)))
+        } else {
+          Err(TypecheckerError::InvalidOperands {
+            operator: kind.operator().to_string(),
+            left: start,
+            right: end,
+          })?
+        }
+      }
+      // borrowing isn't overloadable. a temporary can be borrowed mutably, but a place has to be
+      // mutable itself
+      ast::util::Expression::Prefix { operator, operand }
+        if operator == "&" || operator == "&mut" =>
+      {
+        let mutable = operator == "&mut";
+        if mutable {
+          self.mutable_place(parent.clone(), &operand, |name| {
+            TypecheckerError::MutableBorrowOfImmutable { name }
+          })?;
+        }
+
+        let found = self.typecheck_expression(parent, *operand)?;
+        Ok(Type::Reference(Box::new(found), mutable))
+      }
+      ast::util::Expression::Prefix { operator, operand } => {
+        let found = self
+          .typecheck_expression(parent.clone(), *operand)?
+          .reduce();
+
+        if let Some(result) =
+          self.overload(parent.clone(), &operator, std::slice::from_ref(&found))?
+        {
+          return Ok(result);
+        }
+
+        if operator == "*" {
+          return match found {
+            Type::Reference(ty, _) => Ok(*ty),
+            found => Err(TypecheckerError::NotDereferenceable { found }),
+          };
+        }
+
+        let valid = match operator.as_str() {
+          "!" => found == Type::Bool,
+          "+" => found.is_numeric(),
+          "-" => found.is_signed(),
+          "~" => found.is_integer(),
+          _ => false,
+        };
+
+        if valid {
+          Ok(found)
+        } else {
+          Err(TypecheckerError::InvalidOperand { operator, found })?
+        }
+      }
+    }
+  }
+}
+
+// `a += b` and the like, which apply the operator without its `=` and assign the result
+const COMPOUND_ASSIGNMENTS: &[&str] = &[
+  "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>=", ">>>=",
+];
+
+// patterns are resolved first, so bare names that refer to variants aren't counted as bindings
+fn coverage(pattern: &ast::util::Pattern<Type>) -> Coverage {
+  match pattern {
+    pattern if pattern.irrefutable() => Coverage::All,
+    ast::util::Pattern::Variant { path, fields } if fields.iter().all(|f| f.irrefutable()) => {
+      Coverage::Variant(path.last().unwrap().clone())
+    }
+    // the fields that are left out match anything
+    ast::util::Pattern::Struct { path, fields } if fields.iter().all(|(_, f)| f.irrefutable()) => {
+      Coverage::Variant(path.last().unwrap().clone())
+    }
+    _ => Coverage::Partial,
+  }
+}
+
+// the value of a constant in scope, for folding
+fn constant(scope: &Rc<RefCell<Scope>>, name: &str) -> Option<i128> {
+  match scope.borrow().get(name) {
+    Some(Item(_, ItemKind::Const(_, value))) => Some(value),
+    _ => None,
+  }
+}
+
+// the struct at `path` in `module`, going through the structs nested in it
+fn nested_struct<'a>(
+  module: &'a mut ast::module::Module<Type>,
+  path: &[String],
+) -> Option<&'a mut ast::r#struct::Struct<Type>> {
+  let r#struct = module
+    .items
+    .iter_mut()
+    .find_map(|item| match &mut item.kind {
+      ast::module::ItemKind::Struct(s) if s.header.name == path[0] => Some(s),
+      _ => None,
+    })?;
+
+  match path.len() {
+    1 => Some(r#struct),
+    _ => nested_struct(&mut r#struct.module, &path[1..]),
+  }
+}
+
+// the names an impl block gives a struct's type parameters, mapped to the struct's own names
+pub(super) fn renames(
+  arguments: &[Type],
+  parameters: &[ast::util::TypeParameter],
+) -> BTreeMap<Vec<String>, Type> {
+  arguments
+    .iter()
+    .zip(parameters)
+    .filter_map(|(argument, parameter)| match argument {
+      Type::Named(name, _) => Some((
+        name.clone(),
+        Type::Named(vec![parameter.name.clone()], vec![]),
+      )),
+      _ => None,
+    })
+    .collect()
+}
+
+// a struct as a type over its own type parameters, which is what `Self` stands for in its items
+fn self_type(header: &ast::r#struct::Header) -> Type {
+  Type::Named(
+    vec![header.name.clone()],
+    header
+      .type_parameters
+      .iter()
+      .map(|parameter| Type::Named(vec![parameter.name.clone()], vec![]))
+      .collect(),
+  )
+}
+
+// replaces `Self` in the items of every struct in a module with that struct. nested structs have a
+// `Self` of their own, so they're resolved in turn
+pub(super) fn resolve_self(module: ast::module::Module<Type>) -> ast::module::Module<Type> {
+  let items = module
+    .items
+    .into_iter()
+    .map(|item| match item.kind {
+      ast::module::ItemKind::Struct(s) => {
+        let arguments = [(vec!["Self".to_string()], self_type(&s.header))]
+          .into_iter()
+          .collect();
+
+        ast::module::Item {
+          kind: ast::module::ItemKind::Struct(ast::r#struct::Struct {
+            module: resolve_self(s.module.substitute(&arguments)),
+            header: s.header,
+          }),
+          ..item
+        }
+      }
+      _ => item,
+    })
+    .collect();
+
+  ast::module::Module { items }
+}
+
+// the value of an unsuffixed integer literal, possibly negated, whose type comes from where it's
+// used. suffixed literals keep their own type. a value past what an i128 can hold is kept as the
+// largest one, which is out of range for every type anyway
+fn integer_literal(expression: &ast::util::Expression<Type>) -> Option<i128> {
+  match expression.unspanned() {
+    ast::util::Expression::Literal(ast::util::Literal::Number(
+      ast::util::NumberLiteral::Integer(n),
+    )) => Some(i128::try_from(*n).unwrap_or(i128::MAX)),
+    ast::util::Expression::Prefix { operator, operand } if operator == "-" => {
+      integer_literal(operand).map(|n| -n)
+    }
+    _ => None,
+  }
+}
+
+// binds the type parameters in `expected` to the parts of `found` in the same places. the first
+// binding of a parameter is kept, and the others are checked against it
+fn infer(
+  parameters: &[Vec<String>],
+  expected: &Type,
+  found: &Type,
+  arguments: &mut BTreeMap<Vec<String>, Type>,
+) {
+  match (expected, found.reduce()) {
+    (Type::Named(path, types), found) if types.is_empty() && parameters.contains(path) => {
+      arguments.entry(path.clone()).or_insert(found);
+    }
+    (Type::Named(a, x), Type::Named(b, y)) if *a == b && x.len() == y.len() => {
+      for (x, y) in x.iter().zip(&y) {
+        infer(parameters, x, y, arguments);
+      }
+    }
+    (Type::Function(a, x), Type::Function(b, y)) if a.len() == b.len() => {
+      for (a, b) in a.iter().zip(&b) {
+        infer(parameters, a, b, arguments);
+      }
+
+      infer(parameters, x, &y, arguments);
+    }
+    (Type::Tuple(a), Type::Tuple(b)) if a.len() == b.len() => {
+      for (a, b) in a.iter().zip(&b) {
+        infer(parameters, a, b, arguments);
+      }
+    }
+    (Type::Record(a), Type::Record(b)) if ast::util::same_fields(a, &b) => {
+      for ((_, a), (_, b)) in a.iter().zip(&b) {
+        infer(parameters, a, b, arguments);
+      }
+    }
+    (Type::Array(a) | Type::FixedArray(a, _), Type::Array(b) | Type::FixedArray(b, _))
+    | (Type::Reference(a, _), Type::Reference(b, _)) => infer(parameters, a, &b, arguments),
+    _ => (),
+  }
+}
+
+// the operator, operands and result of an operator declaration
+fn operator_signature(
+  operator: &ast::operator::Operator<Type>,
+) -> (&String, Vec<&ast::util::Parameter<Type>>, &Type) {
+  match &operator.header {
+    ast::operator::Header::Prefix(p) => (&p.operator, vec![&p.operand], &p.result),
+    ast::operator::Header::Infix(i) => (&i.operator, vec![&i.operands.0, &i.operands.1], &i.result),
+  }
+}
+
+fn function_type(function: &ast::function::Function<Type>) -> Type {
+  Type::Function(
+    function
+      .header
+      .parameters
+      .iter()
+      .map(ast::util::Parameter::binding)
+      .collect(),
+    Box::new(function.header.ty.clone().unwrap_or(Type::Tuple(vec![]))),
+  )
+}
+
+// variants without fields are values rather than constructors
+// a variant with named fields is only built with a struct literal, so it has no constructor
+fn constructor_type(
+  ty: Type,
+  variant: ast::r#enum::Variant<Type>,
+) -> Result<Type, TypecheckerError<Type>> {
+  if !variant.names.is_empty() {
+    Err(TypecheckerError::NamedVariantFields {
+      variant: variant.name,
+    })?
+  }
+
+  Ok(if variant.fields.is_empty() {
+    ty
+  } else {
+    Type::Function(variant.fields, Box::new(ty))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    error::{Error, LexerError, ParserError},
+    lexer::token::TokenKind,
+  };
+
+  // both parse with spans, like `typecheck_str`, so the tests also cover spanned expressions. the
+  // spans of errors are left to the tests that are about them
+  fn typecheck(source: &str) -> Result<(), Vec<TypecheckerError<Type>>> {
+    let tokens = Lexer::new(source).lex(false).unwrap();
+    let mut parser = Parser::new(tokens.iter());
+    parser.spans = true;
+    let module = parser.parse().unwrap();
+    Typechecker::new()
+      .typecheck(module)
+      .map_err(|errors| errors.into_iter().map(|Error(_, error)| error).collect())
+  }
+
+  fn typecheck_expression(source: &str) -> Result<Type, TypecheckerError<Type>> {
+    let tokens = Lexer::new(source).lex(false).unwrap();
+    let mut parser = Parser::new(tokens.iter());
+    parser.spans = true;
+    let expression = parser.parse_expression().unwrap();
+    Typechecker::new().typecheck_expression(Rc::new(RefCell::new(Scope::default())), expression)
+  }
+
+  #[test]
+  fn test_satisfies_cache() {
+    let mut typechecker = Typechecker::new();
+    let a = union!(Type::Char, Type::Bool);
+    let b = union!(Type::Char, Type::Bool, Type::I32);
+
+    assert!(typechecker.satisfies(&a, &b));
+    assert!(!typechecker.satisfies(&b, &a));
+    // `(a)` reduces to `a`, so it shares the entry
+    assert!(typechecker.satisfies(&Type::Tuple(vec![a.clone()]), &b));
+    assert_eq!(typechecker.satisfied.len(), 2);
+  }
+
+  #[test]
+  fn test_typecheck_str() {
+    assert_eq!(
+      Typechecker::new().typecheck_str("fn main(x: bool): bool x;"),
+      Ok(())
+    );
+
+    assert_eq!(
+      Typechecker::new().typecheck_str("fn main(): char '';"),
+      Err(CompileError::Lexer(Error(
+        17..18,
+        LexerError::UnexpectedCharacter('\'', &[], &[])
+      )))
+    );
+
+    assert!(matches!(
+      Typechecker::new().typecheck_str("fn main(): char"),
+      Err(CompileError::Parser(Error(
+        _,
+        ParserError::UnexpectedToken(Some(TokenKind::Eof), _)
+      )))
+    ));
+
+    assert_eq!(
+      Typechecker::new().typecheck_str("fn main(): char true;"),
+      Err(CompileError::Typechecker(vec![Error(
+        16..20,
+        TypecheckerError::InvalidType {
+          expected: Type::Char,
+          found: Type::Bool
+        }
+      )]))
+    );
+  }
+
+  #[test]
+  fn test_error_spans() {
+    let errors = |source: &str| match Typechecker::new().typecheck_str(source) {
+      Err(CompileError::Typechecker(errors)) => errors,
+      result => panic!("{:?}", result),
+    };
+
+    // a mismatch is at the value, and other errors are at the innermost expression they came from
+    assert_eq!(
+      errors("fn f(): () { let x: bool = 12; };"),
+      vec![Error(
+        27..29,
+        TypecheckerError::InvalidType {
+          expected: Type::Bool,
+          found: Type::I32
+        }
+      )]
+    );
+    assert!(matches!(
+      &errors("fn f(): i32 1 + g(true); fn g(x: i32): i32 x;")[..],
+      [Error(span, TypecheckerError::InvalidArguments { .. })] if *span == (16..23)
+    ));
+    assert_eq!(
+      errors("struct S { fn f(): i32 if 'a' 1 else 2; };"),
+      vec![Error(
+        26..29,
+        TypecheckerError::InvalidType {
+          expected: Type::Bool,
+          found: Type::Char
+        }
+      )]
+    );
+
+    // errors that aren't in an expression are at their item
+    assert_eq!(
+      errors("fn f(): () (); fn f(): () ();"),
+      vec![Error(
+        15..29,
+        TypecheckerError::DuplicateDefinition {
+          name: "f".to_string(),
+          kind: "function"
+        }
+      )]
+    );
+  }
+
+  #[test]
+  fn test_unused() {
+    let warnings = |source| {
+      let mut typechecker = Typechecker::new();
+      typechecker.typecheck_str(source).unwrap();
+      typechecker.warnings
+    };
+
+    assert_eq!(
+      warnings("fn f(a: bool, b: bool): bool a;"),
+      vec![TypecheckerWarning::UnusedVariable("b".to_string())]
+    );
+
+    assert_eq!(
+      warnings("fn f(a: bool): () { let b = a; let c = b; fn(d: char) (); };"),
+      vec![
+        TypecheckerWarning::UnusedVariable("c".to_string()),
+        TypecheckerWarning::UnusedVariable("d".to_string())
+      ]
+    );
+
+    assert_eq!(warnings("fn f(_a: bool): () { let _b = 'b' };"), vec![]);
+  }
+
+  #[test]
+  fn test_duplicate_definition() {
+    assert_eq!(
+      typecheck("fn f(): () (); fn f(): () ();"),
+      Err(vec![TypecheckerError::DuplicateDefinition {
+        name: "f".to_string(),
+        kind: "function"
+      }])
+    );
+
+    assert_eq!(
+      typecheck("struct X {}; fn X(): () ();"),
+      Err(vec![TypecheckerError::DuplicateDefinition {
+        name: "X".to_string(),
+        kind: "function"
+      }])
+    );
+
+    assert_eq!(
+      typecheck("struct X { static fn f(): () (); fn f(): () (); };"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_multiple_errors() {
+    assert_eq!(
+      typecheck("fn f(): char true; fn g(): bool y; fn h(): bool true;"),
+      Err(vec![
+        TypecheckerError::InvalidType {
+          expected: Type::Char,
+          found: Type::Bool
+        },
+        TypecheckerError::UnresolvedIdentifier("y".to_string())
+      ])
+    );
+
+    // errors in a struct's static and instance items are all reported
+    assert_eq!(
+      typecheck(
+        "struct X { static fn f(): char true; fn g(): () f(); fn f(): () (); fn f(): () (); };"
+      ),
+      Err(vec![
+        TypecheckerError::InvalidType {
+          expected: Type::Char,
+          found: Type::Bool
+        },
+        TypecheckerError::DuplicateDefinition {
+          name: "f".to_string(),
+          kind: "function"
+        }
+      ])
+    );
+  }
+
+  #[test]
+  fn test_recursion() {
+    // every item is registered before any body is checked
+    assert_eq!(
+      typecheck("fn f(x: bool): bool if x f(false) else x;"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(
+        r#"
+fn is_even(n: bool): bool if n is_odd(n) else true;
+fn is_odd(n: bool): bool if n is_even(n) else false;
+"#
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): char g(); fn g(): bool f();"),
+      Err(vec![
+        TypecheckerError::InvalidType {
+          expected: Type::Char,
+          found: Type::Bool
+        },
+        TypecheckerError::InvalidType {
+          expected: Type::Bool,
+          found: Type::Char
+        }
+      ])
+    );
+
+    assert_eq!(
+      typecheck("enum List { Cons(i32, List), Nil }; fn cons(x: i32, xs: List): List Cons(x, xs);"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_trait() {
+    assert_eq!(
+      typecheck(
+        r#"
+trait Shape {
+  fn area(): f64;
+  static fn unit(f64): bool;
+};
+
+struct Square: Shape {
+  fn area(): f64 1.0;
+  static fn unit(x: f64): bool true;
+};
+"#
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("trait Shape { fn area(): f64; }; struct Square: Shape {};"),
+      Err(vec![TypecheckerError::UnimplementedTraitMethod {
+        r#trait: "Shape".to_string(),
+        method: "area".to_string()
+      }])
+    );
+
+    // a method with the wrong signature doesn't count
+    assert_eq!(
+      typecheck("trait Shape { fn area(): f64; }; struct Square: Shape { fn area(): f32 1f32; };"),
+      Err(vec![TypecheckerError::UnimplementedTraitMethod {
+        r#trait: "Shape".to_string(),
+        method: "area".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("trait A { trait B { fn b(): (); }; }; struct X: A::B { fn b() (); };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("struct X: A {};"),
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: "A".to_string()
+      }])
+    );
+  }
+
+  #[test]
+  fn test_operator_overloading() {
+    let vector = r#"
+trait Add { + (Self, Self): Self; };
+
+struct Vector: Add {
+  + (a: Vector, _b: Vector): Vector a;
+  - (v: Vector): Vector v;
+};
+
+struct Point {};
+"#;
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(a: Vector, b: Vector): Vector -(a + b);",
+        vector
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(a: Point, b: Point): Point a + b;",
+        vector
+      )),
+      Err(vec![TypecheckerError::InvalidOperands {
+        operator: "+".to_string(),
+        left: Type::Named(vec!["Point".to_string()], vec![]),
+        right: Type::Named(vec!["Point".to_string()], vec![])
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(a: Vector): Vector a + 1;", vector)),
+      Err(vec![TypecheckerError::InvalidOperands {
+        operator: "+".to_string(),
+        left: Type::Named(vec!["Vector".to_string()], vec![]),
+        right: Type::I32
+      }])
+    );
+
+    assert_eq!(
+      typecheck("trait Add { + (Self, Self): Self; }; struct Point: Add {};"),
+      Err(vec![TypecheckerError::UnimplementedTraitMethod {
+        r#trait: "Add".to_string(),
+        method: "+".to_string()
+      }])
+    );
+
+    // the struct's type arguments are substituted into the operator
+    assert_eq!(
+      typecheck("struct Box<T> { + (_a: Box<T>, b: T): T b; }; fn f(b: Box<char>): char b + 'c';"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_generic_struct() {
+    assert_eq!(
+      typecheck(
+        r#"
+struct Box<T> {
+  fn get(x: T): T x;
+  static fn wrap(x: T): (T, T) (x, x);
+};
+
+fn unbox(b: Box<i32>): Box<i32> b;
+"#
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("struct Box<T> { fn get(x: U): T x; };"),
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: "U".to_string()
+      }])
+    );
+
+    // type parameters aren't visible outside the struct
+    assert_eq!(
+      typecheck("struct Box<T> {}; fn f(x: T): () ();"),
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: "T".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("struct Box<T> {}; fn f(x: Box): () ();"),
+      Err(vec![TypecheckerError::InvalidTypeArguments {
+        name: "Box".to_string(),
+        expected: 1,
+        found: 0
+      }])
+    );
+
+    assert_eq!(
+      typecheck("trait A {}; struct Box<T: A, U> { fn f(x: T, y: U): () (); };"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_bounds() {
+    let sorted = r#"
+trait Comparable { fn compare(Self): i32; };
+struct Number: Comparable { fn compare(other: Number): i32 0; };
+struct Sorted<T: Comparable> {};
+"#;
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(s: Sorted<Number>): () (); struct Nested<U: Comparable> {{ fn g(s: Sorted<U>): () (); }};",
+        sorted
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(s: Sorted<bool>): () ();", sorted)),
+      Err(vec![TypecheckerError::UnsatisfiedBound {
+        type_arg: Type::Bool,
+        trait_name: "Comparable".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} struct Plain {{}}; fn f(s: Sorted<Plain>): () ();",
+        sorted
+      )),
+      Err(vec![TypecheckerError::UnsatisfiedBound {
+        type_arg: Type::Named(vec!["Plain".to_string()], vec![]),
+        trait_name: "Comparable".to_string()
+      }])
+    );
+  }
+
+  #[test]
+  fn test_if() {
+    assert_eq!(
+      typecheck_expression("if true 'a' else if false 1 else true"),
+      Ok(union!(Type::Char, Type::I32, Type::Bool))
+    );
+
+    assert_eq!(
+      typecheck_expression("if true 'a' else if false 1"),
+      Ok(union!(Type::Char, Type::I32, Type::Tuple(vec![])))
+    );
+
+    assert_eq!(
+      typecheck_expression("if true { 'a' }"),
+      Ok(union!(Type::Char, Type::Tuple(vec![])))
+    );
+
+    assert_eq!(
+      typecheck("fn main(): char if true { 'a' };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: union!(Type::Char, Type::Tuple(vec![]))
+      }])
+    );
+
+    assert_eq!(
+      typecheck_expression("if 'a' 'b'"),
+      Err(TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::Char
+      })
+    );
+  }
+
+  #[test]
+  fn test_field() {
+    let point = r#"
+struct Point {
+  pub fn x(): i32 1;
+  fn secret(): i32 2;
+  pub static fn origin(): i32 0;
+
+  pub fn peek(p: Point): i32 p.secret();
+};
+"#;
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(p: Point): i32 p.x();", point)),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(p: Point): i32 p.y();", point)),
+      Err(vec![TypecheckerError::UnknownField {
+        struct_name: "Point".to_string(),
+        field: "y".to_string()
+      }])
+    );
+
+    // static functions aren't fields
+    assert_eq!(
+      typecheck(&format!("{} fn f(p: Point): i32 p.origin();", point)),
+      Err(vec![TypecheckerError::UnknownField {
+        struct_name: "Point".to_string(),
+        field: "origin".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(p: Point): i32 p.secret();", point)),
+      Err(vec![TypecheckerError::PrivateField {
+        struct_name: "Point".to_string(),
+        field: "secret".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("struct Box<T> { pub fn get(x: T): T x; }; fn f(b: Box<char>): char b.get('a');"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck_expression("true.x"),
+      Err(TypecheckerError::UnknownField {
+        struct_name: "bool".to_string(),
+        field: "x".to_string()
+      })
+    );
+
+    // fields are read like methods, and assigned to like variables
+    let pair = "struct Pair<T> { pub a: T; b: T; pub fn second(): T self.b; };";
+    assert_eq!(
+      typecheck(&format!("{} fn f(p: Pair<char>): char p.a;", pair)),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck(&format!("{} fn f(p: Pair<char>): char p.b;", pair)),
+      Err(vec![TypecheckerError::PrivateField {
+        struct_name: "Pair<char>".to_string(),
+        field: "b".to_string()
+      }])
+    );
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(p: Pair<char>): () {{ let mut q = p; q.a = 'b' }};",
+        pair
+      )),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck("struct P { x: i32; x: i32; };"),
+      Err(vec![TypecheckerError::DuplicateDefinition {
+        name: "x".to_string(),
+        kind: "field"
+      }])
+    );
+    assert_eq!(
+      typecheck("struct P { x: i32 = 'a'; };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::I32,
+        found: Type::Char
+      }])
+    );
+  }
+
+  #[test]
+  fn test_struct_literal() {
+    let point = "struct Point { pub x: i32; pub y: i32; pub z: i32 = 0; };";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(y: i32): Point Point {{ x: 1, y }};",
+        point
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): i32 Point {{ x: 1, y: 2, z: 3 }}.z;",
+        point
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Point Point {{ x: 1 }};", point)),
+      Err(vec![TypecheckerError::MissingFields {
+        struct_name: "Point".to_string(),
+        fields: vec!["y".to_string()]
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Point Point {{ x: 1, y: 2, w: 3 }};",
+        point
+      )),
+      Err(vec![TypecheckerError::UnknownField {
+        struct_name: "Point".to_string(),
+        field: "w".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Point Point {{ x: 1, y: 2, x: 3 }};",
+        point
+      )),
+      Err(vec![TypecheckerError::DuplicateField {
+        struct_name: "Point".to_string(),
+        field: "x".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Point Point {{ x: 1, y: 'a' }};",
+        point
+      )),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::I32,
+        found: Type::Char
+      }])
+    );
+
+    // private fields can only be given inside the struct
+    let secret = "struct Secret { x: i32; pub static fn new(): Secret Secret { x: 1 }; };";
+    assert_eq!(typecheck(secret), Ok(()));
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Secret Secret {{ x: 1 }};", secret)),
+      Err(vec![TypecheckerError::PrivateField {
+        struct_name: "Secret".to_string(),
+        field: "x".to_string()
+      }])
+    );
+
+    // type arguments are inferred from the values
+    let pair = "struct Pair<T> { pub a: T; pub b: [T]; };";
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Pair<char> Pair {{ a: 'a', b: ['b'] }};",
+        pair
+      )),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Pair<char> Pair {{ a: 'a', b: [1] }};",
+        pair
+      )),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Array(Box::new(Type::Char)),
+        found: Type::Array(Box::new(Type::I32))
+      }])
+    );
+    assert_eq!(
+      typecheck("struct Empty<T> {}; fn f(): () { Empty {}; };"),
+      Err(vec![TypecheckerError::CannotInferTypeArgument {
+        name: "T".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("enum E { A }; fn f(): E E {};"),
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: "E".to_string()
+      }])
+    );
+  }
+
+  #[test]
+  fn test_method() {
+    let counter = r#"
+struct Counter<T> {
+  pub fn get(): i32 1;
+  pub fn pair(x: T): (i32, T) (self.get(), x);
+  pub fn me(): Counter<T> self;
+  pub static fn new(): () ();
+};
+"#;
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(c: Counter<char>): (i32, char) c.me().pair('a');",
+        counter
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(c: Counter<char>): i32 c.get(1);",
+        counter
+      )),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![],
+        found: vec![Type::I32]
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(c: Counter<char>): () c.new();", counter)),
+      Err(vec![TypecheckerError::UnknownField {
+        struct_name: "Counter<char>".to_string(),
+        field: "new".to_string()
+      }])
+    );
+
+    // static functions have no instance
+    assert_eq!(
+      typecheck("struct Counter { static fn new(): () self; };"),
+      Err(vec![TypecheckerError::UnresolvedIdentifier(
+        "self".to_string()
+      )])
+    );
+  }
+
+  #[test]
+  fn test_self_type() {
+    assert_eq!(
+      typecheck(
+        r#"
+trait Clone {
+  fn clone(): Self;
+};
+
+struct Pair<T>: Clone {
+  pub fn clone(): Self self;
+  pub fn swap(other: Self): Pair<T> other;
+};
+
+fn f(p: Pair<char>): Pair<char> p.clone();
+"#
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): Self f();"),
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: "Self".to_string()
+      }])
+    );
+
+    // a nested struct has its own `Self`
+    assert_eq!(
+      typecheck(
+        "
+          struct P {
+            pub struct Q { pub static fn new(): Self Q {}; pub fn me(): Self self; };
+            pub fn me(): Self self;
+            fn f(): Q Q::new().me();
+          };
+        "
+      ),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck("struct P { pub struct Q { pub fn me(): Self self; }; fn f(q: Q): P q.me(); };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Named(vec!["P".to_string()], vec![]),
+        found: Type::Named(vec!["Q".to_string()], vec![])
+      }])
+    );
+
+    assert_eq!(
+      typecheck("trait Clone { fn clone(): Self; }; struct X: Clone { fn clone(): X self; };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("trait Clone { fn clone(): Self; }; struct X: Clone { fn clone(): bool true; };"),
+      Err(vec![TypecheckerError::UnimplementedTraitMethod {
+        r#trait: "Clone".to_string(),
+        method: "clone".to_string()
+      }])
+    );
+  }
+
+  #[test]
+  fn test_path() {
+    let shapes = r#"
+enum Shape { Circle(f64), Empty };
+
+struct Canvas {
+  pub static fn new(): Canvas new();
+  static fn secret(): i32 0;
+  pub static fn hidden(): i32 Canvas::Hidden::new();
+
+  pub enum Color { Red, Rgb(u8, u8, u8) };
+  struct Hidden { pub static fn new(): i32 0; };
+};
+"#;
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Shape Shape::Circle(1.0); fn g(): Shape Shape::Empty; fn h(): Canvas Canvas::new();",
+        shapes
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Canvas::Color Canvas::Color::Rgb(1u8, 2u8, 3u8);",
+        shapes
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Shape Shape::Square;", shapes)),
+      Err(vec![TypecheckerError::UnresolvedPath {
+        path: "Shape::Square".to_string(),
+        segment: "Square".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Shape Canvas::Shade::Red;", shapes)),
+      Err(vec![TypecheckerError::UnresolvedPath {
+        path: "Canvas::Shade::Red".to_string(),
+        segment: "Shade".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Shape Easel::Red;", shapes)),
+      Err(vec![TypecheckerError::UnresolvedPath {
+        path: "Easel::Red".to_string(),
+        segment: "Easel".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): i32 Canvas::secret();", shapes)),
+      Err(vec![TypecheckerError::PrivateItem {
+        name: "Canvas::secret".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): i32 Canvas::Hidden::new();", shapes)),
+      Err(vec![TypecheckerError::PrivateItem {
+        name: "Canvas::Hidden".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(h: Canvas::Hidden): i32 0;", shapes)),
+      Err(vec![TypecheckerError::PrivateItem {
+        name: "Canvas::Hidden".to_string()
+      }])
+    );
+  }
+
+  #[test]
+  fn test_number_suffix() {
+    for (source, ty) in [
+      ("1", Type::I32),
+      ("1.0", Type::F64),
+      ("1i8", Type::I8),
+      ("1i16", Type::I16),
+      ("1i32", Type::I32),
+      ("10i64", Type::I64),
+      ("1i128", Type::I128),
+      ("42u8", Type::U8),
+      ("1u16", Type::U16),
+      ("1u32", Type::U32),
+      ("1u64", Type::U64),
+      ("1u128", Type::U128),
+      ("3.0f32", Type::F32),
+      ("1f32", Type::F32),
+      ("3.0f64", Type::F64),
+    ] {
+      assert_eq!(typecheck_expression(source), Ok(ty), "{}", source);
+    }
+
+    assert_eq!(
+      typecheck("fn f(): i32 1f32;"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::I32,
+        found: Type::F32
+      }])
+    );
+  }
+
+  #[test]
+  fn test_captures() {
+    let captured = |typechecker: &Typechecker| -> Vec<Vec<String>> {
+      typechecker
+        .captures
+        .iter()
+        .map(|captures| captures.iter().map(|(_, name)| name.clone()).collect())
+        .collect()
+    };
+
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str(
+        r#"
+fn main(a: i32): i32 {
+  let b = 1;
+  let f = fn(c: i32): i32 {
+    let d = c;
+    let g = fn(): i32 a + b + d;
+    b + g()
+  };
+  f(a)
+};
+"#
+      ),
+      Ok(())
+    );
+    // parameters and bindings inside a closure aren't captures, but they are for the closures in it
+    assert_eq!(
+      captured(&typechecker),
+      vec![
+        vec!["a".to_string(), "b".to_string()],
+        vec!["a".to_string(), "b".to_string(), "d".to_string()],
+      ]
+    );
+
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str("fn main() { let f = fn(): i32 x; f() };"),
+      Err(CompileError::Typechecker(vec![Error(
+        30..31,
+        TypecheckerError::UnresolvedIdentifier("x".to_string())
+      )]))
+    );
+  }
+
+  #[test]
+  fn test_closure_inference() {
+    let source = "
+      fn map(xs: [i32], f: fn(i32): i32): [i32] xs;
+      fn main(): [i32] map([1, 2], fn(x) x + 1);
+    ";
+    assert_eq!(typecheck(source), Ok(()));
+    assert_eq!(Typechecker::new().typecheck_str(source), Ok(()));
+
+    // named arguments and annotations that agree are fine too
+    assert_eq!(
+      typecheck(
+        "
+        fn map(xs: [i32], f: fn(i32, bool): i32): [i32] xs;
+        fn main(): [i32] map(f: fn(x: i32, y) x, xs: [1]);
+        "
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(
+        "
+        fn map(xs: [i32], f: fn(i32): i32): [i32] xs;
+        fn main(): [i32] map([1, 2], fn(x: char) 1);
+        "
+      ),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::I32,
+        found: Type::Char
+      }])
+    );
+
+    // a closure has to accept everything it could be given, so its parameters can be wider than
+    // expected but not narrower
+    assert_eq!(
+      typecheck(
+        "
+        fn apply(f: fn(i32 | bool): i32): i32 f(true);
+        fn main(): i32 apply(fn(x: i32) x + 1);
+        "
+      ),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::union([Type::I32, Type::Bool]),
+        found: Type::I32
+      }])
+    );
+    assert_eq!(
+      typecheck(
+        "
+        fn apply(f: fn(i32): i32): i32 f(1);
+        fn main(): i32 apply(fn(x: i32 | bool) 1);
+        "
+      ),
+      Ok(())
+    );
+
+    // the inferred type is used in the body
+    assert_eq!(
+      typecheck(
+        "
+        fn map(xs: [i32], f: fn(i32): char): [i32] xs;
+        fn main(): [i32] map([1, 2], fn(x) x);
+        "
+      ),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![
+          Type::Array(Box::new(Type::I32)),
+          Type::Function(vec![Type::I32], Box::new(Type::Char))
+        ],
+        found: vec![
+          Type::Array(Box::new(Type::I32)),
+          Type::Function(vec![Type::I32], Box::new(Type::I32))
+        ]
+      }])
+    );
+
+    // without a function parameter to go by, there's nothing to infer from
+    assert_eq!(
+      typecheck("fn main(): () { let f = fn(x) x; };"),
+      Err(vec![TypecheckerError::CannotInferParameter {
+        name: "x".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(
+        "
+        fn apply(f: fn(i32): i32): i32 f(1);
+        fn main(): i32 apply(fn(x, y) x);
+        "
+      ),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::Function(vec![Type::I32], Box::new(Type::I32))],
+        found: vec![Type::Function(
+          vec![Type::Infer(2), Type::Infer(3)],
+          Box::new(Type::Infer(2))
+        )]
+      }])
+    );
+  }
+
+  #[test]
+  fn test_default_parameters() {
+    let f = "fn f(x: i32, y: i32 = 0, z: bool = true): i32 x; struct S { pub static fn g(x: i32 = 1): i32 x; };";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): i32 {{ f(1); f(1, 2); f(1, 2, false); S::g(); S::g(2) }};",
+        f
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): i32 f();", f)),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::I32, Type::I32, Type::Bool],
+        found: vec![]
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): i32 f(1, 2, true, 3);", f)),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::I32, Type::I32, Type::Bool],
+        found: vec![Type::I32, Type::I32, Type::Bool, Type::I32]
+      }])
+    );
+
+    // a default before a parameter without one can never be used
+    assert_eq!(
+      typecheck("fn f(x: i32 = 0, y: i32): i32 x; fn main(): i32 f(1);"),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::I32, Type::I32],
+        found: vec![Type::I32]
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(x: i32 = 'a'): i32 x;"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::I32,
+        found: Type::Char
+      }])
+    );
+  }
+
+  #[test]
+  fn test_rest_parameter() {
+    let f = "fn f(x: bool, ys: i32...): [i32] ys;";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): [i32] {{ f(true); f(true, 1); f(true, 1, 2, 3); f(x: true, ys: [1, 2]) }};",
+        f
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): [i32] f(true, 1, 'a');", f)),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::Bool, Type::Array(Box::new(Type::I32))],
+        found: vec![Type::Bool, Type::I32, Type::Char]
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): [i32] f(true, ys: [1], 2);", f)),
+      Err(vec![TypecheckerError::DuplicateArgument {
+        name: "ys".to_string()
+      }])
+    );
+
+    // the rest parameter is required when the function is used as a value
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): [i32] {{ let g = f; g(true, [1]) }};",
+        f
+      )),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_named_arguments() {
+    let f = "fn f(x: i32, y: bool = true, z: char = 'a'): i32 x;";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): i32 {{ f(x: 1); f(1, z: 'b'); f(z: 'b', x: 1, y: false); f(1, true, z: 'c') }};",
+        f
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): i32 f(1, w: 2);", f)),
+      Err(vec![TypecheckerError::UnknownArgument {
+        name: "w".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): i32 f(1, x: 2);", f)),
+      Err(vec![TypecheckerError::DuplicateArgument {
+        name: "x".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): i32 f(y: false);", f)),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::I32, Type::Bool, Type::Char],
+        found: vec![Type::Bool]
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): i32 f(1, z: true);", f)),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::I32, Type::Bool, Type::Char],
+        found: vec![Type::I32, Type::Bool]
+      }])
+    );
+
+    // a closure's type doesn't have its parameters' names
+    assert_eq!(
+      typecheck("fn main(): i32 { let g = fn(x: i32): i32 x; g(x: 1) };"),
+      Err(vec![TypecheckerError::UnknownArgument {
+        name: "x".to_string()
+      }])
+    );
+  }
+
+  #[test]
+  fn test_mutability() {
+    assert_eq!(
+      typecheck("fn f(): i32 { let mut x = 1; x = x + 1; x };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 { let mut (a, b) = (1, 2); b = a; b };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 { let x = 1; x = 2; x };"),
+      Err(vec![TypecheckerError::AssignToImmutable {
+        name: "x".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 { let mut x = 1; x = 'a'; x };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::I32,
+        found: Type::Char
+      }])
+    );
+
+    // elements of an immutable array can't be assigned to either
+    assert_eq!(
+      typecheck("fn f(xs: [i32]): () xs[0] = 1;"),
+      Err(vec![TypecheckerError::AssignToImmutable {
+        name: "xs".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): () { let mut xs = [1, 2]; xs[0] = 3 };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("const N: i32 = 1; fn f(): () N = 2;"),
+      Err(vec![TypecheckerError::AssignToImmutable {
+        name: "N".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): () 1 = 2;"),
+      Err(vec![TypecheckerError::InvalidAssignmentTarget])
+    );
+  }
+
+  #[test]
+  fn test_compound_assignment() {
+    assert_eq!(
+      typecheck("fn f(): i32 { let mut x: i32 = 1; x += 2; x };"),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck("fn f(): u8 { let mut x = 1u8; x <<= 2; x *= 3; x };"),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck("fn f(): i64 { let mut x = -8i64; x >>>= 1u8; x };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 { let x = 1; x += 2; x };"),
+      Err(vec![TypecheckerError::AssignToImmutable {
+        name: "x".to_string()
+      }])
+    );
+    assert_eq!(
+      typecheck("fn f(): () { let mut x = true; x -= false };"),
+      Err(vec![TypecheckerError::InvalidOperands {
+        operator: "-".to_string(),
+        left: Type::Bool,
+        right: Type::Bool
+      }])
+    );
+    assert_eq!(
+      typecheck("fn f(): () { let mut x = 1; x += 'a' };"),
+      Err(vec![TypecheckerError::InvalidOperands {
+        operator: "+".to_string(),
+        left: Type::I32,
+        right: Type::Char
+      }])
+    );
+
+    // operators without a built-in meaning are errors rather than crashes
+    assert_eq!(
+      typecheck_expression("1 -> 2"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "->".to_string(),
+        left: Type::I32,
+        right: Type::I32
+      })
+    );
+  }
+
+  #[test]
+  fn test_destructuring() {
+    assert_eq!(
+      typecheck("fn f(pair: (i32, char)): char { let (a, b) = pair; let c: i32 = a; b };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(
+        "fn f(): bool { let (a, (b, c)): (i32, (char, bool)) = (1, ('a', true)); let d: (i32, char) = (a, b); c };"
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 { let (a, b, c) = (1, 'a'); a };"),
+      Err(vec![TypecheckerError::InvalidTuplePattern {
+        elements: 3,
+        found: Type::Tuple(vec![Type::I32, Type::Char])
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 { let (a, b) = 1; a };"),
+      Err(vec![TypecheckerError::InvalidTuplePattern {
+        elements: 2,
+        found: Type::I32
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 { let (a, 1) = (1, 1); a };"),
+      Err(vec![TypecheckerError::RefutablePattern])
+    );
+
+    assert_eq!(
+      typecheck("fn f(pairs: [(i32, char)]) { for (n, _) in pairs { let m: i32 = n; }; };"),
+      Ok(())
+    );
+
+    // match arms can destructure too, with literals
+    assert_eq!(
+      typecheck("fn f(x: (i32, char)): char match x { (0, c) => c, (n, _c) => 'z' };"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_wildcard() {
+    assert_eq!(
+      typecheck("fn f(pair: (i32, char)): char { let (_, b) = pair; b };"),
+      Ok(())
+    );
+
+    let scope = Rc::new(RefCell::new(Scope::default()));
+    let pattern = ast::util::Pattern::Tuple(vec![
+      ast::util::Pattern::Wildcard,
+      ast::util::Pattern::Identifier("b".to_string()),
+    ]);
+    assert_eq!(
+      Typechecker::new().bind(
+        scope.clone(),
+        pattern,
+        Type::Tuple(vec![Type::I32, Type::Char]),
+        false
+      ),
+      Ok(())
+    );
+    assert_eq!(
+      scope.borrow().items.keys().collect::<Vec<_>>(),
+      vec![&"b".to_string()]
+    );
+
+    assert_eq!(
+      typecheck("fn f(x: i32): char match x { 1 => 'a', _ => 'b' };"),
+      Ok(())
+    );
+
+    assert!(matches!(
+      Typechecker::new().typecheck_str("fn f(): i32 { let _ = 1; _ };"),
+      Err(CompileError::Parser(Error(
+        _,
+        ParserError::WildcardExpression
+      )))
+    ));
+  }
+
+  #[test]
+  fn test_exhaustiveness() {
+    let shape = "enum Shape { Circle(f64), Rectangle(f64, f64), Empty };";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn area(s: Shape): f64 match s {{ Circle(r) => r * r, Shape::Rectangle(w, h) => w * h, Empty => 0.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn area(s: Shape): f64 match s {{ Circle(r) => r, _ => 0.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+
+    // a literal in a field only covers some circles
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn area(s: Shape): f64 match s {{ Circle(1.0) => 1.0, Empty => 0.0 }};",
+        shape
+      )),
+      Err(vec![TypecheckerError::NonExhaustiveMatch {
+        missing: vec!["Circle".to_string(), "Rectangle".to_string()]
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn area(s: Shape): f64 match s {{ Circle(_, _) => 1.0, _ => 0.0 }};",
+        shape
+      )),
+      Err(vec![TypecheckerError::InvalidVariantPattern {
+        variant: "Circle".to_string(),
+        expected: 1,
+        found: 2
+      }])
+    );
+
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str(&format!(
+        "{} fn area(s: Shape): f64 match s {{ _ => 0.0, Empty => 1.0, Empty => 2.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+    assert_eq!(
+      typechecker.warnings,
+      vec![
+        TypecheckerWarning::UnreachableArm,
+        TypecheckerWarning::UnreachableArm
+      ]
+    );
+
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str(&format!(
+        "{} fn area(s: Shape): f64 match s {{ Empty => 1.0, Empty => 2.0, _ => 0.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+    assert_eq!(
+      typechecker.warnings,
+      vec![TypecheckerWarning::UnreachableArm]
+    );
+  }
+
+  #[test]
+  fn test_tuple_index() {
+    assert_eq!(typecheck_expression("('a', (true, 1)).0"), Ok(Type::Char));
+
+    assert_eq!(typecheck_expression("('a', (true, 1)).1.1"), Ok(Type::I32));
+
+    assert_eq!(
+      typecheck_expression("('a', true).2"),
+      Err(TypecheckerError::TupleIndexOutOfBounds {
+        found: Type::Tuple(vec![Type::Char, Type::Bool]),
+        index: 2
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("true.0"),
+      Err(TypecheckerError::TupleIndexOutOfBounds {
+        found: Type::Bool,
+        index: 0
+      })
+    );
+  }
+
+  #[test]
+  fn test_reference() {
+    assert_eq!(typecheck("fn f(x: i32): &i32 &x;"), Ok(()));
+    assert_eq!(typecheck("fn f(x: i32): i32 *&x;"), Ok(()));
+    assert_eq!(
+      typecheck_expression("&&mut 1"),
+      Ok(Type::Reference(
+        Box::new(Type::Reference(Box::new(Type::I32), true)),
+        false
+      ))
+    );
+
+    assert_eq!(
+      typecheck_expression("*1"),
+      Err(TypecheckerError::NotDereferenceable { found: Type::I32 })
+    );
+    assert_eq!(typecheck("fn f(r: &&i32): i32 **r;"), Ok(()));
+
+    // a mutable reference can be written through, and used where an immutable one is expected
+    assert_eq!(
+      typecheck("fn f(): i32 { let mut x = 1; let r = &mut x; *r = 2; x };"),
+      Ok(())
+    );
+    assert_eq!(typecheck("fn f(r: &mut i32): () *r = 1;"), Ok(()));
+    assert_eq!(typecheck("fn f(r: &mut i32): &i32 r;"), Ok(()));
+    assert_eq!(
+      typecheck("fn f(r: &i32): &mut i32 r;"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Reference(Box::new(Type::I32), true),
+        found: Type::Reference(Box::new(Type::I32), false)
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(r: &i32): () *r = 1;"),
+      Err(vec![TypecheckerError::ImmutableReference])
+    );
+    assert_eq!(
+      typecheck("fn f(xs: [i32]): () { let r = &mut xs[0]; };"),
+      Err(vec![TypecheckerError::MutableBorrowOfImmutable {
+        name: "xs".to_string()
+      }])
+    );
+    assert_eq!(
+      typecheck("fn f(r: &i32): () { let s = &mut *r; };"),
+      Err(vec![TypecheckerError::ImmutableReference])
+    );
+  }
+
+  #[test]
+  fn test_cast() {
+    assert_eq!(typecheck_expression("'a' as u32"), Ok(Type::U32));
+
+    assert_eq!(typecheck_expression("1 as f64"), Ok(Type::F64));
+
+    assert_eq!(typecheck_expression("65u8 as char"), Ok(Type::Char));
+
+    assert_eq!(
+      typecheck_expression("true as char"),
+      Err(TypecheckerError::InvalidCast {
+        from: Type::Bool,
+        to: Type::Char
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("1.5 as char"),
+      Err(TypecheckerError::InvalidCast {
+        from: Type::F64,
+        to: Type::Char
+      })
+    );
+  }
+
+  #[test]
+  fn test_comparison() {
+    assert_eq!(typecheck_expression("1 < 2"), Ok(Type::Bool));
+
+    assert_eq!(typecheck_expression("1.5 >= 2.0"), Ok(Type::Bool));
+
+    assert_eq!(typecheck_expression("'a' == 'b'"), Ok(Type::Bool));
+
+    assert_eq!(typecheck_expression("true != false"), Ok(Type::Bool));
+
+    assert_eq!(
+      typecheck_expression("1 < true"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "<".to_string(),
+        left: Type::I32,
+        right: Type::Bool
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("1 == 1u8"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "==".to_string(),
+        left: Type::I32,
+        right: Type::U8
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("'a' < 'b'"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "<".to_string(),
+        left: Type::Char,
+        right: Type::Char
+      })
+    );
+  }
+
+  #[test]
+  fn test_arithmetic() {
+    assert_eq!(typecheck_expression("1 + 2"), Ok(Type::I32));
+
+    assert_eq!(typecheck_expression("1.0 * 2.0"), Ok(Type::F64));
+
+    assert_eq!(typecheck_expression("1u8 - 2u8 % 3u8"), Ok(Type::U8));
+
+    assert_eq!(
+      typecheck_expression("1 + true"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "+".to_string(),
+        left: Type::I32,
+        right: Type::Bool
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression(r#""a" + "b""#),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "+".to_string(),
+        left: Type::Array(Box::new(Type::Char)),
+        right: Type::Array(Box::new(Type::Char))
+      })
+    );
+
+    let mut typechecker = Typechecker::new();
+    typechecker
+      .typecheck_str("fn f(x: i32): i32 x / 0;")
+      .unwrap();
+    assert_eq!(
+      typechecker.warnings,
+      vec![TypecheckerWarning::DivisionByZero]
+    );
+  }
+
+  #[test]
+  fn test_symbol_at() {
+    let source = "fn f(): char { let x = 1; let y = x; let x = 'a'; y + 1; x };";
+    let mut typechecker = Typechecker::new();
+    typechecker.typecheck_str(source).unwrap();
+
+    let at = |pattern: &str| {
+      let offset = source.find(pattern).unwrap();
+      typechecker.symbol_at(offset).cloned()
+    };
+
+    let first = at("x;").unwrap();
+    assert_eq!(first.1, ItemKind::Variable(Type::I32, false));
+    assert_eq!(at("y + 1").unwrap().1, ItemKind::Variable(Type::I32, false));
+
+    // the last `x` refers to the one that shadows the first
+    let second = at("x }").unwrap();
+    assert_eq!(second.1, ItemKind::Variable(Type::Char, false));
+    assert_ne!(first.0, second.0);
+
+    assert_eq!(at(" = 1"), None);
+    assert_eq!(at("let"), None);
+
+    let f = "fn f(): i32 g() + g(); fn g(): i32 1;";
+    let mut typechecker = Typechecker::new();
+    typechecker.typecheck_str(f).unwrap();
+    let g = typechecker.symbol_at(f.find("g()").unwrap()).unwrap();
+    assert!(matches!(g, Item(_, ItemKind::Function(_))));
+    assert_eq!(typechecker.symbol_at(f.find("+ g()").unwrap() + 2), Some(g));
+
+    // offsets are in bytes, so characters longer than one don't shift the ones after them
+    let source = "fn f(): char { let s = \"é→🦀\"; let c = '→'; let d = c; d };";
+    let mut typechecker = Typechecker::new();
+    typechecker.typecheck_str(source).unwrap();
+    let c = typechecker.symbol_at(source.find("c; d").unwrap()).unwrap();
+    assert_eq!(c.1, ItemKind::Variable(Type::Char, false));
+    let d = typechecker.symbol_at(source.find("d }").unwrap()).unwrap();
+    assert_ne!(c.0, d.0);
+    assert_eq!(typechecker.symbol_at(source.find(" }").unwrap()), None);
+  }
+
+  #[test]
+  fn test_type_at() {
+    let source = "fn f(): (i32, char) (1 + 2, 'c');";
+    let mut typechecker = Typechecker::new();
+    typechecker.typecheck_str(source).unwrap();
+
+    let at = |pattern: &str| typechecker.type_at(source.find(pattern).unwrap());
+
+    assert_eq!(at("+"), Some(Type::I32));
+    assert_eq!(at("2, '"), Some(Type::I32));
+    assert_eq!(at("'c'"), Some(Type::Char));
+    assert_eq!(at(", '"), Some(Type::Tuple(vec![Type::I32, Type::Char])));
+    assert_eq!(at("fn"), None);
+
+    // offsets are in bytes, so characters longer than one don't shift the ones after them
+    let source = "fn f(): (char, i32) { let s = \"é→🦀\"; ('→', 1 + 2) };";
+    let mut typechecker = Typechecker::new();
+    typechecker.typecheck_str(source).unwrap();
+
+    let at = |pattern: &str| typechecker.type_at(source.find(pattern).unwrap());
+
+    assert_eq!(at("'→'"), Some(Type::Char));
+    assert_eq!(at("2) }"), Some(Type::I32));
+    assert_eq!(at(", 1"), Some(Type::Tuple(vec![Type::Char, Type::I32])));
+  }
+
+  #[test]
+  fn test_const() {
+    assert_eq!(
+      typecheck(
+        "const N: i32 = 2 + 1; const M: i32 = N * 2; fn f(): [i32; M] [1, 2, 3, 4, 5, 6]; fn g(): i32 N;"
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): i32 1; const N: i32 = f();"),
+      Err(vec![TypecheckerError::NonConstantValue {
+        name: "N".to_string()
+      }])
+    );
+
+    assert_eq!(typecheck("const N: u8 = 1;"), Ok(()));
+
+    assert_eq!(
+      typecheck("const N: u8 = 1i64;"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::U8,
+        found: Type::I64
+      }])
+    );
+
+    assert_eq!(
+      typecheck("const N: i32 = 3; fn f(): [i32; N] [1, 2];"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::FixedArray(Box::new(Type::I32), 3),
+        found: Type::FixedArray(Box::new(Type::I32), 2)
+      }])
+    );
+
+    let mut typechecker = Typechecker::new();
+    typechecker
+      .typecheck_str("const Z: i32 = 1 - 1; fn f(x: i32): i32 x / Z;")
+      .unwrap();
+    assert_eq!(
+      typechecker.warnings,
+      vec![TypecheckerWarning::DivisionByZero]
+    );
+  }
+
+  #[test]
+  fn test_const_eval() {
+    let eval = |source: &str| {
+      let tokens = Lexer::new(source).lex(false).unwrap();
+      let expression = Parser::new(tokens.iter()).parse_expression().unwrap();
+      expression.const_eval()
+    };
+
+    assert_eq!(eval("2 + 3 * 4"), Some(14));
+    assert_eq!(eval("(2 + 3) * 4"), Some(20));
+    assert_eq!(eval("-7 / 2 % 3"), Some(0));
+    assert_eq!(eval("1 << 4 | 0xf ^ 3"), Some(28));
+    assert_eq!(eval("~0u8"), Some(255));
+    assert_eq!(eval("~0i8"), Some(-1));
+    assert_eq!(eval("~1u16 & 0xff"), Some(254));
+    assert_eq!(eval("~0u128"), None);
+    assert_eq!(eval("0u8 - 1u8"), None);
+    assert_eq!(eval("200u8 + 100u8"), None);
+    assert_eq!(eval("1u8 << 8"), None);
+
+    assert_eq!(eval("x + 1"), None);
+    assert_eq!(eval("1.5 * 2.0"), None);
+    assert_eq!(eval("1 / (2 - 2)"), None);
+    assert_eq!(eval("1 << 200"), None);
+    assert_eq!(
+      eval("170141183460469231731687303715884105727i128 + 1i128"),
+      None
+    );
+
+    let mut typechecker = Typechecker::new();
+    typechecker
+      .typecheck_str("fn f(x: i32): i32 x % (3 - 3);")
+      .unwrap();
+    assert_eq!(
+      typechecker.warnings,
+      vec![TypecheckerWarning::DivisionByZero]
+    );
+  }
+
+  #[test]
+  fn test_logical() {
+    assert_eq!(typecheck_expression("true && false"), Ok(Type::Bool));
+
+    assert_eq!(typecheck_expression("1 < 2 || !true"), Ok(Type::Bool));
+
+    assert_eq!(typecheck_expression("!true"), Ok(Type::Bool));
+
+    assert_eq!(typecheck_expression("-1.5"), Ok(Type::F64));
+
+    assert_eq!(typecheck_expression("~1u8"), Ok(Type::U8));
+
+    assert_eq!(
+      typecheck_expression("true && 1"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "&&".to_string(),
+        left: Type::Bool,
+        right: Type::I32
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("!1"),
+      Err(TypecheckerError::InvalidOperand {
+        operator: "!".to_string(),
+        found: Type::I32
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("-'a'"),
+      Err(TypecheckerError::InvalidOperand {
+        operator: "-".to_string(),
+        found: Type::Char
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("-1u8"),
+      Err(TypecheckerError::InvalidOperand {
+        operator: "-".to_string(),
+        found: Type::U8
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("~1.0"),
+      Err(TypecheckerError::InvalidOperand {
+        operator: "~".to_string(),
+        found: Type::F64
+      })
+    );
+  }
+
+  #[test]
+  fn test_range() {
+    assert_eq!(
+      typecheck_expression("0..10"),
+      Ok(Type::Array(Box::new(Type::I32)))
+    );
+
+    assert_eq!(
+      typecheck_expression("0u8..=255u8"),
+      Ok(Type::Array(Box::new(Type::U8)))
+    );
+
+    assert_eq!(
+      typecheck("fn f(n: u64): () for i in 0u64..n { i; };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck_expression("0..'a'"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "..".to_string(),
+        left: Type::I32,
+        right: Type::Char
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("0.0..1.0"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "..".to_string(),
+        left: Type::F64,
+        right: Type::F64
+      })
+    );
+  }
+
+  #[test]
+  fn test_index() {
+    assert_eq!(typecheck_expression("['a', 'b'][0]"), Ok(Type::Char));
+
+    assert_eq!(typecheck("fn f(arr: [bool], i: u8): bool arr[i];"), Ok(()));
+
+    assert_eq!(
+      typecheck("fn f(arr: [bool]): bool arr['a'];"),
+      Err(vec![TypecheckerError::InvalidIndex { found: Type::Char }])
+    );
+
+    assert_eq!(
+      typecheck_expression("[1][-1]"),
+      Err(TypecheckerError::NegativeIndex)
+    );
+
+    assert_eq!(
+      typecheck_expression("[1][1 - 2 * 3]"),
+      Err(TypecheckerError::NegativeIndex)
+    );
+
+    assert_eq!(typecheck_expression("[1][-1 + 1]"), Ok(Type::I32));
+
+    // unsigned indexes are folded in their own type, so they're never negative
+    assert_eq!(typecheck_expression("[1][~0u8]"), Ok(Type::I32));
+    assert_eq!(typecheck_expression("[1][0u8 - 1u8]"), Ok(Type::I32));
+
+    assert_eq!(
+      typecheck_expression("true[0]"),
+      Err(TypecheckerError::NotIndexable { found: Type::Bool })
+    );
+  }
+
+  #[test]
+  fn test_let() {
+    assert_eq!(typecheck_expression("{ let x = 'a'; x }"), Ok(Type::Char));
+
+    assert_eq!(
+      typecheck_expression("{ let x: char | bool = 'a'; x }"),
+      Ok(union!(Type::Char, Type::Bool))
+    );
+
+    assert_eq!(
+      typecheck_expression("{ let x: char | bool = if true 'a' else false; x }"),
+      Ok(union!(Type::Char, Type::Bool))
+    );
+
+    assert_eq!(
+      typecheck_expression("{ let x: char = true; x }"),
+      Err(TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::Bool
+      })
+    );
+
+    // bindings don't outlive their block
+    assert_eq!(
+      typecheck_expression("{ { let x = 'a' }; x }"),
+      Err(TypecheckerError::UnresolvedIdentifier("x".to_string()))
+    );
+
+    assert_eq!(typecheck_expression("let x = 'a'"), Ok(Type::Tuple(vec![])));
+  }
+
+  #[test]
+  fn test_while() {
+    assert_eq!(
+      typecheck_expression("while true { 'a' }"),
+      Ok(Type::Tuple(vec![]))
+    );
+
+    assert_eq!(
+      typecheck_expression("while 'a' true"),
+      Err(TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::Char
+      })
+    );
+  }
+
+  #[test]
+  fn test_return() {
+    assert_eq!(
+      typecheck("fn main(x: bool): char { if x return 'a' else 'b' };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn main(): char { return true; 'a' };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::Bool
+      }])
+    );
+
+    assert_eq!(typecheck("fn main(): () { return };"), Ok(()));
+
+    assert_eq!(
+      typecheck("fn main(): char { return; 'a' };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::Tuple(vec![])
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn main(c: bool): char if c { 'a' } else { return 'b' };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck_expression("if true { 'a' } else { return 'b' }"),
+      Ok(Type::Char)
+    );
+
+    assert_eq!(typecheck("fn main(): char loop {};"), Ok(()));
+
+    // returns inside a closure are checked against the closure
+    assert_eq!(
+      typecheck("fn main(): char { fn(): bool { return true }; 'a' };"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_impl() {
+    let point = "struct Point { x: i32; }; impl Point { pub fn getx(): i32 self.x; };";
+    assert_eq!(
+      typecheck(&format!("{} fn f(p: Point): i32 p.getx();", point)),
+      Ok(())
+    );
+
+    // methods from impl blocks can use the struct's private members and type parameters
+    assert_eq!(
+      typecheck(
+        "struct Box<T> { value: T; }; impl Box<T> { pub fn get(): T self.value; static fn swap(b: Self): T b.get(); }; fn f(b: Box<char>): char b.get();"
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} impl Point {{ pub fn getx(): i32 0; }};",
+        point
+      )),
+      Err(vec![TypecheckerError::DuplicateDefinition {
+        name: "getx".to_string(),
+        kind: "function"
+      }])
+    );
+
+    assert_eq!(
+      typecheck("struct A { pub struct B {}; }; impl A::B { fn f(): () (); }; impl C {};"),
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: "C".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("struct Box<T> {}; impl Box {};"),
+      Err(vec![TypecheckerError::InvalidTypeArguments {
+        name: "Box".to_string(),
+        expected: 1,
+        found: 0
+      }])
+    );
+
+    // the block's names for the type parameters stand for the struct's, whatever they're called
+    let boxed = "struct Box<T> { value: T; }; impl Box<U> { pub fn get(): U { let v: U = self.value; v }; pub fn me(): Self self; };";
+    assert_eq!(
+      typecheck(&format!("{} fn f(b: Box<char>): char b.get();", boxed)),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck(&format!("{} fn f(b: Box<char>): Box<char> b.me();", boxed)),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck(&format!("{} fn f(b: Box<char>): Box<i32> b.me();", boxed)),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Named(vec!["Box".to_string()], vec![Type::I32]),
+        found: Type::Named(vec!["Box".to_string()], vec![Type::Char])
+      }])
+    );
+  }
+
+  #[test]
+  fn test_block() {
+    assert_eq!(typecheck_expression("{ 'a' }"), Ok(Type::Char));
+    assert_eq!(typecheck_expression("{ 'a'; }"), Ok(Type::Tuple(vec![])));
+    assert_eq!(typecheck_expression("{ true; 'a' }"), Ok(Type::Char));
+    assert_eq!(typecheck_expression("{}"), Ok(Type::Tuple(vec![])));
+
+    assert_eq!(
+      typecheck("fn f(): char { 'a'; };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::Tuple(vec![])
+      }])
+    );
+  }
+
+  #[test]
+  fn test_literal_range() {
+    assert_eq!(typecheck("fn f(): u8 { let x: u8 = 255; x };"), Ok(()));
+    assert_eq!(typecheck("fn f(): i8 -128;"), Ok(()));
+
+    assert_eq!(
+      typecheck("fn f(): u8 { let x: u8 = 256; x };"),
+      Err(vec![TypecheckerError::LiteralOutOfRange {
+        value: 256,
+        ty: Type::U8
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): u8 { return -1 };"),
+      Err(vec![TypecheckerError::LiteralOutOfRange {
+        value: -1,
+        ty: Type::U8
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(x: u8): u8 x; fn main(): u8 f(1000);"),
+      Err(vec![TypecheckerError::LiteralOutOfRange {
+        value: 1000,
+        ty: Type::U8
+      }])
+    );
+
+    // without an expected type, a literal is an i32
+    assert_eq!(typecheck_expression("{ let x = 256; x }"), Ok(Type::I32));
+    assert_eq!(
+      typecheck_expression("{ let x = 5000000000; x }"),
+      Err(TypecheckerError::LiteralOutOfRange {
+        value: 5000000000,
+        ty: Type::I32
+      })
+    );
+
+    // an unsuffixed literal is only checked against the type it's used as
+    assert_eq!(
+      typecheck("fn f(): u64 { let x: u64 = 5000000000; x };"),
+      Ok(())
+    );
+    assert_eq!(typecheck("fn f(): i32 -2147483648;"), Ok(()));
+
+    // while a suffixed one keeps its own type
+    assert_eq!(
+      typecheck("fn f(): u8 { let x: u8 = 1i32; x };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::U8,
+        found: Type::I32
+      }])
+    );
+  }
+
+  #[test]
+  fn test_expected_literal() {
+    // the expected type reaches literals in the value of a block, in branches and in arrays
+    assert_eq!(typecheck("fn f(): u8 { 200 };"), Ok(()));
+    assert_eq!(
+      typecheck("fn f(): u8 { if true { 1 } else { 2 } };"),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck("fn f(x: bool): u8 match x { true => 1, false => { 2 } };"),
+      Ok(())
+    );
+    assert_eq!(typecheck("fn f(): [u8] { [1, 2] };"), Ok(()));
+    assert_eq!(typecheck("fn f(): [u8; 2] [{ 1 }, 255];"), Ok(()));
+    assert_eq!(typecheck("const C: u8 = 200;"), Ok(()));
+
+    assert_eq!(
+      typecheck("fn f(): u8 { if true { 1 } else { 256 } };"),
+      Err(vec![TypecheckerError::LiteralOutOfRange {
+        value: 256,
+        ty: Type::U8
+      }])
+    );
+    assert_eq!(
+      typecheck("fn f(): [u8] [1, -1];"),
+      Err(vec![TypecheckerError::LiteralOutOfRange {
+        value: -1,
+        ty: Type::U8
+      }])
+    );
+
+    // only the value of a block is expected to have the type
+    assert_eq!(typecheck("fn f(): u8 { let x: i32 = 1; 2 };"), Ok(()));
+  }
+
+  #[test]
+  fn test_unreachable() {
+    let warnings = |source: &str| {
+      let mut typechecker = Typechecker::new();
+      typechecker.typecheck_str(source).unwrap();
+      typechecker.warnings
+    };
+
+    let source = "fn f(): i32 { return 1; let x = 2; x };";
+    let start = source.find("let").unwrap();
+    assert_eq!(
+      warnings(source),
+      vec![TypecheckerWarning::UnreachableCode {
+        span: Some(start..start + "let x = 2; x".len())
+      }]
+    );
+
+    assert_eq!(
+      warnings("fn f(x: bool): () while x { continue; (); };"),
+      vec![TypecheckerWarning::UnreachableCode { span: Some(38..40) }]
+    );
+
+    assert_eq!(warnings("fn f(): i32 { let x = 2; return x; };"), vec![]);
+    assert_eq!(warnings("fn f(): i32 { return 1 };"), vec![]);
+
+    // a block that diverges never produces a value, so it fits wherever one is expected
+    assert_eq!(
+      typecheck("fn f(x: bool): char { let c = if x { return 'a'; 'b'; } else { 'b' }; c };"),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_break() {
+    assert_eq!(
+      typecheck_expression("while true { if false break (); continue }"),
+      Ok(Type::Tuple(vec![]))
+    );
+
+    assert_eq!(
+      typecheck("fn main(chars: [char]): () for c in chars { if true break else continue };"),
+      Ok(())
+    );
+
+    // loops produce unit, so that's all they can break with
+    assert_eq!(
+      typecheck_expression("while true break 'a'"),
+      Err(TypecheckerError::InvalidType {
+        expected: Type::Tuple(vec![]),
+        found: Type::Char
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("break"),
+      Err(TypecheckerError::BreakOutsideLoop)
+    );
+
+    assert_eq!(
+      typecheck("fn main(): () continue;"),
+      Err(vec![TypecheckerError::BreakOutsideLoop])
+    );
+
+    // a closure can't break out of the loop it's defined in
+    assert_eq!(
+      typecheck_expression("while true fn() break"),
+      Err(TypecheckerError::BreakOutsideLoop)
+    );
+  }
+
+  #[test]
+  fn test_loop() {
+    assert_eq!(typecheck_expression("loop { break 'a' }"), Ok(Type::Char));
+
+    assert_eq!(
+      typecheck_expression("loop { if true break 'a'; break true }"),
+      Ok(union!(Type::Char, Type::Bool))
+    );
+
+    assert_eq!(typecheck_expression("loop {}"), Ok(Type::Never));
+
+    // breaks belong to the innermost loop
+    assert_eq!(
+      typecheck_expression("loop { while true break; break 1 }"),
+      Ok(Type::I32)
+    );
+  }
+
+  #[test]
+  fn test_for() {
+    assert_eq!(
+      typecheck(
+        r#"
+fn print(c: char): () ();
+fn main(chars: [char]): () for c in chars print(c);
+"#
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn main(): () for c in true c;"),
+      Err(vec![TypecheckerError::NotIterable(Type::Bool)])
+    );
+
+    assert_eq!(
+      typecheck("fn main(chars: [char]): char for c in chars c;"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::Tuple(vec![])
+      }])
+    );
+  }
+
+  #[test]
+  fn test_string() {
+    assert_eq!(typecheck(r#"fn main(): [char] "hello";"#), Ok(()));
+
+    assert_eq!(
+      typecheck(r#"fn main(): char "hello";"#),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::Array(Box::new(Type::Char))
+      }])
+    );
+  }
+
+  #[test]
+  fn test_array() {
+    assert_eq!(
+      typecheck_expression("[true, false]"),
+      Ok(Type::Array(Box::new(Type::Bool)))
+    );
+
+    // the length is only kept where it's asked for, so a variable can take an array of another
+    assert_eq!(
+      typecheck("fn f(): () { let mut v = [1]; v = [1, 2]; };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck_expression("[true, 'a']"),
+      Err(TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::Char
+      })
+    );
+
+    // the element type of an empty array is worked out from how it's used
+    assert_eq!(
+      typecheck_expression("[]"),
+      Ok(Type::Array(Box::new(Type::Infer(0))))
+    );
+  }
+
+  #[test]
+  fn test_fixed_array() {
+    assert_eq!(
+      typecheck("fn f(): [i32] { let a: [i32; 3] = [1, 2, 3]; let b: [i32; 1 + 2] = a; a };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): [i32; 3] [1, 2];"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::FixedArray(Box::new(Type::I32), 3),
+        found: Type::FixedArray(Box::new(Type::I32), 2)
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(a: [i32]): [i32; 3] a;"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::FixedArray(Box::new(Type::I32), 3),
+        found: Type::Array(Box::new(Type::I32))
+      }])
+    );
+
+    assert_eq!(typecheck_expression("[1, 2][0]"), Ok(Type::I32));
+  }
+
+  #[test]
+  fn test_enum() {
+    assert_eq!(
+      typecheck(
+        r#"
+enum Shape {
+  Circle(f64),
+  Rectangle(f64, f64),
+  Empty
+};
+
+fn circle(): Shape Circle(1.0);
+fn empty(): Shape Empty;
+fn rectangle(): Shape Rectangle(1.0, 2.0);
+"#
+      ),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("enum Shape { Circle(f64), Circle(f32) };"),
+      Err(vec![TypecheckerError::DuplicateVariant(
+        "Circle".to_string()
+      )])
+    );
+
+    assert_eq!(
+      typecheck("enum Shape { Circle(Radius) };"),
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: "Radius".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("enum Shape { Circle(f64) }; fn circle(): Shape Circle('a');"),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::F64],
+        found: vec![Type::Char]
+      }])
+    );
+  }
+
+  #[test]
+  fn test_call() {
+    assert_eq!(
+      typecheck("fn f(x: bool): char 'a'; fn main(): char f(true);"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(x: u8): u8 x; fn main(): u8 f(1u8);"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(x: u8): u8 x; fn main(): u8 f(1u16);"),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::U8],
+        found: vec![Type::U16]
+      }])
+    );
+
+    assert_eq!(typecheck("fn f(x: u8): u8 x; fn main(): u8 f(1);"), Ok(()));
+
+    assert_eq!(
+      typecheck_expression("true()"),
+      Err(TypecheckerError::NotCallable { found: Type::Bool })
+    );
+  }
+
+  #[test]
+  fn test_named_variant() {
+    let shape = "enum Shape { Circle { r: f64 }, Rect { w: f64, h: f64 }, Empty };";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Shape Shape::Rect {{ h: 1.0, w: 2.0 }}; fn g(): Shape Circle {{ r: 1.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+
+    // matching binds the named fields, and the ones left out match anything
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn area(s: Shape): f64 match s {{ Circle {{ r }} => r * r, Rect {{ w, h: height }} => w * height, Rect {{}} => 0.0, Empty => 0.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(s: Shape): f64 match s {{ Circle {{ r }} => r, _ => 0.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Shape Rect {{ w: 1.0 }};", shape)),
+      Err(vec![TypecheckerError::MissingFields {
+        struct_name: "Rect".to_string(),
+        fields: vec!["h".to_string()]
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Shape Circle {{ r: true }};", shape)),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::F64,
+        found: Type::Bool
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): Shape Circle {{ r: 1.0, d: 2.0 }};",
+        shape
+      )),
+      Err(vec![TypecheckerError::UnknownField {
+        struct_name: "Circle".to_string(),
+        field: "d".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(s: Shape): f64 match s {{ Circle {{ d }} => d, _ => 0.0 }};",
+        shape
+      )),
+      Err(vec![TypecheckerError::UnknownField {
+        struct_name: "Circle".to_string(),
+        field: "d".to_string()
+      }])
+    );
+
+    // there's no positional constructor to call
+    assert_eq!(
+      typecheck(&format!("{} fn f(): Shape Circle(1.0);", shape)),
+      Err(vec![TypecheckerError::NamedVariantFields {
+        variant: "Circle".to_string()
+      }])
+    );
+
+    assert_eq!(
+      typecheck("enum E { V { x: i32, x: bool } };"),
+      Err(vec![TypecheckerError::DuplicateDefinition {
+        name: "x".to_string(),
+        kind: "field"
+      }])
+    );
+
+    // and no positional pattern to match
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(s: Shape): f64 match s {{ Circle(r) => r, _ => 0.0 }};",
+        shape
+      )),
+      Err(vec![TypecheckerError::NamedVariantFields {
+        variant: "Circle".to_string()
+      }])
+    );
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(s: Shape): f64 match s {{ Shape::Circle(r) => r, _ => 0.0 }};",
+        shape
+      )),
+      Err(vec![TypecheckerError::NamedVariantFields {
+        variant: "Circle".to_string()
+      }])
+    );
+
+    // while matching by name covers the variant, whichever fields it leaves out
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(s: Shape): f64 match s {{ Circle {{ r }} => r, Rect {{ w }} => w, Empty => 0.0 }};",
+        shape
+      )),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_match() {
+    assert_eq!(
+      typecheck_expression("match true { true => 'a', false => 1 }"),
+      Ok(union!(Type::Char, Type::I32))
+    );
+
+    assert_eq!(typecheck_expression("match 'a' { c => c }"), Ok(Type::Char));
+
+    assert_eq!(
+      typecheck_expression("match true { 'a' => 1 }"),
+      Err(TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::Char
+      })
+    );
+  }
+
+  #[test]
+  fn test_if_let() {
+    let option = "enum Option { Some(i32), None };";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(o: Option): i32 if let Some(x) = o {{ x }} else {{ 0 }};",
+        option
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(o: Option): () {{ let mut o = o; while let Option::Some(x) = o {{ o = if x > 0 Some(x - 1) else None }} }};",
+        option
+      )),
+      Ok(())
+    );
+
+    // the bindings are only in scope in the branch
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(o: Option): i32 {{ if let Some(x) = o {{ x }}; x }};",
+        option
+      )),
+      Err(vec![TypecheckerError::UnresolvedIdentifier(
+        "x".to_string()
+      )])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn f(): i32 if let Some(x) = 'a' {{ x }} else {{ 0 }};",
+        option
+      )),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::Named(vec!["Option".to_string()], vec![])
+      }])
+    );
+
+    assert_eq!(
+      typecheck_expression("if let (a, 1) = ('a', 2) { a }"),
+      Ok(union!(Type::Char, Type::Tuple(vec![])))
+    );
+  }
+
+  #[test]
+  fn test_inference() {
+    // an empty array's element type comes from where it's used later
+    assert_eq!(typecheck("fn f(): [i32] { let a = []; a };"), Ok(()));
+    assert_eq!(
+      typecheck("fn g(xs: [char]): () (); fn f(): () { let a = []; g(a) };"),
+      Ok(())
+    );
+
+    // and once it's known, it stays that way
+    assert_eq!(
+      typecheck("fn f(): () { let a = []; let b: [i32] = a; let c: [char] = a; };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Array(Box::new(Type::Char)),
+        found: Type::Array(Box::new(Type::I32))
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): () { let a = []; };"),
+      Err(vec![TypecheckerError::CannotInfer])
+    );
+
+    // a member of a union that doesn't fit leaves nothing solved for the next one to trip over
+    assert_eq!(
+      typecheck("fn f(): () { let c = fn(x) x; let y: (fn(i32): char) | fn(char): char = c; };"),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck("fn f(): () { let a = []; let y: (i32, char) | (char, bool) = (a[0], true); };"),
+      Ok(())
+    );
+
+    // a closure's parameters are solved by its body or its calls, and its return type follows
+    assert_eq!(
+      typecheck("fn f(): i32 { let id = fn(x) x; id(1) };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): char { let inc = fn(x) x + 1; inc(1) };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::I32
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): () { let inc = fn(x) x + 1; inc('a'); };"),
+      Err(vec![TypecheckerError::InvalidArguments {
+        expected: vec![Type::I32],
+        found: vec![Type::Char]
+      }])
+    );
+  }
+
+  #[test]
+  fn test_tuple_return() {
+    let divide = "fn divide(a: i32, b: i32): (i32, bool) (a / b, b != 0);";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): i32 {{ let (n, ok) = divide(7, 2); if ok n else 0 }};",
+        divide
+      )),
+      Ok(())
+    );
+
+    // the elements take their types from the declared return type
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): bool {{ let (n, _) = divide(7, 2); n }};",
+        divide
+      )),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::I32
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): () {{ let (n, ok, _) = divide(7, 2); }};",
+        divide
+      )),
+      Err(vec![TypecheckerError::InvalidTuplePattern {
+        elements: 3,
+        found: Type::Tuple(vec![Type::I32, Type::Bool])
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn main(): () {{ let (n, ok): (i32, char) = divide(7, 2); }};",
+        divide
+      )),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Tuple(vec![Type::I32, Type::Char]),
+        found: Type::Tuple(vec![Type::I32, Type::Bool])
+      }])
+    );
+  }
+
+  #[test]
+  fn test_try() {
+    let result = "
+      enum Parsed { Ok(i32), Err(char) };
+      enum Doubled { Ok(i32), Err(char) };
+      fn parse(s: [char]): Parsed Parsed::Ok(1);
+    ";
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn double(s: [char]): Doubled Doubled::Ok(parse(s)? * 2);",
+        result
+      )),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck(&format!(
+        "{} fn double(s: [char]): i32 parse(s)? * 2;",
+        result
+      )),
+      Err(vec![TypecheckerError::TryOutsideResult {
+        found: Some(Type::I32)
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn main(): () {{ parse(\"1\")?; }};", result)),
+      Err(vec![TypecheckerError::TryOutsideResult {
+        found: Some(Type::Tuple(vec![]))
+      }])
+    );
+
+    // the error has to fit the function's own
+    assert_eq!(
+      typecheck(&format!(
+        "{} enum Other {{ Ok(i32), Err(bool) }}; fn double(s: [char]): Other Other::Ok(parse(s)?);",
+        result
+      )),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Bool,
+        found: Type::Char
+      }])
+    );
+
+    assert_eq!(
+      typecheck(&format!("{} fn double(x: i32): Doubled x?;", result)),
+      Err(vec![TypecheckerError::NotTryable(Type::I32)])
+    );
+  }
+
+  #[test]
+  fn test_labels() {
+    // a labeled break leaves the outer loop, which produces its value
+    assert_eq!(
+      typecheck("fn f(): i32 'outer: loop { loop { break 'outer 1 } };"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(): char 'outer: loop { loop { break 'outer 1 }; break 'a' };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: union!(Type::I32, Type::Char)
+      }])
+    );
+
+    assert_eq!(
+      typecheck(
+        "fn f(): () 'outer: for i in [1, 2] { while true { if i == 2 { continue 'outer }; break 'outer } };"
+      ),
+      Ok(())
+    );
+
+    // `while` and `for` produce unit, even when they're broken out of from an inner loop
+    assert_eq!(
+      typecheck("fn f(): () 'outer: while true { loop { break 'outer 1 } };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Tuple(vec![]),
+        found: Type::I32
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(): () loop { break 'outer };"),
+      Err(vec![TypecheckerError::UndefinedLabel {
+        label: "outer".to_string()
+      }])
+    );
+
+    // a closure can't leave a loop outside of it
+    assert_eq!(
+      typecheck("fn f(): () 'a: loop { let g = fn(): () { continue 'a }; g(); break };"),
+      Err(vec![TypecheckerError::UndefinedLabel {
+        label: "a".to_string()
+      }])
+    );
+  }
+
+  #[test]
+  fn test_coalesce() {
+    assert_eq!(typecheck("fn f(x: char | ()): char x ?? 'z';"), Ok(()));
+
+    // the rest of the union is kept
+    assert_eq!(
+      typecheck("fn f(x: char | bool | ()): char | bool x ?? true;"),
+      Ok(())
+    );
+
+    assert_eq!(
+      typecheck("fn f(x: char | ()): char x ?? 1;"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: Type::Char,
+        found: Type::I32
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn f(x: char | bool): char x ?? 'z';"),
+      Err(vec![TypecheckerError::NotNullable(union!(
+        Type::Char,
+        Type::Bool
+      ))])
+    );
+
+    assert_eq!(
+      typecheck_expression("'a' ?? 'b'"),
+      Err(TypecheckerError::NotNullable(Type::Char))
+    );
+  }
+
+  #[test]
+  fn test_bitwise() {
+    assert_eq!(typecheck_expression("1 << 2"), Ok(Type::I32));
+    assert_eq!(typecheck_expression("0xF & 0x1"), Ok(Type::I32));
+    assert_eq!(typecheck_expression("1u8 | 2u8 ^ 3u8"), Ok(Type::U8));
+
+    // the shift amount doesn't have to have the same type as what's shifted
+    assert_eq!(typecheck_expression("1i64 >>> 2u8"), Ok(Type::I64));
+
+    assert_eq!(
+      typecheck_expression("1.0 << 2"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "<<".to_string(),
+        left: Type::F64,
+        right: Type::I32
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("1u8 & 2"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "&".to_string(),
+        left: Type::U8,
+        right: Type::I32
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("true | false"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "|".to_string(),
+        left: Type::Bool,
+        right: Type::Bool
+      })
+    );
+
+    assert_eq!(
+      typecheck_expression("'a' ^ 'b'"),
+      Err(TypecheckerError::InvalidOperands {
+        operator: "^".to_string(),
+        left: Type::Char,
+        right: Type::Char
+      })
+    );
+  }
+
+  #[test]
+  fn test_type_of_item() {
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str(
+        "
+          struct Pair<T> {
+            struct Inner {};
+            a: T;
+          };
+          enum Color { Red };
+          fn f(x: i32): bool true;
+        "
+      ),
+      Ok(())
+    );
+
+    let id = |path: &[&str]| {
+      typechecker.declarations[&path.iter().map(|s| s.to_string()).collect::<Vec<_>>()]
+    };
+
+    assert_eq!(
+      typechecker.type_of_item(id(&["Pair"])),
+      Some(Type::Named(
+        vec!["Pair".to_string()],
+        vec![Type::Named(vec!["T".to_string()], vec![])]
+      ))
+    );
+    assert_eq!(
+      typechecker.type_of_item(id(&["Pair", "Inner"])),
+      Some(Type::Named(
+        vec!["Pair".to_string(), "Inner".to_string()],
+        vec![]
+      ))
+    );
+    assert_eq!(
+      typechecker.type_of_item(id(&["Color"])),
+      Some(Type::Named(vec!["Color".to_string()], vec![]))
+    );
+
+    // functions aren't declared under a path, but they're still kept by id
+    let f = typechecker
+      .types
+      .values()
+      .find(|item| matches!(&item.1, ItemKind::Function(f) if f.header.name == "f"))
+      .unwrap()
+      .0;
+    assert_eq!(
+      typechecker.type_of_item(f),
+      Some(Type::Function(vec![Type::I32], Box::new(Type::Bool)))
+    );
+
+    // type parameters aren't types of their own, and unknown ids have nothing
+    assert_eq!(typechecker.type_of_item(id(&["Pair", "T"])), None);
+    assert_eq!(typechecker.type_of_item(Uuid::new_v4()), None);
+  }
+
+  #[test]
+  fn test_record() {
+    let record = |fields: &[(&str, Type)]| {
+      Type::Record(
+        fields
+          .iter()
+          .map(|(name, ty)| (name.to_string(), ty.clone()))
+          .collect(),
+      )
+    };
+
+    assert_eq!(
+      typecheck_expression("{ x: 1, y: 'a' }"),
+      Ok(record(&[("x", Type::I32), ("y", Type::Char)]))
+    );
+
+    // fields are matched up by name, whatever order they're written in
+    assert_eq!(
+      typecheck("fn main(): { x: i32, y: char } { y: 'a', x: 1 };"),
+      Ok(())
+    );
+    assert_eq!(
+      typecheck(
+        "
+          fn f(p: { x: i32, y: i32 }): i32 p.x + p.y;
+          fn main(): i32 f({ y: 2, x: 1 });
+        "
+      ),
+      Ok(())
+    );
+
+    // literals in the fields take the types of the expected ones
+    assert_eq!(typecheck("fn f(): { x: u8 } { x: 1 };"), Ok(()));
+    assert_eq!(
+      typecheck("fn f(): { x: u8 } { x: 256 };"),
+      Err(vec![TypecheckerError::LiteralOutOfRange {
+        value: 256,
+        ty: Type::U8
+      }])
+    );
+
+    assert_eq!(
+      typecheck("fn main(): { x: i32, y: char } { x: 1 };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: record(&[("x", Type::I32), ("y", Type::Char)]),
+        found: record(&[("x", Type::I32)]),
+      }])
+    );
+    assert_eq!(
+      typecheck("fn main(): { x: i32 } { x: 1, y: 'a' };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: record(&[("x", Type::I32)]),
+        found: record(&[("x", Type::I32), ("y", Type::Char)]),
+      }])
+    );
+    assert_eq!(
+      typecheck("fn main(): { x: i32 } { x: 'a' };"),
+      Err(vec![TypecheckerError::InvalidType {
+        expected: record(&[("x", Type::I32)]),
+        found: record(&[("x", Type::Char)]),
+      }])
+    );
+
+    assert_eq!(
+      typecheck_expression("{ x: 1 }.y"),
+      Err(TypecheckerError::UnknownField {
+        struct_name: "{ x: i32 }".to_string(),
+        field: "y".to_string(),
+      })
+    );
+    assert_eq!(
+      typecheck_expression("{ x: 1, x: 2 }"),
+      Err(TypecheckerError::DuplicateField {
+        struct_name: "{ x: i32, x: i32 }".to_string(),
+        field: "x".to_string(),
+      })
+    );
+  }
+
+  #[test]
+  fn test_parameter_types() {
+    assert_eq!(typecheck("struct P {}; fn f(p: P): P p;"), Ok(()));
+
+    let unresolved = |name: &str| {
+      Err(vec![TypecheckerError::UnresolvedType {
+        name: name.to_string(),
+      }])
+    };
+
+    assert_eq!(typecheck("fn f(p: P): () ();"), unresolved("P"));
+    assert_eq!(typecheck("fn f(): P f();"), unresolved("P"));
+    // types are checked wherever they're nested
+    assert_eq!(
+      typecheck("struct P {}; fn f(p: [fn(P): a::Q]): () ();"),
+      unresolved("a::Q")
+    );
+    assert_eq!(typecheck("fn f(p: { x: &Q }): () ();"), unresolved("Q"));
+  }
+
+  #[test]
+  fn test_type_references() {
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str(
+        "
+          struct P<T> { pub struct Q {}; fn get(t: T): T t; };
+          enum E { A };
+          fn f(p: P<E>, q: P::Q): E E::A;
+        "
+      ),
+      Ok(())
+    );
+
+    let path = |path: &str| path.split("::").map(str::to_string).collect::<Vec<_>>();
+    let declared = |name: &str| typechecker.declarations[&path(name)];
+    let resolved = |name: &str| {
+      typechecker
+        .type_references
+        .iter()
+        .filter(|(written, _)| *written == path(name))
+        .map(|(_, id)| *id)
+        .collect::<HashSet<_>>()
+    };
+
+    assert_eq!(resolved("P"), HashSet::from([declared("P")]));
+    assert_eq!(resolved("P::Q"), HashSet::from([declared("P::Q")]));
+    assert_eq!(resolved("E"), HashSet::from([declared("E")]));
+    assert_eq!(resolved("T"), HashSet::from([declared("P::T")]));
+
+    // a nested type can be referred to before its struct is checked
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str(
+        "
+          fn f(q: P::Q::R): () ();
+          struct P { pub struct Q { pub struct R {}; }; };
+        "
+      ),
+      Ok(())
+    );
+    assert_eq!(
+      typechecker
+        .type_references
+        .iter()
+        .filter(|(written, _)| *written == path("P::Q::R"))
+        .map(|(_, id)| *id)
+        .collect::<HashSet<_>>(),
+      HashSet::from([typechecker.declarations[&path("P::Q::R")]])
+    );
+
+    let mut typechecker = Typechecker::new();
+    assert_eq!(
+      typechecker.typecheck_str("fn f(r: R): () ();"),
+      Err(CompileError::Typechecker(vec![Error(
+        0..18,
+        TypecheckerError::UnresolvedType {
+          name: "R".to_string()
+        }
+      )]))
+    );
+    assert!(typechecker.type_references.is_empty());
+  }
 }
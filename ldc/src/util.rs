@@ -1,12 +1,7 @@
 #[macro_export]
 macro_rules! union {
   ($a:expr, $($b:expr),*) => {
-    {
-      let mut set = std::collections::BTreeSet::new();
-      set.insert($a);
-      $(set.insert($b);)*
-      crate::parser::ast::util::Type::Union(set)
-    }
+    $crate::parser::ast::util::Type::union([$a, $($b),*])
   };
 }
 
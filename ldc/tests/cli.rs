@@ -0,0 +1,128 @@
+use std::process::{Command, Output};
+
+fn run(fixture: &str) -> (Output, String) {
+  let output = Command::new(env!("CARGO_BIN_EXE_ldc"))
+    .arg(format!(
+      "{}/tests/fixtures/{}",
+      env!("CARGO_MANIFEST_DIR"),
+      fixture
+    ))
+    .output()
+    .unwrap();
+
+  // drop ansi color codes
+  let mut stdout = String::new();
+  let mut rest = String::from_utf8_lossy(&output.stdout).into_owned();
+  while let Some(start) = rest.find('\x1b') {
+    stdout.push_str(&rest[..start]);
+    let end = rest[start..].find('m').unwrap();
+    rest = rest[start + end + 1..].to_string();
+  }
+  stdout.push_str(&rest);
+
+  (output, stdout)
+}
+
+#[test]
+fn test_ok() {
+  let (output, stdout) = run("ok.ldc");
+
+  assert!(output.status.success());
+  assert!(stdout.contains("Typechecked successfully"));
+}
+
+#[test]
+fn test_tuple_return() {
+  let (output, stdout) = run("tuple_return.ldc");
+
+  assert!(output.status.success());
+  assert!(stdout.contains("Typechecked successfully"));
+  assert!(!stdout.contains("Warning"));
+}
+
+#[test]
+fn test_parse_error() {
+  let (output, stdout) = run("parse_error.ldc");
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stdout.contains("parse_error.ldc:2:15"));
+
+  let lines = stdout.lines().collect::<Vec<_>>();
+  let source = lines
+    .iter()
+    .position(|line| line.contains("let x = 1 + ;"))
+    .unwrap();
+
+  // the caret under the source line points at the `;`
+  assert_eq!(
+    lines[source + 1].chars().position(|c| c == '^'),
+    lines[source].chars().position(|c| c == ';')
+  );
+}
+
+#[test]
+fn test_lex_error() {
+  let (output, stdout) = run("lex_error.ldc");
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stdout.contains("lex_error.ldc:2:17"));
+
+  let lines = stdout.lines().collect::<Vec<_>>();
+  let source = lines
+    .iter()
+    .position(|line| line.contains("let s = \"é→\"; $"))
+    .unwrap();
+
+  // columns count characters, so the multi-byte ones before the `$` don't push the caret along
+  assert_eq!(
+    lines[source + 1].chars().position(|c| c == '^'),
+    lines[source].chars().position(|c| c == '$')
+  );
+}
+
+#[test]
+fn test_type_error() {
+  let (output, stdout) = run("type_error.ldc");
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stdout.contains("type_error.ldc:2:17"));
+  assert!(stdout.contains("Type i32 does not satisfy expected type bool"));
+
+  let lines = stdout.lines().collect::<Vec<_>>();
+  let source = lines
+    .iter()
+    .position(|line| line.contains("let x: bool = 12;"))
+    .unwrap();
+
+  // the carets under the source line point at the value
+  assert_eq!(
+    lines[source + 1].chars().position(|c| c == '^'),
+    lines[source].chars().position(|c| c == '1')
+  );
+  assert_eq!(lines[source + 1].matches('^').count(), 2);
+}
+
+#[test]
+fn test_unreachable_warning() {
+  let (output, stdout) = run("unreachable.ldc");
+
+  assert!(output.status.success());
+  assert!(stdout.contains("Warning: Unreachable code"));
+  assert!(stdout.contains("unreachable.ldc:3:3"));
+
+  let lines = stdout.lines().collect::<Vec<_>>();
+  let source = lines.iter().position(|line| *line == "3 |   2").unwrap();
+
+  // the caret points at the expression after the return
+  assert_eq!(
+    lines[source + 1].chars().position(|c| c == '^'),
+    lines[source].chars().position(|c| c == '2')
+  );
+}
+
+#[test]
+fn test_missing_file() {
+  let (output, _) = run("missing.ldc");
+
+  assert_eq!(output.status.code(), Some(1));
+}